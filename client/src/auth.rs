@@ -0,0 +1,161 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Event, HtmlInputElement};
+use serde_json::json;
+use core::http_models::http_responses::HttpSendResponse;
+use crate::client_request::{forget_csrf_token, post_json};
+use crate::client_tools::log;
+use crate::modal::Modal;
+
+/// Connexion par mot de passe puis, si le compte l'exige, par code TOTP saisi dans la modale
+///
+/// Retourne `true` une fois la session ouverte (cookie posé par le serveur).
+pub async fn login(modal: &Modal, login: &str, password: &str) -> Result<bool, JsValue> {
+    let response = post_json("/api/auth/login", &json!({ "login": login, "password": password })).await?;
+    if response.status != 202 {
+        if response.is_success() {
+            forget_csrf_token();
+        }
+        modal.show(&response.get_message())?;
+        return Ok(response.is_success());
+    }
+
+    // 202 : mot de passe accepté, second facteur attendu
+    let Some(challenge) = response.data.as_ref().and_then(|data| data["challenge"].as_str()).map(str::to_string) else {
+        modal.show("❌ Réponse du serveur invalide")?;
+        return Ok(false);
+    };
+    second_factor(modal, &challenge).await
+}
+
+/// Code TOTP ou de secours pour la connexion en attente `challenge`, redemandé tant qu'il est refusé
+pub async fn second_factor(modal: &Modal, challenge: &str) -> Result<bool, JsValue> {
+    let mut message = "🔐 Saisissez le code de votre application d'authentification, ou un code de secours".to_string();
+    loop {
+        let Some(code) = modal.prompt_code(&message, "123456").await? else {
+            return Ok(false);
+        };
+        let response = post_json("/api/auth/login/totp", &json!({ "challenge": challenge, "code": code })).await?;
+        match response.status {
+            // Code refusé : on peut réessayer tant que la connexion en attente est valide
+            422 => message = format!("❌ {}<br>Nouvel essai :", response.get_message()),
+            _ => {
+                if response.is_success() {
+                    forget_csrf_token();
+                }
+                modal.show(&response.get_message())?;
+                return Ok(response.is_success());
+            }
+        }
+    }
+}
+
+/// Enrôlement du second facteur : QR code à scanner, confirmation par un premier code,
+/// puis affichage des codes de secours
+pub async fn enroll_totp(modal: &Modal) -> Result<bool, JsValue> {
+    let response = post_json("/api/auth/totp/enroll", &json!({})).await?;
+    if !response.is_success() {
+        modal.show(&response.get_message())?;
+        return Ok(false);
+    }
+    let qr_svg = data_str(&response, "qr_svg");
+    let secret = data_str(&response, "secret");
+
+    let mut message = format!(
+        "<div class=\"totp-qr\">{}</div><p>Clé : <code>{}</code></p><p>Saisissez le code affiché pour confirmer :</p>",
+        qr_svg, secret
+    );
+    loop {
+        let Some(code) = modal.prompt_code(&message, "123456").await? else {
+            return Ok(false);
+        };
+        let response = post_json("/api/auth/totp/confirm", &json!({ "code": code })).await?;
+        if response.status == 422 {
+            message = format!("<div class=\"totp-qr\">{}</div><p>❌ {}</p>", qr_svg, response.get_message());
+            continue;
+        }
+        if !response.is_success() {
+            modal.show(&response.get_message())?;
+            return Ok(false);
+        }
+        let codes: Vec<String> = response.data.as_ref()
+            .and_then(|data| serde_json::from_value(data["recovery_codes"].clone()).ok())
+            .unwrap_or_default();
+        modal.show(&format!(
+            "✅ Double authentification activée<p>Conservez ces codes de secours, ils ne seront plus affichés :</p><pre>{}</pre>",
+            codes.join("\n")
+        ))?;
+        return Ok(true);
+    }
+}
+
+fn data_str(response: &HttpSendResponse, key: &str) -> String {
+    response.data.as_ref().and_then(|data| data[key].as_str()).unwrap_or_default().to_string()
+}
+
+fn input_value(document: &web_sys::Document, id: &str) -> String {
+    document.get_element_by_id(id)
+        .and_then(|element| element.dyn_into::<HtmlInputElement>().ok())
+        .map(|input| input.value())
+        .unwrap_or_default()
+}
+
+/// Jeton laissé dans l'ancre par le retour OIDC d'un compte à second facteur, retiré de l'adresse
+fn take_oidc_challenge() -> Option<String> {
+    let window = window()?;
+    let location = window.location();
+    let challenge = location.hash().ok()?.strip_prefix("#totp_challenge=")?.to_string();
+    let path = location.pathname().ok()?;
+    let _ = window.history().ok()?.replace_state_with_url(&JsValue::NULL, "", Some(&path));
+    Some(challenge)
+}
+
+/// Branche le formulaire `#login_form` (champs `#auth_login` et `#auth_password`)
+/// et le bouton `#button_totp` s'ils sont présents dans la page ; termine une connexion
+/// OIDC qui attend le second facteur
+pub fn auth_init() -> Result<(), JsValue> {
+    let document = window().unwrap().document().unwrap();
+    let modal = Modal::new()?;
+
+    if let Some(challenge) = take_oidc_challenge() {
+        let modal = modal.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = second_factor(&modal, &challenge).await {
+                log(&format!("❌ Erreur lors de la connexion: {:?}", e));
+            }
+        });
+    }
+
+    if let Some(form) = document.get_element_by_id("login_form") {
+        let modal = modal.clone();
+        let document = document.clone();
+        let on_submit = Closure::wrap(Box::new(move |e: Event| {
+            e.prevent_default();
+            let modal = modal.clone();
+            let login_value = input_value(&document, "auth_login");
+            let password = input_value(&document, "auth_password");
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = login(&modal, &login_value, &password).await {
+                    log(&format!("❌ Erreur lors de la connexion: {:?}", e));
+                }
+            });
+        }) as Box<dyn FnMut(Event)>);
+        form.add_event_listener_with_callback("submit", on_submit.as_ref().unchecked_ref())?;
+        on_submit.forget();
+    }
+
+    if let Some(button) = document.get_element_by_id("button_totp") {
+        let on_click = Closure::wrap(Box::new(move |e: Event| {
+            e.prevent_default();
+            let modal = modal.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = enroll_totp(&modal).await {
+                    log(&format!("❌ Erreur lors de l'activation du second facteur: {:?}", e));
+                }
+            });
+        }) as Box<dyn FnMut(Event)>);
+        button.add_event_listener_with_callback("click", on_click.as_ref().unchecked_ref())?;
+        on_click.forget();
+    }
+    Ok(())
+}
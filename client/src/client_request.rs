@@ -2,8 +2,70 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, RequestInit, RequestMode, window};
 use serde::Deserialize;
+use std::cell::RefCell;
 use core::http_models::http_responses::HttpSendResponse;
 
+thread_local! {
+    /// CSRF header name and token from `/api/csrf`, fetched on the first submission
+    static CSRF_TOKEN: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+/// CSRF header to send with state-changing requests, fetched again when `refresh` is set
+async fn csrf_header(refresh: bool) -> Result<(String, String), JsValue> {
+    if !refresh {
+        if let Some(cached) = CSRF_TOKEN.with(|token| token.borrow().clone()) {
+            return Ok(cached);
+        }
+    }
+    let response: HttpSendResponse = fetch_json("/api/csrf").await?;
+    let data = response.data.unwrap_or_default();
+    let header = data["header"].as_str().unwrap_or("X-CSRF-Token").to_string();
+    let token = data["token"].as_str().ok_or_else(|| JsValue::from_str("CSRF token unavailable"))?.to_string();
+    CSRF_TOKEN.with(|cached| *cached.borrow_mut() = Some((header.clone(), token.clone())));
+    Ok((header, token))
+}
+
+/// Drops the cached CSRF token: the server replaces it when a session is opened or closed
+pub fn forget_csrf_token() {
+    CSRF_TOKEN.with(|cached| *cached.borrow_mut() = None);
+}
+
+/// Fetches the CSRF token in the background, so the first submission does not wait for it
+pub fn prefetch_csrf_token() {
+    wasm_bindgen_futures::spawn_local(async {
+        if let Err(e) = csrf_header(false).await {
+            crate::client_tools::log(&format!("❌ CSRF token unavailable: {:?}", e));
+        }
+    });
+}
+
+/// POST `body` with the CSRF token; a 403 (token expired, server restarted) is retried
+/// once with a fresh token
+async fn post_with_csrf(endpoint: &str, body: &JsValue, content_type: Option<&str>) -> Result<web_sys::Response, JsValue> {
+    let window = window().unwrap();
+    let mut refresh = false;
+    loop {
+        let (header, token) = csrf_header(refresh).await?;
+        let opts = RequestInit::new();
+        opts.set_method("POST");
+        opts.set_mode(RequestMode::SameOrigin);
+        opts.set_body(body);
+
+        let request = Request::new_with_str_and_init(endpoint, &opts)?;
+        request.headers().set("Accept", "application/json")?;
+        if let Some(content_type) = content_type {
+            request.headers().set("Content-Type", content_type)?;
+        }
+        request.headers().set(&header, &token)?;
+
+        let response: web_sys::Response = JsFuture::from(window.fetch_with_request(&request)).await?.dyn_into()?;
+        if response.status() != 403 || refresh {
+            return Ok(response);
+        }
+        refresh = true;
+    }
+}
+
 /// Fetch JSON data from a URL
 pub async fn fetch_json<T>(url: &str) -> Result<T, JsValue> 
 where T: for<'a> Deserialize<'a> {
@@ -25,21 +87,14 @@ where T: for<'a> Deserialize<'a> {
 }
 
 /// Submit form data via POST
+///
+/// The CSRF token is added automatically; the browser sets the multipart Content-Type.
 pub async fn post_form(endpoint: &str, form_data: &web_sys::FormData) -> Result<HttpSendResponse, JsValue> {
-    let opts = web_sys::RequestInit::new();
-    opts.set_method("POST");
-    opts.set_mode(RequestMode::Cors);
-    
     let form_data_js: JsValue = form_data.clone().into();
-    opts.set_body(&form_data_js);
-
-    let request = Request::new_with_str_and_init(endpoint, &opts)?;
-    
-    let window = web_sys::window().unwrap();
-    let response = JsFuture::from(window.fetch_with_request(&request)).await?;
-    let response: web_sys::Response = response.dyn_into()?;
+    let response = post_with_csrf(endpoint, &form_data_js, None).await?;
     
-    if !response.ok() {
+    // 429 : l'enveloppe porte le délai d'attente (`retry_after`), à montrer tel quel
+    if !response.ok() && response.status() != 429 {
         return Err(JsValue::from_str(&format!("HTTP error! status: {}", response.status())));
     }
     
@@ -48,6 +103,18 @@ pub async fn post_form(endpoint: &str, form_data: &web_sys::FormData) -> Result<
     Ok(response_data)
 }
 
+/// Send a JSON body via POST and return the response envelope, whatever its status
+///
+/// Unlike `post_form`, non-2xx statuses are not turned into errors: the caller
+/// needs them (202 for a pending second factor, 422 for a rejected code...).
+/// The CSRF token is added automatically.
+pub async fn post_json(endpoint: &str, body: &serde_json::Value) -> Result<HttpSendResponse, JsValue> {
+    let response = post_with_csrf(endpoint, &JsValue::from_str(&body.to_string()), Some("application/json")).await?;
+
+    let json = JsFuture::from(response.json()?).await?;
+    Ok(serde_wasm_bindgen::from_value(json)?)
+}
+
 /// Fetch text/HTML data from a URL
 pub async fn fetch_text(url: &str) -> Result<String, JsValue> {
     let window = window().unwrap();
@@ -243,6 +243,10 @@ impl FormHandler {
                     }
                     return Ok(());
                 }
+                // Débit dépassé : réessayer tout de suite ne ferait que prolonger l'attente
+                Ok(response) if response.status == 429 => {
+                    self.modal.show(&format!("⨯ {}", response.get_message()))?;
+                }
                 Ok(response) => {
                     let error_msg = format!("⨯ Server Error: {}", response.get_message());
                     
@@ -1,5 +1,6 @@
 mod client_tools;
 mod client_request;
+pub mod auth;
 pub mod form;
 pub mod modal;
 pub mod refresh;
@@ -19,7 +20,11 @@ use refresh::config::DataTransform;
 // Fonction d'initialisation du script
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
-    log("# Begin script - Enhanced Form System");    // Initialiser les rafraîchissements automatiques
+    log("# Begin script - Enhanced Form System");
+    // Jeton CSRF ajouté par client_request à chaque envoi de formulaire
+    client_request::prefetch_csrf_token();
+
+    // Initialiser les rafraîchissements automatiques
     log("# Initializing auto-refresh system");
     wasm_bindgen_futures::spawn_local(init_auto_refresh());
     
@@ -143,6 +148,14 @@ pub fn run() -> Result<(), JsValue> {
         }
     }       
     
+    match auth::auth_init() {
+        Ok(_) => log("✅ Connexion et double authentification initialisées"),
+        Err(e) => {
+            log(&format!("❌ Erreur lors de l'initialisation de la connexion: {:?}", e));
+            return Err(e);
+        }
+    }
+
     log("# End script - Enhanced Form System with Auto-Refresh Ready");
     Ok(())
 }
@@ -195,7 +208,7 @@ async fn init_auto_refresh() {
         15,  // Toutes les 15 secondes
         "#form-data-table",
         None,  // Pas de champ JSON, on prend tout le HTML
-    );// Démarrer tous les rafraîchissements
+    ).with_navigation();  // Conserver page et tri entre deux rafraîchissements// Démarrer tous les rafraîchissements
 
     RefreshScheduler::new()
         .add_refresh(temperature_config)
@@ -1,5 +1,8 @@
-use web_sys::{window, Element, Event};
+use web_sys::{window, Element, Event, HtmlInputElement};
 use wasm_bindgen::prelude::*;
+use futures::channel::oneshot;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// Modal structure representing a popup dialog
 /// Contains the main element, content container and message area
@@ -83,4 +86,63 @@ impl Modal {
         self.element.set_class_name("modal");
         Ok(())
     }
+
+    /// Shows a message followed by a code input and waits for the user's answer
+    ///
+    /// Resolves to `None` if the modal is closed (close button or outside click)
+    /// without submitting. `message` is HTML, like in `show`.
+    pub async fn prompt_code(&self, message: &str, placeholder: &str) -> Result<Option<String>, JsValue> {
+        let document = window().unwrap().document().unwrap();
+
+        let form = document.create_element("form")?;
+        form.set_class_name("modal-prompt");
+        let input: HtmlInputElement = document.create_element("input")?.dyn_into()?;
+        input.set_type("text");
+        input.set_placeholder(placeholder);
+        input.set_autocomplete("one-time-code");
+        input.set_attribute("inputmode", "numeric")?;
+        let submit = document.create_element("button")?;
+        submit.set_attribute("type", "submit")?;
+        submit.set_inner_html("Valider");
+        form.append_child(&input)?;
+        form.append_child(&submit)?;
+
+        self.show(message)?;
+        self.message_container.append_child(&form)?;
+        input.focus()?;
+
+        let (sender, receiver) = oneshot::channel::<Option<String>>();
+        let sender = Rc::new(RefCell::new(Some(sender)));
+
+        let on_submit = {
+            let sender = sender.clone();
+            let input = input.clone();
+            Closure::wrap(Box::new(move |e: Event| {
+                e.prevent_default();
+                if let Some(sender) = sender.borrow_mut().take() {
+                    let _ = sender.send(Some(input.value()));
+                }
+            }) as Box<dyn FnMut(Event)>)
+        };
+        // Fermer la fenêtre retire la classe "show" : on répond alors `None`
+        let on_close = {
+            let sender = sender.clone();
+            let element = self.element.clone();
+            Closure::wrap(Box::new(move |_: Event| {
+                if element.class_name() != "modal show" {
+                    if let Some(sender) = sender.borrow_mut().take() {
+                        let _ = sender.send(None);
+                    }
+                }
+            }) as Box<dyn FnMut(Event)>)
+        };
+        form.add_event_listener_with_callback("submit", on_submit.as_ref().unchecked_ref())?;
+        self.element.add_event_listener_with_callback("click", on_close.as_ref().unchecked_ref())?;
+
+        let answer = receiver.await.unwrap_or(None);
+
+        self.element.remove_event_listener_with_callback("click", on_close.as_ref().unchecked_ref())?;
+        form.remove();
+        Ok(answer.map(|code| code.trim().to_string()).filter(|code| !code.is_empty()))
+    }
 }
@@ -19,6 +19,9 @@ pub struct RefreshConfig {
     pub show_errors: bool,    /// Sélecteurs de champs input dont les valeurs seront utilisées comme paramètres
     /// Format: (nom_parametre, selecteur_css)
     pub input_field_selectors: Vec<(String, String)>,
+    /// Suivre les liens `data-query` de la cible (pagination, tri) et conserver
+    /// leur requête pour les rafraîchissements suivants
+    pub navigation: bool,
 }
 
 /// Type de contenu à insérer dans l'élément
@@ -62,6 +65,7 @@ impl RefreshConfig {    /// Constructeur simple pour un rafraîchissement de tex
             transform: None,
             show_errors: true,
             input_field_selectors: Vec::new(),
+            navigation: false,
         }
     }/// Constructeur pour un rafraîchissement HTML
     pub fn new_html(
@@ -80,6 +84,7 @@ impl RefreshConfig {    /// Constructeur simple pour un rafraîchissement de tex
             transform: None,
             show_errors: true,
             input_field_selectors: Vec::new(),
+            navigation: false,
         }
     }/// Ajouter une transformation
     pub fn with_transform(mut self, transform: DataTransform) -> Self {
@@ -91,6 +96,12 @@ impl RefreshConfig {    /// Constructeur simple pour un rafraîchissement de tex
         self
     }
 
+    /// Activer la navigation par liens `data-query` (pagination, tri)
+    pub fn with_navigation(mut self) -> Self {
+        self.navigation = true;
+        self
+    }
+
     /// Désactiver l'affichage des erreurs
     pub fn without_errors(mut self) -> Self {
         self.show_errors = false;
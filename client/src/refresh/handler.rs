@@ -1,35 +1,76 @@
 use crate::refresh::config::{RefreshConfig, ContentType};
 use crate::client_request::{fetch_json, fetch_text};
 use crate::client_tools::log;
-use web_sys::{HtmlElement, HtmlInputElement};
+use web_sys::{Element, Event, HtmlElement, HtmlInputElement};
+use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
 use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// Gestionnaire pour les rafraîchissements automatiques
 pub struct RefreshHandler {
     pub config: RefreshConfig,
+    /// Requête du dernier lien de navigation suivi (page, tri, filtres)
+    navigation_query: RefCell<Option<String>>,
 }
 
 impl RefreshHandler {
     /// Créer un nouveau gestionnaire
     pub fn new(config: RefreshConfig) -> Self {
-        Self { config }
+        Self { config, navigation_query: RefCell::new(None) }
+    }
+
+    /// Intercepter les clics sur les liens `data-query` de la cible
+    ///
+    /// L'écouteur est posé sur la cible elle-même, qui survit aux remplacements
+    /// de son contenu ; la requête du lien est conservée puis la cible rafraîchie.
+    pub fn attach_navigation(handler: &Rc<RefreshHandler>) -> Result<(), String> {
+        let window = web_sys::window().ok_or("No window")?;
+        let document = window.document().ok_or("No document")?;
+
+        let element = document
+            .query_selector(&handler.config.target_selector)
+            .map_err(|_| "Query selector failed")?
+            .ok_or_else(|| format!("Element not found: {}", handler.config.target_selector))?;
+
+        let handler_ref = Rc::clone(handler);
+        let closure = Closure::wrap(Box::new(move |e: Event| {
+            let link = e.target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|element| element.closest("[data-query]").ok().flatten());
+
+            if let Some(query) = link.and_then(|link| link.get_attribute("data-query")) {
+                e.prevent_default();
+                *handler_ref.navigation_query.borrow_mut() = Some(query);
+
+                let handler = Rc::clone(&handler_ref);
+                spawn_local(async move {
+                    handler.execute_refresh().await;
+                });
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        element
+            .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+            .map_err(|_| "Failed to attach navigation listener")?;
+        closure.forget();
+
+        Ok(())
     }
 
     /// Exécuter un rafraîchissement
     pub async fn execute_refresh(&self) {
         log(&format!("🔄 Refreshing: {}", self.config.id));
 
-        // Construire l'URL avec les paramètres si des champs input sont configurés
-        let url = if !self.config.input_field_selectors.is_empty() {
-            match self.build_url_with_params() {
-                Ok(url) => url,
-                Err(e) => {
-                    log(&format!("⚠️ Failed to build URL with params: {}", e));
-                    self.config.endpoint.clone()
-                }
-            }        } else {
-            self.config.endpoint.clone()
+        // Construire l'URL avec les paramètres des champs input et de la navigation
+        let url = match self.build_url_with_params() {
+            Ok(url) => url,
+            Err(e) => {
+                log(&format!("⚠️ Failed to build URL with params: {}", e));
+                self.config.endpoint.clone()
+            }
         };
 
         // Utiliser client_request pour faire l'appel API
@@ -72,9 +113,14 @@ impl RefreshHandler {
         }
     }
 
-    /// Construire l'URL avec tous les paramètres des champs input
+    /// Construire l'URL avec tous les paramètres des champs input et la requête de navigation
     fn build_url_with_params(&self) -> Result<String, String> {
         let mut params = Vec::new();
+
+        // Déjà encodée par le serveur
+        if let Some(query) = self.navigation_query.borrow().as_ref() {
+            params.push(query.clone());
+        }
         
         for (param_name, selector) in &self.config.input_field_selectors {
             match self.get_input_value(selector) {
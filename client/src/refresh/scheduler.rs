@@ -2,10 +2,11 @@ use crate::refresh::{RefreshConfig, RefreshHandler};
 use crate::client_tools::log;
 use wasm_bindgen_futures::spawn_local;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 /// Planificateur pour gérer plusieurs rafraîchissements automatiques
 pub struct RefreshScheduler {
-    handlers: HashMap<String, RefreshHandler>,
+    handlers: HashMap<String, Rc<RefreshHandler>>,
 }
 
 impl RefreshScheduler {
@@ -18,7 +19,7 @@ impl RefreshScheduler {
 
     /// Ajouter une configuration de rafraîchissement
     pub fn add_refresh(mut self, config: RefreshConfig) -> Self {
-        let handler = RefreshHandler::new(config.clone());
+        let handler = Rc::new(RefreshHandler::new(config.clone()));
         self.handlers.insert(config.id.clone(), handler);
         self
     }
@@ -30,6 +31,12 @@ impl RefreshScheduler {
         for (id, handler) in self.handlers {
             let config = handler.config.clone();
             log(&format!("⏰ Scheduling refresh '{}' every {} seconds", id, config.interval_seconds));
+
+            if config.navigation {
+                if let Err(e) = RefreshHandler::attach_navigation(&handler) {
+                    log(&format!("⚠️ Navigation disabled for {}: {}", id, e));
+                }
+            }
             
             spawn_local(async move {
                 // Exécuter immédiatement
@@ -42,7 +49,7 @@ impl RefreshScheduler {
     }
 
     /// Planifier un rafraîchissement périodique
-    async fn schedule_refresh(handler: RefreshHandler) {
+    async fn schedule_refresh(handler: Rc<RefreshHandler>) {
         let interval_ms = handler.config.interval_seconds * 1000;
         
         loop {
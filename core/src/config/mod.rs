@@ -7,6 +7,15 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
     pub upload: UploadConfig,
+    pub storage: StorageConfig,
+    /// `None` tant que `LINK_SECRET` n'est pas configuré
+    pub links: Option<SignedLinkConfig>,
+    pub session: SessionConfig,
+    pub csrf: CsrfConfig,
+    pub rate_limit: RateLimitConfig,
+    pub totp: TotpConfig,
+    /// `None` tant que `OIDC_ISSUER` n'est pas configuré
+    pub oidc: Option<OidcConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +42,322 @@ pub struct UploadConfig {
     pub max_file_size: u64,
     pub allowed_extensions: Vec<String>,
     pub upload_dir: String,
+    pub image: ImageConfig,
+}
+
+/// Traitement des images uploadées
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageConfig {
+    /// Dimensions maximales acceptées, en pixels
+    pub max_width: u32,
+    pub max_height: u32,
+    /// Côté des miniatures carrées, en pixels
+    pub thumbnail_size: u32,
+}
+
+impl ImageConfig {
+    /// `IMAGE_MAX_WIDTH` / `IMAGE_MAX_HEIGHT` (8192 par défaut) et `THUMBNAIL_SIZE` (256)
+    pub fn from_env() -> Self {
+        let pixels = |name: &str, default: u32| env::var(name).ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v: &u32| v > 0)
+            .unwrap_or(default);
+        ImageConfig {
+            max_width: pixels("IMAGE_MAX_WIDTH", 8192),
+            max_height: pixels("IMAGE_MAX_HEIGHT", 8192),
+            thumbnail_size: pixels("THUMBNAIL_SIZE", 256),
+        }
+    }
+}
+
+impl UploadConfig {
+    /// Limites d'upload lues dans l'environnement, valeurs par défaut sinon
+    pub fn from_env() -> Self {
+        UploadConfig {
+            max_file_size: env::var("MAX_FILE_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(10 * 1024 * 1024), // 10MB
+            allowed_extensions: env::var("ALLOWED_EXTENSIONS")
+                .unwrap_or_else(|_| "jpg,jpeg,png,pdf,txt".to_string())
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            upload_dir: env::var("UPLOAD_DIR").unwrap_or_else(|_| "storage/files".to_string()),
+            image: ImageConfig::from_env(),
+        }
+    }
+
+    /// Extension autorisée (insensible à la casse) ; une liste vide autorise tout
+    pub fn allows_extension(&self, extension: &str) -> bool {
+        self.allowed_extensions.is_empty()
+            || self.allowed_extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(extension))
+    }
+}
+
+/// Backend de stockage des fichiers uploadés
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Disque local, sous `UploadConfig::upload_dir`
+    Local,
+    /// En mémoire, perdu à l'arrêt (tests)
+    Memory,
+    /// Stockage objet compatible S3 (AWS, MinIO...)
+    S3,
+}
+
+/// Accès à un stockage objet compatible S3
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    /// URL du service, par exemple `http://127.0.0.1:9000` pour MinIO
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    #[serde(skip_serializing)]
+    pub secret_key: String,
+    /// `endpoint/bucket/clé` (MinIO) plutôt que `bucket.endpoint/clé` (AWS)
+    pub path_style: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Renseigné quand `backend` vaut `S3`
+    pub s3: Option<S3Config>,
+}
+
+impl StorageConfig {
+    /// `STORAGE_BACKEND` = `local` (défaut), `memory` ou `s3` ; en S3, `S3_BUCKET`,
+    /// `S3_ACCESS_KEY` et `S3_SECRET_KEY` sont obligatoires
+    pub fn from_env() -> Result<Self, String> {
+        let backend = match env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string()).trim().to_lowercase().as_str() {
+            "local" => StorageBackend::Local,
+            "memory" => StorageBackend::Memory,
+            "s3" => StorageBackend::S3,
+            other => return Err(format!("Unknown STORAGE_BACKEND '{}' (expected local, memory or s3)", other)),
+        };
+
+        let s3 = if backend == StorageBackend::S3 {
+            let required = |name: &str| env::var(name).map_err(|_| format!("{} must be set when STORAGE_BACKEND=s3", name));
+            Some(S3Config {
+                endpoint: env::var("S3_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:9000".to_string()),
+                bucket: required("S3_BUCKET")?,
+                region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key: required("S3_ACCESS_KEY")?,
+                secret_key: required("S3_SECRET_KEY")?,
+                path_style: env::var("S3_PATH_STYLE").map(|v| v != "false").unwrap_or(true),
+            })
+        } else {
+            None
+        };
+
+        Ok(StorageConfig { backend, s3 })
+    }
+}
+
+/// Liens de téléchargement signés et temporaires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedLinkConfig {
+    /// Clé HMAC partagée par le serveur et la commande `link`
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Durée de validité par défaut, en secondes
+    pub default_ttl: u64,
+    /// Durée de validité maximale acceptée, en secondes
+    pub max_ttl: u64,
+}
+
+impl SignedLinkConfig {
+    /// Longueur minimale de `LINK_SECRET`, en octets
+    pub const MIN_SECRET_LEN: usize = 32;
+
+    /// `LINK_SECRET` obligatoire (32 caractères au moins) ; `LINK_DEFAULT_TTL` (1 heure par défaut)
+    /// et `LINK_MAX_TTL` (7 jours) en secondes
+    pub fn from_env() -> Result<Self, String> {
+        let secret = env::var("LINK_SECRET").map_err(|_| "LINK_SECRET is not set".to_string())?;
+        if secret.len() < Self::MIN_SECRET_LEN {
+            return Err(format!("LINK_SECRET must be at least {} characters long", Self::MIN_SECRET_LEN));
+        }
+        let seconds = |name: &str, default: u64| env::var(name).ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v: &u64| v > 0)
+            .unwrap_or(default);
+        let max_ttl = seconds("LINK_MAX_TTL", 7 * 24 * 3600);
+        Ok(SignedLinkConfig {
+            secret,
+            default_ttl: seconds("LINK_DEFAULT_TTL", 3600).min(max_ttl),
+            max_ttl,
+        })
+    }
+}
+
+/// Sessions ouvertes par `POST /api/auth/login`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Nom du cookie portant le jeton de session
+    pub cookie_name: String,
+    /// Durée de vie d'une session, en secondes
+    pub ttl: u64,
+    /// Cookie réservé à HTTPS (`Secure`)
+    pub secure: bool,
+}
+
+impl SessionConfig {
+    /// `SESSION_COOKIE` (`session` par défaut), `SESSION_TTL` en secondes (7 jours) et
+    /// `SESSION_COOKIE_SECURE`, qui suit `SSL_ENABLED` s'il n'est pas défini
+    pub fn from_env() -> Self {
+        let flag = |name: &str| env::var(name).ok().and_then(|v| v.parse::<bool>().ok());
+        SessionConfig {
+            cookie_name: env::var("SESSION_COOKIE").ok().filter(|v| !v.is_empty()).unwrap_or_else(|| "session".to_string()),
+            ttl: env::var("SESSION_TTL").ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&v: &u64| v > 0)
+                .unwrap_or(7 * 24 * 3600),
+            secure: flag("SESSION_COOKIE_SECURE").or_else(|| flag("SSL_ENABLED")).unwrap_or(true),
+        }
+    }
+}
+
+/// Protection CSRF des routes `/api` qui modifient l'état (jeton « double-submit » signé)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfConfig {
+    pub enabled: bool,
+    /// Nom du cookie portant le jeton
+    pub cookie_name: String,
+    /// En-tête dans lequel le client renvoie le jeton
+    pub header_name: String,
+    /// Clé HMAC des jetons ; tirée au démarrage si absente, ce qui invalide les jetons
+    /// à chaque redémarrage et entre plusieurs instances
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+    /// Cookie réservé à HTTPS (`Secure`)
+    pub secure: bool,
+}
+
+impl CsrfConfig {
+    /// `CSRF_PROTECTION` (activée par défaut), `CSRF_COOKIE` (`csrf_token`), `CSRF_HEADER`
+    /// (`X-CSRF-Token`), `CSRF_SECRET` (ignoré sous 32 caractères) et `Secure` comme le cookie de session
+    pub fn from_env() -> Self {
+        let value = |name: &str| env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        CsrfConfig {
+            enabled: value("CSRF_PROTECTION").and_then(|v| v.parse().ok()).unwrap_or(true),
+            cookie_name: value("CSRF_COOKIE").unwrap_or_else(|| "csrf_token".to_string()),
+            header_name: value("CSRF_HEADER").unwrap_or_else(|| "X-CSRF-Token".to_string()),
+            secret: value("CSRF_SECRET").filter(|v| v.len() >= SignedLinkConfig::MIN_SECRET_LEN),
+            secure: SessionConfig::from_env().secure,
+        }
+    }
+}
+
+/// Débit accordé à un groupe de routes : `capacity` requêtes d'affilée, puis `capacity`
+/// requêtes toutes les `period` secondes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period: u64,
+}
+
+impl RateLimit {
+    /// Lit `requêtes/secondes`, par exemple `120/60`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (capacity, period) = value.split_once('/')?;
+        let limit = RateLimit { capacity: capacity.trim().parse().ok()?, period: period.trim().parse().ok()? };
+        (limit.capacity > 0 && limit.period > 0).then_some(limit)
+    }
+
+    /// Jetons rendus par seconde
+    pub fn per_second(&self) -> f64 {
+        self.capacity as f64 / self.period as f64
+    }
+}
+
+/// Limitation du débit de `/api` par groupe de routes, par clé d'API, utilisateur ou adresse IP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Routes de l'API non classées ailleurs
+    pub api: RateLimit,
+    /// Envois de fichiers (`POST /api/form`)
+    pub uploads: RateLimit,
+    /// Connexion et second facteur (`/api/auth/*`)
+    pub auth: RateLimit,
+    /// Seaux en base (table `rate_limits`), partagés entre plusieurs instances
+    pub shared: bool,
+    /// Adresse du client lue dans `X-Forwarded-For`/`Forwarded`, derrière un proxy de confiance seulement
+    pub trust_proxy: bool,
+}
+
+impl RateLimitConfig {
+    /// `RATE_LIMIT_ENABLED` (activée par défaut), `RATE_LIMIT_API` (`120/60`), `RATE_LIMIT_UPLOADS`
+    /// (`10/60`), `RATE_LIMIT_AUTH` (`10/60`), `RATE_LIMIT_STORE` (`memory` ou `database`)
+    /// et `RATE_LIMIT_TRUST_PROXY`
+    pub fn from_env() -> Self {
+        let value = |name: &str| env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let limit = |name: &str, capacity: u32, period: u64| value(name)
+            .and_then(|v| RateLimit::parse(&v))
+            .unwrap_or(RateLimit { capacity, period });
+        RateLimitConfig {
+            enabled: value("RATE_LIMIT_ENABLED").and_then(|v| v.parse().ok()).unwrap_or(true),
+            api: limit("RATE_LIMIT_API", 120, 60),
+            uploads: limit("RATE_LIMIT_UPLOADS", 10, 60),
+            auth: limit("RATE_LIMIT_AUTH", 10, 60),
+            shared: value("RATE_LIMIT_STORE").is_some_and(|v| v.eq_ignore_ascii_case("database")),
+            trust_proxy: value("RATE_LIMIT_TRUST_PROXY").and_then(|v| v.parse().ok()).unwrap_or(false),
+        }
+    }
+}
+
+/// Second facteur TOTP des comptes locaux
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpConfig {
+    /// Nom du service affiché par l'application d'authentification
+    pub issuer: String,
+}
+
+impl TotpConfig {
+    /// `TOTP_ISSUER` (`crate` par défaut)
+    pub fn from_env() -> Self {
+        TotpConfig {
+            issuer: env::var("TOTP_ISSUER").ok().filter(|v| !v.trim().is_empty()).unwrap_or_else(|| "crate".to_string()),
+        }
+    }
+}
+
+/// Connexion par un fournisseur OpenID Connect (code d'autorisation + PKCE)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Émetteur, dont `/.well-known/openid-configuration` décrit le fournisseur
+    pub issuer: String,
+    pub client_id: String,
+    /// Absent pour un client public, qui ne s'authentifie que par PKCE
+    #[serde(skip_serializing)]
+    pub client_secret: Option<String>,
+    /// Adresse de `/api/auth/oidc/callback` telle que déclarée chez le fournisseur
+    pub redirect_uri: String,
+    pub scopes: String,
+    /// Claim de l'ID token recopié dans `users.login`
+    pub login_claim: String,
+    /// Durée de conservation des clés du fournisseur (JWKS), en secondes
+    pub jwks_ttl: u64,
+}
+
+impl OidcConfig {
+    /// `OIDC_ISSUER`, `OIDC_CLIENT_ID` et `OIDC_REDIRECT_URI` obligatoires ; `OIDC_CLIENT_SECRET`,
+    /// `OIDC_SCOPES` (`openid email profile`), `OIDC_LOGIN_CLAIM` (`preferred_username`)
+    /// et `OIDC_JWKS_TTL` en secondes (1 heure) facultatifs
+    pub fn from_env() -> Result<Self, String> {
+        let value = |name: &str| env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let required = |name: &str| value(name).ok_or_else(|| format!("{} is not set", name));
+        Ok(OidcConfig {
+            issuer: required("OIDC_ISSUER")?.trim_end_matches('/').to_string(),
+            client_id: required("OIDC_CLIENT_ID")?,
+            client_secret: value("OIDC_CLIENT_SECRET"),
+            redirect_uri: required("OIDC_REDIRECT_URI")?,
+            scopes: value("OIDC_SCOPES").unwrap_or_else(|| "openid email profile".to_string()),
+            login_claim: value("OIDC_LOGIN_CLAIM").unwrap_or_else(|| "preferred_username".to_string()),
+            jwks_ttl: value("OIDC_JWKS_TTL").and_then(|v| v.parse().ok()).unwrap_or(3600),
+        })
+    }
 }
 
 impl AppConfig {
@@ -57,15 +382,15 @@ impl AppConfig {
                     .map(|s| s.trim().to_string())
                     .collect(),
             },
-            upload: UploadConfig {
-                max_file_size: env::var("MAX_FILE_SIZE")?.parse().unwrap_or(10 * 1024 * 1024), // 10MB
-                allowed_extensions: env::var("ALLOWED_EXTENSIONS")
-                    .unwrap_or_else(|_| "jpg,jpeg,png,pdf,txt".to_string())
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect(),
-                upload_dir: env::var("UPLOAD_DIR").unwrap_or_else(|_| "storage/files".to_string()),
-            },
+            upload: UploadConfig::from_env(),
+            // Le détail de l'erreur est donné par `StorageConfig::from_env`
+            storage: StorageConfig::from_env().map_err(|_| env::VarError::NotPresent)?,
+            links: SignedLinkConfig::from_env().ok(),
+            session: SessionConfig::from_env(),
+            csrf: CsrfConfig::from_env(),
+            rate_limit: RateLimitConfig::from_env(),
+            totp: TotpConfig::from_env(),
+            oidc: OidcConfig::from_env().ok(),
         })
     }
 
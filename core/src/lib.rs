@@ -11,4 +11,4 @@ pub use repositories::{UserRepository, _database};
 
 // Always available exports
 pub use http_models::http_responses::HttpSendResponse;
-pub use table::Table;
+pub use table::{Table, TableRenderer};
@@ -4,8 +4,13 @@ use uuid::Uuid;
 use time::OffsetDateTime;
 use std::collections::HashMap;
 use std::env;
-use crate::repositories::migration_repository::{MigrationRepository, Migration};
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use crate::repositories::{_init_repository::InitRepository};
+use crate::repositories::_schema::{Column, TableSchema};
+use crate::repositories::_from_row::FromDatabaseRow;
 use crate::repositories::migrations;
 
 #[cfg(feature = "postgres")]
@@ -23,6 +28,24 @@ pub enum DatabasePool {
     Sqlite(SqlitePool),
 }
 
+/// Dialecte SQL du pool, utilisé pour générer du DDL et des requêtes compatibles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite,
+}
+
+impl DatabasePool {
+    pub fn dialect(&self) -> Dialect {
+        match self {
+            #[cfg(feature = "postgres")]
+            DatabasePool::Postgres(_) => Dialect::Postgres,
+            #[cfg(feature = "sqlite")]
+            DatabasePool::Sqlite(_) => Dialect::Sqlite,
+        }
+    }
+}
+
 /// Type alias pour les rows de base de données
 pub enum DatabaseRow {
     #[cfg(feature = "postgres")]
@@ -65,10 +88,172 @@ impl DatabaseRow {
     }
 }
 
+/// Valeur typée liée à un paramètre de requête (`?` dans le SQL)
+///
+/// Chaque variante porte une `Option` afin que les valeurs NULL restent typées :
+/// PostgreSQL refuse un NULL de type TEXT dans une colonne INTEGER.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DbValue {
+    Bool(Option<bool>),
+    Int(Option<i32>),
+    BigInt(Option<i64>),
+    Double(Option<f64>),
+    Text(Option<String>),
+    Uuid(Option<Uuid>),
+    Timestamp(Option<OffsetDateTime>),
+}
+
+macro_rules! impl_db_value_from {
+    ($variant:ident, $ty:ty) => {
+        impl From<$ty> for DbValue {
+            fn from(value: $ty) -> Self {
+                DbValue::$variant(Some(value))
+            }
+        }
+
+        impl From<Option<$ty>> for DbValue {
+            fn from(value: Option<$ty>) -> Self {
+                DbValue::$variant(value)
+            }
+        }
+    };
+}
+
+impl_db_value_from!(Bool, bool);
+impl_db_value_from!(Int, i32);
+impl_db_value_from!(BigInt, i64);
+impl_db_value_from!(Double, f64);
+impl_db_value_from!(Text, String);
+impl_db_value_from!(Uuid, Uuid);
+impl_db_value_from!(Timestamp, OffsetDateTime);
+
+impl From<&str> for DbValue {
+    fn from(value: &str) -> Self {
+        DbValue::Text(Some(value.to_string()))
+    }
+}
+
+impl From<&String> for DbValue {
+    fn from(value: &String) -> Self {
+        DbValue::Text(Some(value.clone()))
+    }
+}
+
+impl From<Option<&str>> for DbValue {
+    fn from(value: Option<&str>) -> Self {
+        DbValue::Text(value.map(|s| s.to_string()))
+    }
+}
+
+impl From<&Option<String>> for DbValue {
+    fn from(value: &Option<String>) -> Self {
+        DbValue::Text(value.clone())
+    }
+}
+
+/// Traduit les placeholders `?` vers la syntaxe attendue par le pool
+///
+/// PostgreSQL attend des paramètres numérotés (`$1`, `$2`...), SQLite accepte `?` tel quel.
+/// Les `?` présents dans une chaîne littérale ou un identifiant entre guillemets sont ignorés.
+fn translate_placeholders(query: &str, pool: &DatabasePool) -> String {
+    match pool {
+        #[cfg(feature = "postgres")]
+        DatabasePool::Postgres(_) => number_placeholders(query),
+        #[cfg(feature = "sqlite")]
+        DatabasePool::Sqlite(_) => query.to_string(),
+    }
+}
+
+/// Remplace chaque `?` hors littéral par `$n`
+#[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+fn number_placeholders(query: &str) -> String {
+    let mut translated = String::with_capacity(query.len() + 8);
+    let mut index = 0;
+    let mut quote: Option<char> = None;
+
+    for c in query.chars() {
+        match (quote, c) {
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                translated.push(c);
+            },
+            (Some(q), _) if q == c => {
+                quote = None;
+                translated.push(c);
+            },
+            (None, '?') => {
+                index += 1;
+                translated.push('$');
+                translated.push_str(&index.to_string());
+            },
+            _ => translated.push(c),
+        }
+    }
+
+    translated
+}
+
+#[cfg(feature = "postgres")]
+fn bind_postgres<'q>(
+    mut query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+    params: &'q [DbValue],
+) -> sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments> {
+    for param in params {
+        query = match param {
+            DbValue::Bool(v)      => query.bind(*v),
+            DbValue::Int(v)       => query.bind(*v),
+            DbValue::BigInt(v)    => query.bind(*v),
+            DbValue::Double(v)    => query.bind(*v),
+            DbValue::Text(v)      => query.bind(v.as_deref()),
+            DbValue::Uuid(v)      => query.bind(*v),
+            DbValue::Timestamp(v) => query.bind(*v),
+        };
+    }
+    query
+}
+
+#[cfg(feature = "sqlite")]
+fn bind_sqlite<'q>(
+    mut query: sqlx::query::Query<'q, Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    params: &'q [DbValue],
+) -> sqlx::query::Query<'q, Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+    for param in params {
+        query = match param {
+            DbValue::Bool(v)      => query.bind(*v),
+            DbValue::Int(v)       => query.bind(*v),
+            DbValue::BigInt(v)    => query.bind(*v),
+            DbValue::Double(v)    => query.bind(*v),
+            DbValue::Text(v)      => query.bind(v.as_deref()),
+            // Les UUID sont stockés en texte dans SQLite pour rester lisibles et comparables
+            DbValue::Uuid(v)      => query.bind(v.map(|id| id.hyphenated().to_string())),
+            DbValue::Timestamp(v) => query.bind(*v),
+        };
+    }
+    query
+}
+
+/// Connexion réservée par une transaction ouverte ; celle de PostgreSQL, bien plus grosse,
+/// est mise en boîte
+enum TransactionConnection {
+    #[cfg(feature = "postgres")]
+    Postgres(Box<sqlx::Transaction<'static, Postgres>>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::Transaction<'static, Sqlite>),
+}
+
+/// Connexion partagée entre les clones d'une transaction, `None` une fois terminée
+type SharedTransaction = Arc<Mutex<Option<TransactionConnection>>>;
+
+fn transaction_finished() -> Error {
+    Error::msg("Transaction already committed or rolled back")
+}
+
 /// Représente une requête de base de données avec adaptateur
 #[derive(Clone)]
 pub struct DatabaseQuery {
     pool: DatabasePool,
+    /// Transaction en cours : toutes les requêtes passent alors par sa connexion
+    transaction: Option<SharedTransaction>,
 }
 
 /// Implémentation de la structure DatabaseQuery
@@ -77,14 +262,16 @@ impl DatabaseQuery {
     #[cfg(feature = "postgres")]
     pub fn new_postgres(pool: PgPool) -> Self {
         Self { 
-            pool: DatabasePool::Postgres(pool)
+            pool: DatabasePool::Postgres(pool),
+            transaction: None,
         }
     }
     
     #[cfg(feature = "sqlite")]
     pub fn new_sqlite(pool: SqlitePool) -> Self {
         Self { 
-            pool: DatabasePool::Sqlite(pool)
+            pool: DatabasePool::Sqlite(pool),
+            transaction: None,
         }
     }
     
@@ -102,128 +289,311 @@ impl DatabaseQuery {
     // Méthode pour récupérer une référence au pool de connexion
     pub fn get_pool(&self) -> &DatabasePool {
         &self.pool
-    }   
-    
-    /// Lance une requête en SQL brut
-    pub async fn run_query(&self, query: &str) -> Result<()> {
-        match &self.pool {
+    }
+
+    /// Dialecte SQL du pool courant
+    pub fn dialect(&self) -> Dialect {
+        self.pool.dialect()
+    }
+
+    /// Ouvre une transaction sur une connexion du pool
+    ///
+    /// Sans `commit`, la transaction est annulée quand son dernier clone est libéré.
+    pub async fn begin(&self) -> Result<DatabaseTransaction> {
+        if self.transaction.is_some() {
+            return Err(Error::msg("Nested transactions are not supported"));
+        }
+
+        let connection = match &self.pool {
             #[cfg(feature = "postgres")]
-            DatabasePool::Postgres(pool) => {
-                sqlx::query(query)
-                    .execute(pool)
+            DatabasePool::Postgres(pool) => TransactionConnection::Postgres(Box::new(
+                pool.begin()
+                    .await
+                    .map_err(|e| Error::msg(format!("\x1b[31mFailed begin: {}\x1b[0m", e)))?
+            )),
+            #[cfg(feature = "sqlite")]
+            DatabasePool::Sqlite(pool) => TransactionConnection::Sqlite(
+                pool.begin()
                     .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query {}\x1b[0m", e)))?;
+                    .map_err(|e| Error::msg(format!("\x1b[31mFailed begin: {}\x1b[0m", e)))?
+            ),
+            #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+            _ => return Err(Error::msg("No database feature enabled")),
+        };
+
+        let connection: SharedTransaction = Arc::new(Mutex::new(Some(connection)));
+        Ok(DatabaseTransaction {
+            query: Self {
+                pool: self.pool.clone(),
+                transaction: Some(connection.clone()),
+            },
+            connection,
+        })
+    }
+
+    /// Exécute `f` dans une transaction : commit si elle réussit, rollback sinon
+    ///
+    /// Le closure reçoit la transaction ; `tx.as_query()` la transmet aux repositories.
+    pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(DatabaseTransaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let transaction = self.begin().await?;
+
+        match f(transaction.clone()).await {
+            Ok(value) => {
+                transaction.commit().await?;
+                Ok(value)
+            },
+            Err(err) => {
+                if let Err(rollback_err) = transaction.rollback().await {
+                    eprintln!("\x1b[31mRollback failed: {}\x1b[0m", rollback_err);
+                }
+                Err(err)
+            },
+        }
+    }
+
+    /// Exécute une requête sur la transaction en cours, ou sur le pool à défaut
+    async fn execute_sql(&self, sql: &str, params: &[DbValue]) -> Result<u64> {
+        if let Some(transaction) = &self.transaction {
+            let mut guard = transaction.lock().await;
+            let rows_affected = match guard.as_mut().ok_or_else(transaction_finished)? {
+                #[cfg(feature = "postgres")]
+                TransactionConnection::Postgres(tx) => {
+                    bind_postgres(sqlx::query(sql), params).execute(&mut ***tx).await?.rows_affected()
+                },
+                #[cfg(feature = "sqlite")]
+                TransactionConnection::Sqlite(tx) => {
+                    bind_sqlite(sqlx::query(sql), params).execute(&mut **tx).await?.rows_affected()
+                },
+            };
+            return Ok(rows_affected);
+        }
+
+        let rows_affected = match &self.pool {
+            #[cfg(feature = "postgres")]
+            DatabasePool::Postgres(pool) => {
+                bind_postgres(sqlx::query(sql), params).execute(pool).await?.rows_affected()
             },
             #[cfg(feature = "sqlite")]
             DatabasePool::Sqlite(pool) => {
-                sqlx::query(query)
-                    .execute(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query {}\x1b[0m", e)))?;
+                bind_sqlite(sqlx::query(sql), params).execute(pool).await?.rows_affected()
             },
             #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
             _ => return Err(Error::msg("No database feature enabled")),
+        };
+        Ok(rows_affected)
+    }
+
+    async fn fetch_one_sql(&self, sql: &str, params: &[DbValue]) -> Result<DatabaseRow> {
+        if let Some(transaction) = &self.transaction {
+            let mut guard = transaction.lock().await;
+            let row = match guard.as_mut().ok_or_else(transaction_finished)? {
+                #[cfg(feature = "postgres")]
+                TransactionConnection::Postgres(tx) => {
+                    DatabaseRow::Postgres(bind_postgres(sqlx::query(sql), params).fetch_one(&mut ***tx).await?)
+                },
+                #[cfg(feature = "sqlite")]
+                TransactionConnection::Sqlite(tx) => {
+                    DatabaseRow::Sqlite(bind_sqlite(sqlx::query(sql), params).fetch_one(&mut **tx).await?)
+                },
+            };
+            return Ok(row);
         }
 
-        println!("\x1b[32mQuery executed successfully: {}\x1b[0m", query);
-        Ok(())
-    }
-    
-    /// Exécute une requête et retourne une ligne
-    pub async fn run_query_fetch_one(&self, query: &str) -> Result<DatabaseRow> {
-        let result = match &self.pool {
+        let row = match &self.pool {
             #[cfg(feature = "postgres")]
             DatabasePool::Postgres(pool) => {
-                let row = sqlx::query(query)
-                    .fetch_one(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_one: {}\x1b[0m", e)))?;
-                DatabaseRow::Postgres(row)
+                DatabaseRow::Postgres(bind_postgres(sqlx::query(sql), params).fetch_one(pool).await?)
             },
             #[cfg(feature = "sqlite")]
             DatabasePool::Sqlite(pool) => {
-                let row = sqlx::query(query)
-                    .fetch_one(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_one: {}\x1b[0m", e)))?;
-                DatabaseRow::Sqlite(row)
+                DatabaseRow::Sqlite(bind_sqlite(sqlx::query(sql), params).fetch_one(pool).await?)
             },
             #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
             _ => return Err(Error::msg("No database feature enabled")),
         };
-
-        println!("\x1b[32mQuery fetch_one executed successfully: {}\x1b[0m", query);
-        Ok(result)
+        Ok(row)
     }
-    
-    /// Exécute une requête et retourne une ligne optionnelle
-    pub async fn run_query_fetch_optional(&self, query: &str) -> Result<Option<DatabaseRow>> {
-        let result = match &self.pool {
+
+    async fn fetch_optional_sql(&self, sql: &str, params: &[DbValue]) -> Result<Option<DatabaseRow>> {
+        if let Some(transaction) = &self.transaction {
+            let mut guard = transaction.lock().await;
+            let row = match guard.as_mut().ok_or_else(transaction_finished)? {
+                #[cfg(feature = "postgres")]
+                TransactionConnection::Postgres(tx) => {
+                    bind_postgres(sqlx::query(sql), params).fetch_optional(&mut ***tx).await?.map(DatabaseRow::Postgres)
+                },
+                #[cfg(feature = "sqlite")]
+                TransactionConnection::Sqlite(tx) => {
+                    bind_sqlite(sqlx::query(sql), params).fetch_optional(&mut **tx).await?.map(DatabaseRow::Sqlite)
+                },
+            };
+            return Ok(row);
+        }
+
+        let row = match &self.pool {
             #[cfg(feature = "postgres")]
             DatabasePool::Postgres(pool) => {
-                match sqlx::query(query)
-                    .fetch_optional(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_optional: {}\x1b[0m", e)))? {
-                    Some(row) => Some(DatabaseRow::Postgres(row)),
-                    None => None,
-                }
+                bind_postgres(sqlx::query(sql), params).fetch_optional(pool).await?.map(DatabaseRow::Postgres)
             },
             #[cfg(feature = "sqlite")]
             DatabasePool::Sqlite(pool) => {
-                match sqlx::query(query)
-                    .fetch_optional(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_optional: {}\x1b[0m", e)))? {
-                    Some(row) => Some(DatabaseRow::Sqlite(row)),
-                    None => None,
-                }
+                bind_sqlite(sqlx::query(sql), params).fetch_optional(pool).await?.map(DatabaseRow::Sqlite)
             },
             #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
             _ => return Err(Error::msg("No database feature enabled")),
         };
-
-        println!("\x1b[32mQuery fetch_optional executed successfully: {}\x1b[0m", query);
-        Ok(result)
+        Ok(row)
     }
-    
-    /// Exécute une requête et retourne toutes les lignes
-    pub async fn run_query_fetch_all(&self, query: &str) -> Result<Vec<DatabaseRow>> {
-        let result = match &self.pool {
+
+    async fn fetch_all_sql(&self, sql: &str, params: &[DbValue]) -> Result<Vec<DatabaseRow>> {
+        if let Some(transaction) = &self.transaction {
+            let mut guard = transaction.lock().await;
+            let rows = match guard.as_mut().ok_or_else(transaction_finished)? {
+                #[cfg(feature = "postgres")]
+                TransactionConnection::Postgres(tx) => {
+                    bind_postgres(sqlx::query(sql), params).fetch_all(&mut ***tx).await?
+                        .into_iter().map(DatabaseRow::Postgres).collect()
+                },
+                #[cfg(feature = "sqlite")]
+                TransactionConnection::Sqlite(tx) => {
+                    bind_sqlite(sqlx::query(sql), params).fetch_all(&mut **tx).await?
+                        .into_iter().map(DatabaseRow::Sqlite).collect()
+                },
+            };
+            return Ok(rows);
+        }
+
+        let rows = match &self.pool {
             #[cfg(feature = "postgres")]
             DatabasePool::Postgres(pool) => {
-                let rows = sqlx::query(query)
-                    .fetch_all(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_all: {}\x1b[0m", e)))?;
-                rows.into_iter().map(|row| DatabaseRow::Postgres(row)).collect()
+                bind_postgres(sqlx::query(sql), params).fetch_all(pool).await?
+                    .into_iter().map(DatabaseRow::Postgres).collect()
             },
             #[cfg(feature = "sqlite")]
             DatabasePool::Sqlite(pool) => {
-                let rows = sqlx::query(query)
-                    .fetch_all(pool)
-                    .await
-                    .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_all: {}\x1b[0m", e)))?;
-                rows.into_iter().map(|row| DatabaseRow::Sqlite(row)).collect()
+                bind_sqlite(sqlx::query(sql), params).fetch_all(pool).await?
+                    .into_iter().map(DatabaseRow::Sqlite).collect()
             },
             #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
             _ => return Err(Error::msg("No database feature enabled")),
         };
+        Ok(rows)
+    }
+    
+    /// Lance une requête en SQL brut
+    pub async fn run_query(&self, query: &str) -> Result<()> {
+        self.execute_sql(query, &[])
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed query {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery executed successfully: {}\x1b[0m", query);
+        Ok(())
+    }
+    
+    /// Exécute une requête et retourne une ligne
+    pub async fn run_query_fetch_one(&self, query: &str) -> Result<DatabaseRow> {
+        let result = self.fetch_one_sql(query, &[])
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_one: {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery fetch_one executed successfully: {}\x1b[0m", query);
+        Ok(result)
+    }
+    
+    /// Exécute une requête et retourne une ligne optionnelle
+    pub async fn run_query_fetch_optional(&self, query: &str) -> Result<Option<DatabaseRow>> {
+        let result = self.fetch_optional_sql(query, &[])
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_optional: {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery fetch_optional executed successfully: {}\x1b[0m", query);
+        Ok(result)
+    }
+    
+    /// Exécute une requête et retourne toutes les lignes
+    pub async fn run_query_fetch_all(&self, query: &str) -> Result<Vec<DatabaseRow>> {
+        let result = self.fetch_all_sql(query, &[])
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed query_fetch_all: {}\x1b[0m", e)))?;
 
         println!("\x1b[32mQuery fetch_all executed successfully: {}\x1b[0m", query);
         Ok(result)
     }
     
-    /// Exécute une requête de création de table
-    pub async fn create_tables(&self, table_name: &str, columns: &str) -> Result<()> {
-        let create_table_query = format!(
-            "CREATE TABLE IF NOT EXISTS {} ({})",
-            table_name, columns
-        );
+    /// Lance une requête paramétrée et retourne le nombre de lignes affectées
+    ///
+    /// Les paramètres sont écrits `?` dans le SQL, quel que soit le backend.
+    pub async fn run_query_with(&self, query: &str, params: &[DbValue]) -> Result<u64> {
+        let sql = translate_placeholders(query, &self.pool);
+        let rows_affected = self.execute_sql(&sql, params)
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed query {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery executed successfully: {}\x1b[0m", query);
+        Ok(rows_affected)
+    }
+
+    /// Exécute une requête paramétrée et retourne une ligne
+    pub async fn fetch_one_with(&self, query: &str, params: &[DbValue]) -> Result<DatabaseRow> {
+        let sql = translate_placeholders(query, &self.pool);
+        let result = self.fetch_one_sql(&sql, params)
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed fetch_one_with: {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery fetch_one executed successfully: {}\x1b[0m", query);
+        Ok(result)
+    }
+
+    /// Exécute une requête paramétrée et retourne une ligne optionnelle
+    pub async fn fetch_optional_with(&self, query: &str, params: &[DbValue]) -> Result<Option<DatabaseRow>> {
+        let sql = translate_placeholders(query, &self.pool);
+        let result = self.fetch_optional_sql(&sql, params)
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed fetch_optional_with: {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery fetch_optional executed successfully: {}\x1b[0m", query);
+        Ok(result)
+    }
+
+    /// Exécute une requête paramétrée et retourne toutes les lignes
+    pub async fn fetch_all_with(&self, query: &str, params: &[DbValue]) -> Result<Vec<DatabaseRow>> {
+        let sql = translate_placeholders(query, &self.pool);
+        let result = self.fetch_all_sql(&sql, params)
+            .await
+            .map_err(|e| Error::msg(format!("\x1b[31mFailed fetch_all_with: {}\x1b[0m", e)))?;
+
+        println!("\x1b[32mQuery fetch_all executed successfully: {}\x1b[0m", query);
+        Ok(result)
+    }
+    /// Exécute une requête paramétrée et convertit la ligne retournée
+    pub async fn fetch_one_as<T: FromDatabaseRow>(&self, query: &str, params: &[DbValue]) -> Result<T> {
+        let row = self.fetch_one_with(query, params).await?;
+        Ok(T::from_row(&row)?)
+    }
+
+    /// Exécute une requête paramétrée et convertit la ligne éventuelle
+    pub async fn fetch_optional_as<T: FromDatabaseRow>(&self, query: &str, params: &[DbValue]) -> Result<Option<T>> {
+        let row = self.fetch_optional_with(query, params).await?;
+        Ok(row.as_ref().map(T::from_row).transpose()?)
+    }
+
+    /// Exécute une requête paramétrée et convertit toutes les lignes
+    pub async fn fetch_all_as<T: FromDatabaseRow>(&self, query: &str, params: &[DbValue]) -> Result<Vec<T>> {
+        let rows = self.fetch_all_with(query, params).await?;
+        Ok(rows.iter().map(T::from_row).collect::<Result<Vec<T>, _>>()?)
+    }
+
+    /// Crée une table à partir de son schéma, dans le dialecte du pool
+    pub async fn create_tables(&self, schema: &TableSchema) -> Result<()> {
+        let create_table_query = schema.create_table_sql(self.dialect());
         
         self.run_query(&create_table_query).await?;
 
-        println!("Table {} created successfully", table_name);
+        println!("Table {} created successfully", schema.name);
         Ok(())
     }    
     
@@ -238,32 +608,48 @@ impl DatabaseQuery {
         println!("Table dropped successfully");
         Ok(())
     }    
+
+    /// Ajoute une colonne à une table existante
+    pub async fn add_column(&self, table_name: &str, column: &Column) -> Result<()> {
+        let add_column_query = format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            table_name, column.to_sql(self.dialect())
+        );
+        self.run_query(&add_column_query).await?;
+
+        println!("Column {} added to table {}", column.name, table_name);
+        Ok(())
+    }
+
+    /// Supprime une colonne d'une table existante
+    pub async fn drop_column(&self, table_name: &str, column_name: &str) -> Result<()> {
+        let drop_column_query = format!(
+            "ALTER TABLE {} DROP COLUMN {}",
+            table_name, column_name
+        );
+        self.run_query(&drop_column_query).await?;
+
+        println!("Column {} dropped from table {}", column_name, table_name);
+        Ok(())
+    }
     
-    /// Exécute une requête de création d'index
-    pub async fn create_indexes(&self, table_name: &str, indexes: Vec<&str>) -> Result<()> {
-        for idx in indexes {
-            let index_query = format!(
-                "CREATE INDEX IF NOT EXISTS idx_{}_{} ON {} ({})",
-                table_name, idx, table_name, idx
-            );
+    /// Crée les index déclarés dans le schéma
+    pub async fn create_indexes(&self, schema: &TableSchema) -> Result<()> {
+        for index_query in schema.create_indexes_sql() {
             self.run_query(&index_query).await?;
         }
 
-        println!("Indexes created successfully for table: {}", table_name);
+        println!("Indexes created successfully for table: {}", schema.name);
         Ok(())
     }    
     
-    /// Exécute une requête de suppression d'index
-    pub async fn drop_indexes(&self, table_name: &str, indexes: Vec<&str>) -> Result<()> {
-        for idx in indexes {
-            let drop_index_query = format!(
-                "DROP INDEX IF EXISTS idx_{}_{}",
-                table_name, idx
-            );
+    /// Supprime les index déclarés dans le schéma
+    pub async fn drop_indexes(&self, schema: &TableSchema) -> Result<()> {
+        for drop_index_query in schema.drop_indexes_sql() {
             self.run_query(&drop_index_query).await?;
         }
         
-        println!("Indexes dropped successfully for table: {}", table_name);
+        println!("Indexes dropped successfully for table: {}", schema.name);
         Ok(())
     }
     
@@ -339,42 +725,99 @@ impl DatabaseQuery {
     }
 }
 
+/// Transaction ouverte par `DatabaseQuery::begin`
+///
+/// Expose, par déréférencement, la même API de requêtes que `DatabaseQuery`.
+/// Les clones partagent la même connexion.
+#[derive(Clone)]
+pub struct DatabaseTransaction {
+    query: DatabaseQuery,
+    connection: SharedTransaction,
+}
+
+impl DatabaseTransaction {
+    /// `DatabaseQuery` lié à cette transaction, à passer aux repositories
+    pub fn as_query(&self) -> DatabaseQuery {
+        self.query.clone()
+    }
 
-/// Exécute les migrations nécessaires pour créer les tables de base de données
-pub async fn _init_migration_tables(db_query: &DatabaseQuery) {
-    println!("Running migrations...");
-    
-    let mut failed_migrations = Vec::new();
-    
-    // Macro pour simplifier l'exécution des migrations
-    macro_rules! run_migration {
-        ($name:expr, $migration:expr) => {
-            if let Err(err) = $migration.await {
-                eprintln!("\x1b[31mMigration '{}' failed: {}\x1b[0m", $name, err);
-                failed_migrations.push($name);
-            }
+    /// Valide la transaction
+    pub async fn commit(self) -> Result<()> {
+        self.finish(true).await
+    }
+
+    /// Annule la transaction
+    pub async fn rollback(self) -> Result<()> {
+        self.finish(false).await
+    }
+
+    /// Prend un verrou exclusif sur `key` jusqu'à la fin de la transaction
+    ///
+    /// Sous PostgreSQL, verrou consultatif ; SQLite sérialise déjà les écritures.
+    pub async fn lock(&self, key: &str) -> Result<()> {
+        match self.query.dialect() {
+            Dialect::Postgres => {
+                self.query.run_query_with("SELECT pg_advisory_xact_lock(hashtext(?))", &[key.into()]).await?;
+            },
+            Dialect::Sqlite => {},
+        }
+        Ok(())
+    }
+
+    async fn finish(&self, commit: bool) -> Result<()> {
+        let connection = self.connection.lock().await.take().ok_or_else(transaction_finished)?;
+        let action = if commit { "commit" } else { "rollback" };
+
+        let result = match connection {
+            #[cfg(feature = "postgres")]
+            TransactionConnection::Postgres(tx) => if commit { (*tx).commit().await } else { (*tx).rollback().await },
+            #[cfg(feature = "sqlite")]
+            TransactionConnection::Sqlite(tx) => if commit { tx.commit().await } else { tx.rollback().await },
         };
+        result.map_err(|e| Error::msg(format!("\x1b[31mFailed {}: {}\x1b[0m", action, e)))?;
+
+        println!("\x1b[32mTransaction {} executed successfully\x1b[0m", action);
+        Ok(())
     }
-    
-    // Exécution séquentielle des migrations
-    run_migration!("migration_create_migration", migrations::migration_create_migration::run(db_query));
-    run_migration!("migration_create_users",     migrations::migration_create_users::run(db_query));
-    run_migration!("migration_create_logs",      migrations::migration_create_logs::run(db_query));
-    run_migration!("migration_test",             migrations::migration_test::run(db_query));
-    
-    // Affichage du résultat final
-    if failed_migrations.is_empty() {
-        println!("\x1b[32mAll migrations completed successfully.\x1b[0m");
+}
+
+impl Deref for DatabaseTransaction {
+    type Target = DatabaseQuery;
+
+    fn deref(&self) -> &DatabaseQuery {
+        &self.query
+    }
+}
+
+
+/// Applique les migrations en attente au démarrage
+///
+/// Échoue si le schéma est à moitié migré (migration `dirty`) ou si une migration
+/// appliquée a été modifiée depuis : le serveur ne doit pas démarrer dans cet état.
+pub async fn _init_migration_tables(db_query: &DatabaseQuery) -> Result<()> {
+    println!("Running migrations...");
+
+    let applied = migrations::Migrator::new(db_query.clone()).up().await?;
+
+    if applied.is_empty() {
+        println!("\x1b[32mDatabase schema is up to date.\x1b[0m");
     } else {
-        eprintln!("\x1b[31m{} migration(s) failed: {}\x1b[0m", 
-            failed_migrations.len(), 
-            failed_migrations.join(", "));
+        println!("\x1b[32m{} migration(s) applied: {}\x1b[0m", applied.len(), applied.join(", "));
     }
+    Ok(())
 }
 
 
-/// Initialise la base de données en créant un pool de connexions
+/// Initialise la base de données et applique les migrations en attente
 pub async fn init_db() -> Result<DatabaseQuery, Error> {
+    let db_query = connect_db().await?;
+    _init_migration_tables(&db_query).await?;
+    Ok(db_query)
+}
+
+
+/// Crée le pool de connexions, sans toucher au schéma
+pub async fn connect_db() -> Result<DatabaseQuery, Error> {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
     println!("Connecting to database...");
@@ -399,9 +842,6 @@ pub async fn init_db() -> Result<DatabaseQuery, Error> {
                             }
 
                             if let Ok(_) = db_query.run_query("SELECT 1").await {
-                                // Initialize and create tables if needed
-                                _init_migration_tables(&db_query).await;
-
                                 return Ok(db_query);
                             }
                         },
@@ -433,9 +873,6 @@ pub async fn init_db() -> Result<DatabaseQuery, Error> {
 
                             // Pour SQLite, pas besoin de créer la base de données explicitement
                             if let Ok(_) = db_query.run_query("SELECT 1").await {
-                                // Initialize and create tables if needed
-                                _init_migration_tables(&db_query).await;
-
                                 return Ok(db_query);
                             }
                         },
@@ -458,4 +895,67 @@ pub async fn init_db() -> Result<DatabaseQuery, Error> {
     }
     
     Err(Error::msg("Could not connect to database after 3 attempts"))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_placeholders_skips_literals() {
+        let query = "SELECT * FROM users WHERE login = ? AND info <> '?' AND \"what?\" = ?";
+        assert_eq!(
+            number_placeholders(query),
+            "SELECT * FROM users WHERE login = $1 AND info <> '?' AND \"what?\" = $2"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn run_query_with_binds_quotes_safely() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let db = DatabaseQuery::new_sqlite(pool);
+        db.run_query("CREATE TABLE people (id TEXT, login TEXT, age INTEGER)").await.unwrap();
+
+        let params: Vec<DbValue> = vec![Uuid::new_v4().into(), "o'brien".into(), None::<i32>.into()];
+        let inserted = db.run_query_with("INSERT INTO people (id, login, age) VALUES (?, ?, ?)", &params).await.unwrap();
+        assert_eq!(inserted, 1);
+
+        let row = db.fetch_one_with("SELECT login, age FROM people WHERE login = ?", &["o'brien".into()]).await.unwrap();
+        let login: String = row.get("login");
+        let age: Option<i32> = row.get("age");
+        assert_eq!(login, "o'brien");
+        assert_eq!(age, None);
+
+        let missing = db.fetch_optional_with("SELECT login FROM people WHERE login = ?", &["' OR '1'='1".into()]).await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn transaction_commits_or_rolls_back() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let db = DatabaseQuery::new_sqlite(pool);
+        db.run_query("CREATE TABLE people (login TEXT)").await.unwrap();
+        let count = "SELECT COUNT(*) AS count FROM people";
+
+        let failed: Result<()> = db.transaction(|tx| async move {
+            tx.run_query_with("INSERT INTO people (login) VALUES (?)", &["alice".into()]).await?;
+            Err(Error::msg("abort"))
+        }).await;
+        assert!(failed.is_err());
+        assert_eq!(db.fetch_one_with(count, &[]).await.unwrap().get::<i64>("count"), 0);
+
+        db.transaction(|tx| async move {
+            tx.run_query_with("INSERT INTO people (login) VALUES (?)", &["bob".into()]).await?;
+            Ok(())
+        }).await.unwrap();
+        assert_eq!(db.fetch_one_with(count, &[]).await.unwrap().get::<i64>("count"), 1);
+
+        let tx = db.begin().await.unwrap();
+        tx.run_query("DELETE FROM people").await.unwrap();
+        tx.clone().rollback().await.unwrap();
+        assert!(tx.run_query("DELETE FROM people").await.is_err());
+        assert_eq!(db.fetch_one_with(count, &[]).await.unwrap().get::<i64>("count"), 1);
+    }
+}
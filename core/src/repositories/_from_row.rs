@@ -0,0 +1,163 @@
+use std::fmt;
+use sqlx::{Row, ValueRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseRow;
+
+#[cfg(feature = "postgres")]
+use sqlx::postgres::PgRow;
+
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::SqliteRow;
+
+pub use core_derive::FromDatabaseRow;
+
+/// Erreur de lecture d'une ligne, avec le nom de la colonne en cause
+#[derive(Debug)]
+pub struct RowError {
+    pub column: String,
+    pub source: sqlx::Error,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to decode column '{}': {}", self.column, self.source)
+    }
+}
+
+impl std::error::Error for RowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Construit une valeur à partir d'une ligne de résultat
+///
+/// Se dérive avec `#[derive(FromDatabaseRow)]`, voir `core_derive`.
+pub trait FromDatabaseRow: Sized {
+    fn from_row(row: &DatabaseRow) -> Result<Self, RowError>;
+}
+
+/// Lecture d'une colonne, backend par backend
+///
+/// Contrairement à `DatabaseRow::get`, un type n'a pas besoin d'un `Decode` commun aux
+/// deux backends : un UUID est lu nativement sous PostgreSQL et depuis du texte sous SQLite.
+pub trait FromDatabaseValue: Sized {
+    #[cfg(feature = "postgres")]
+    fn from_postgres(row: &PgRow, column: &str) -> Result<Self, sqlx::Error>;
+
+    #[cfg(feature = "sqlite")]
+    fn from_sqlite(row: &SqliteRow, column: &str) -> Result<Self, sqlx::Error>;
+}
+
+/// Types décodés de la même manière par les deux backends
+macro_rules! impl_from_database_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FromDatabaseValue for $ty {
+                #[cfg(feature = "postgres")]
+                fn from_postgres(row: &PgRow, column: &str) -> Result<Self, sqlx::Error> {
+                    row.try_get(column)
+                }
+
+                #[cfg(feature = "sqlite")]
+                fn from_sqlite(row: &SqliteRow, column: &str) -> Result<Self, sqlx::Error> {
+                    row.try_get(column)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_database_value!(bool, i32, i64, f64, String, OffsetDateTime);
+
+impl FromDatabaseValue for Uuid {
+    #[cfg(feature = "postgres")]
+    fn from_postgres(row: &PgRow, column: &str) -> Result<Self, sqlx::Error> {
+        row.try_get(column)
+    }
+
+    /// Texte (format de `DbValue::Uuid`) ou, à défaut, blob de 16 octets (format natif sqlx)
+    #[cfg(feature = "sqlite")]
+    fn from_sqlite(row: &SqliteRow, column: &str) -> Result<Self, sqlx::Error> {
+        match row.try_get::<String, _>(column) {
+            Ok(text) => Uuid::parse_str(&text).map_err(|e| sqlx::Error::ColumnDecode {
+                index: format!("{:?}", column),
+                source: Box::new(e),
+            }),
+            Err(_) => row.try_get(column),
+        }
+    }
+}
+
+impl<T: FromDatabaseValue> FromDatabaseValue for Option<T> {
+    #[cfg(feature = "postgres")]
+    fn from_postgres(row: &PgRow, column: &str) -> Result<Self, sqlx::Error> {
+        if row.try_get_raw(column)?.is_null() {
+            return Ok(None);
+        }
+        T::from_postgres(row, column).map(Some)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn from_sqlite(row: &SqliteRow, column: &str) -> Result<Self, sqlx::Error> {
+        if row.try_get_raw(column)?.is_null() {
+            return Ok(None);
+        }
+        T::from_sqlite(row, column).map(Some)
+    }
+}
+
+impl DatabaseRow {
+    /// Lit une colonne ; en cas d'échec, l'erreur indique la colonne concernée
+    pub fn decode<T: FromDatabaseValue>(&self, column: &str) -> Result<T, RowError> {
+        let result = match self {
+            #[cfg(feature = "postgres")]
+            DatabaseRow::Postgres(row) => T::from_postgres(row, column),
+            #[cfg(feature = "sqlite")]
+            DatabaseRow::Sqlite(row) => T::from_sqlite(row, column),
+            #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+            _ => unreachable!("No database feature enabled"),
+        };
+
+        result.map_err(|source| RowError {
+            column: column.to_string(),
+            source,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::_database::DatabaseQuery;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[derive(FromDatabaseRow, Debug, PartialEq)]
+    struct Person {
+        id: Uuid,
+        r#type: String,
+        #[database(rename = "years")]
+        age: Option<i32>,
+        #[database(default)]
+        note: String,
+    }
+
+    #[tokio::test]
+    async fn derives_row_mapping_and_names_failing_column() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let db = DatabaseQuery::new_sqlite(pool);
+        db.run_query("CREATE TABLE people (id TEXT, type TEXT, years INTEGER)").await.unwrap();
+
+        let id = Uuid::new_v4();
+        db.run_query_with("INSERT INTO people (id, type, years) VALUES (?, ?, ?)", &[id.into(), "admin".into(), None::<i32>.into()]).await.unwrap();
+
+        let row = db.fetch_one_with("SELECT * FROM people", &[]).await.unwrap();
+        let person = Person::from_row(&row).unwrap();
+        assert_eq!(person, Person { id, r#type: "admin".to_string(), age: None, note: String::new() });
+
+        db.run_query("UPDATE people SET id = 'not-a-uuid'").await.unwrap();
+        let row = db.fetch_one_with("SELECT * FROM people", &[]).await.unwrap();
+        assert_eq!(Person::from_row(&row).unwrap_err().column, "id");
+    }
+}
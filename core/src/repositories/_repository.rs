@@ -0,0 +1,420 @@
+use std::marker::PhantomData;
+use anyhow::{Error, Result};
+use serde::{Serialize, Deserialize};
+use time::OffsetDateTime;
+use crate::repositories::_database::{DatabaseQuery, DbValue};
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_schema::quote_identifier;
+
+pub use core_derive::Entity;
+
+/// Table associée à une struct : nom, clé primaire et colonnes
+///
+/// Se dérive avec `#[derive(Entity)]` et `#[database(table = "...")]`.
+pub trait Entity: FromDatabaseRow + Send + Sync {
+    type Id: Into<DbValue> + Send;
+
+    const TABLE: &'static str;
+    const PRIMARY_KEY: &'static str;
+    /// Colonnes lues par `FromDatabaseRow`, seules autorisées pour filtrer et trier
+    const COLUMNS: &'static [&'static str];
+
+    fn id(&self) -> Self::Id;
+
+    /// Colonnes écrites à l'insertion, avec leur valeur
+    fn values(&self) -> Vec<(&'static str, DbValue)>;
+
+    /// Tri appliqué par `Repository::find_where`
+    fn default_sort() -> Sort {
+        Sort::asc(Self::PRIMARY_KEY)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Tri sur une colonne
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sort {
+    pub column: String,
+    pub direction: SortDirection,
+}
+
+impl Sort {
+    pub fn asc(column: &str) -> Self {
+        Self { column: column.to_string(), direction: SortDirection::Asc }
+    }
+
+    pub fn desc(column: &str) -> Self {
+        Self { column: column.to_string(), direction: SortDirection::Desc }
+    }
+
+    /// Lit un tri écrit `colonne` (croissant) ou `-colonne` (décroissant)
+    pub fn parse(value: &str) -> Self {
+        match value.strip_prefix('-') {
+            Some(column) => Self::desc(column),
+            None => Self::asc(value),
+        }
+    }
+
+    fn to_sql(&self, columns: &[&str], table: &str) -> Result<String> {
+        check_column(&self.column, columns, table)?;
+        let direction = match self.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        Ok(format!(" ORDER BY {} {}", quote_identifier(&self.column), direction))
+    }
+}
+
+/// Page demandée, numérotée à partir de 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page {
+    pub number: u32,
+    pub size: u32,
+}
+
+impl Page {
+    pub const MAX_SIZE: u32 = 500;
+
+    /// Ramène le numéro et la taille dans des bornes valides
+    pub fn new(number: u32, size: u32) -> Self {
+        Self {
+            number: number.max(1),
+            size: size.clamp(1, Self::MAX_SIZE),
+        }
+    }
+
+    fn offset(&self) -> i64 {
+        i64::from(self.number - 1) * i64::from(self.size)
+    }
+}
+
+/// Résultat paginé, avec le nombre total d'éléments correspondant
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl<T> Paginated<T> {
+    pub fn total_pages(&self) -> u32 {
+        let per_page = i64::from(self.per_page.max(1));
+        ((self.total + per_page - 1) / per_page) as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    Contains,
+    IsNull,
+    IsNotNull,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Condition {
+    column: String,
+    operator: Operator,
+    value: Option<DbValue>,
+}
+
+/// Conditions combinées par `AND`, construites par chaînage
+///
+/// Les noms de colonnes sont vérifiés contre `Entity::COLUMNS` avant d'être placés
+/// dans le SQL ; les valeurs sont toujours liées en paramètres.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, column: &str, operator: Operator, value: Option<DbValue>) -> Self {
+        self.conditions.push(Condition { column: column.to_string(), operator, value });
+        self
+    }
+
+    pub fn eq(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Eq, Some(value.into()))
+    }
+
+    pub fn ne(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Ne, Some(value.into()))
+    }
+
+    pub fn lt(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Lt, Some(value.into()))
+    }
+
+    pub fn le(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Le, Some(value.into()))
+    }
+
+    pub fn gt(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Gt, Some(value.into()))
+    }
+
+    pub fn ge(self, column: &str, value: impl Into<DbValue>) -> Self {
+        self.push(column, Operator::Ge, Some(value.into()))
+    }
+
+    /// Motif SQL `LIKE` (`%` et `_` comme jokers)
+    pub fn like(self, column: &str, pattern: &str) -> Self {
+        self.push(column, Operator::Like, Some(pattern.into()))
+    }
+
+    /// Recherche insensible à la casse de `text` dans la colonne, quel que soit son type
+    pub fn contains(self, column: &str, text: &str) -> Self {
+        let escaped = text.to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        self.push(column, Operator::Contains, Some(format!("%{}%", escaped).into()))
+    }
+
+    pub fn is_null(self, column: &str) -> Self {
+        self.push(column, Operator::IsNull, None)
+    }
+
+    pub fn is_not_null(self, column: &str) -> Self {
+        self.push(column, Operator::IsNotNull, None)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Clause `WHERE` et ses paramètres, vide si aucune condition
+    fn to_sql(&self, columns: &[&str], table: &str) -> Result<(String, Vec<DbValue>)> {
+        if self.conditions.is_empty() {
+            return Ok((String::new(), Vec::new()));
+        }
+
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        for condition in &self.conditions {
+            check_column(&condition.column, columns, table)?;
+            let column = quote_identifier(&condition.column);
+            clauses.push(match condition.operator {
+                Operator::Eq => format!("{} = ?", column),
+                Operator::Ne => format!("{} <> ?", column),
+                Operator::Lt => format!("{} < ?", column),
+                Operator::Le => format!("{} <= ?", column),
+                Operator::Gt => format!("{} > ?", column),
+                Operator::Ge => format!("{} >= ?", column),
+                Operator::Like => format!("{} LIKE ?", column),
+                Operator::Contains => format!("LOWER(CAST({} AS TEXT)) LIKE ? ESCAPE '\\'", column),
+                Operator::IsNull => format!("{} IS NULL", column),
+                Operator::IsNotNull => format!("{} IS NOT NULL", column),
+            });
+            params.extend(condition.value.clone());
+        }
+
+        Ok((format!(" WHERE {}", clauses.join(" AND ")), params))
+    }
+}
+
+fn check_column(column: &str, columns: &[&str], table: &str) -> Result<()> {
+    if columns.contains(&column) {
+        Ok(())
+    } else {
+        Err(Error::msg(format!("Unknown column '{}' for table '{}'", column, table)))
+    }
+}
+
+/// Repository générique : CRUD, filtrage et pagination pour toute `Entity`
+pub struct Repository<T: Entity> {
+    db: DatabaseQuery,
+    entity: PhantomData<T>,
+}
+
+impl<T: Entity> Clone for Repository<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.db.clone())
+    }
+}
+
+impl<T: Entity> Repository<T> {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { db: db_query, entity: PhantomData }
+    }
+
+    /// Recherche un enregistrement par clé primaire
+    pub async fn find_by_id(&self, id: T::Id) -> Result<Option<T>> {
+        let query = format!("SELECT * FROM {} WHERE {} = ?", T::TABLE, quote_identifier(T::PRIMARY_KEY));
+        self.db.fetch_optional_as(&query, &[id.into()]).await
+    }
+
+    /// Enregistrements correspondant au filtre, dans l'ordre de `Entity::default_sort`
+    pub async fn find_where(&self, filter: &Filter) -> Result<Vec<T>> {
+        let (where_clause, params) = filter.to_sql(T::COLUMNS, T::TABLE)?;
+        let order_by = T::default_sort().to_sql(T::COLUMNS, T::TABLE)?;
+        let query = format!("SELECT * FROM {}{}{}", T::TABLE, where_clause, order_by);
+        self.db.fetch_all_as(&query, &params).await
+    }
+
+    /// Page d'enregistrements triés
+    pub async fn list(&self, page: Page, sort: &Sort) -> Result<Paginated<T>> {
+        self.list_where(&Filter::new(), page, sort).await
+    }
+
+    /// Page d'enregistrements filtrés et triés, avec le total correspondant au filtre
+    pub async fn list_where(&self, filter: &Filter, page: Page, sort: &Sort) -> Result<Paginated<T>> {
+        let (where_clause, mut params) = filter.to_sql(T::COLUMNS, T::TABLE)?;
+        let order_by = sort.to_sql(T::COLUMNS, T::TABLE)?;
+        let total = self.count_where(filter).await?;
+
+        let query = format!("SELECT * FROM {}{}{} LIMIT ? OFFSET ?", T::TABLE, where_clause, order_by);
+        params.push(i64::from(page.size).into());
+        params.push(page.offset().into());
+        let items = self.db.fetch_all_as(&query, &params).await?;
+
+        Ok(Paginated { items, total, page: page.number, per_page: page.size })
+    }
+
+    /// Insère un enregistrement et le relit, colonnes générées comprises
+    pub async fn insert(&self, item: &T) -> Result<T> {
+        let (columns, params): (Vec<&str>, Vec<DbValue>) = item.values().into_iter().unzip();
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({}) RETURNING *",
+            T::TABLE,
+            columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        self.db.fetch_one_as(&query, &params).await
+    }
+
+    /// Insère plusieurs enregistrements en une seule requête, sans les relire ;
+    /// retourne le nombre de lignes insérées
+    pub async fn insert_many(&self, items: &[T]) -> Result<u64> {
+        let Some(first) = items.first() else {
+            return Ok(0);
+        };
+        let columns: Vec<&str> = first.values().into_iter().map(|(column, _)| column).collect();
+        let row = format!("({})", vec!["?"; columns.len()].join(", "));
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            T::TABLE,
+            columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+            vec![row; items.len()].join(", ")
+        );
+        let params: Vec<DbValue> = items.iter().flat_map(|item| item.values().into_iter().map(|(_, value)| value)).collect();
+        self.db.run_query_with(&query, &params).await
+    }
+
+    /// Met à jour toutes les colonnes sauf la clé primaire et `created_at` ;
+    /// `updated_at`, si présent, prend l'heure courante. `None` si l'enregistrement n'existe pas.
+    pub async fn update(&self, item: &T) -> Result<Option<T>> {
+        let mut assignments = Vec::new();
+        let mut params = Vec::new();
+        for (column, value) in item.values() {
+            let value = match column {
+                _ if column == T::PRIMARY_KEY || column == "created_at" => continue,
+                "updated_at" => OffsetDateTime::now_utc().into(),
+                _ => value,
+            };
+            assignments.push(format!("{} = ?", quote_identifier(column)));
+            params.push(value);
+        }
+        params.push(item.id().into());
+
+        let query = format!(
+            "UPDATE {} SET {} WHERE {} = ? RETURNING *",
+            T::TABLE,
+            assignments.join(", "),
+            quote_identifier(T::PRIMARY_KEY)
+        );
+        self.db.fetch_optional_as(&query, &params).await
+    }
+
+    /// Supprime un enregistrement par clé primaire
+    pub async fn delete(&self, id: T::Id) -> Result<bool> {
+        let query = format!("DELETE FROM {} WHERE {} = ?", T::TABLE, quote_identifier(T::PRIMARY_KEY));
+        let rows_affected = self.db.run_query_with(&query, &[id.into()]).await?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Supprime les enregistrements correspondant au filtre et retourne leur nombre
+    pub async fn delete_where(&self, filter: &Filter) -> Result<u64> {
+        let (where_clause, params) = filter.to_sql(T::COLUMNS, T::TABLE)?;
+        let query = format!("DELETE FROM {}{}", T::TABLE, where_clause);
+        self.db.run_query_with(&query, &params).await
+    }
+
+    /// Nombre total d'enregistrements
+    pub async fn count(&self) -> Result<i64> {
+        self.count_where(&Filter::new()).await
+    }
+
+    /// Nombre d'enregistrements correspondant au filtre
+    pub async fn count_where(&self, filter: &Filter) -> Result<i64> {
+        let (where_clause, params) = filter.to_sql(T::COLUMNS, T::TABLE)?;
+        let query = format!("SELECT COUNT(*) AS count FROM {}{}", T::TABLE, where_clause);
+        let row = self.db.fetch_one_with(&query, &params).await?;
+        Ok(row.decode("count")?)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::database;
+    use crate::repositories::user_repository::User;
+    use std::collections::HashMap;
+
+    fn user(login: &str, age: i32) -> User {
+        let fields = HashMap::from([
+            ("login".to_string(), login.to_string()),
+            ("age".to_string(), age.to_string()),
+        ]);
+        User::from_form_fields(&fields, &Vec::new())
+    }
+
+    #[tokio::test]
+    async fn crud_filter_and_pagination() {
+        let users = Repository::<User>::new(database().await);
+        for (login, age) in [("alice", 31), ("bob", 25), ("carol", 42)] {
+            users.insert(&user(login, age)).await.unwrap();
+        }
+
+        assert_eq!(users.count().await.unwrap(), 3);
+        let adults = users.find_where(&Filter::new().ge("age", 30)).await.unwrap();
+        assert_eq!(adults.len(), 2);
+        assert_eq!(users.count_where(&Filter::new().contains("login", "AR")).await.unwrap(), 1);
+        assert_eq!(users.count_where(&Filter::new().contains("age", "2")).await.unwrap(), 2);
+        assert_eq!(users.count_where(&Filter::new().contains("login", "%")).await.unwrap(), 0);
+
+        let page = users.list(Page::new(2, 2), &Sort::desc("age")).await.unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.total_pages(), 2);
+        assert_eq!(page.items.iter().map(|u| u.age).collect::<Vec<_>>(), vec![Some(25)]);
+
+        let mut bob = users.find_where(&Filter::new().eq("login", "bob")).await.unwrap().remove(0);
+        bob.email = Some("bob@example.com".to_string());
+        let updated = users.update(&bob).await.unwrap().unwrap();
+        assert_eq!(updated.email.as_deref(), Some("bob@example.com"));
+        assert_eq!(users.find_by_id(bob.id).await.unwrap().unwrap().email, updated.email);
+
+        assert!(users.delete(bob.id).await.unwrap());
+        assert!(!users.delete(bob.id).await.unwrap());
+        assert!(users.update(&bob).await.unwrap().is_none());
+        assert!(users.find_where(&Filter::new().eq("login; DROP TABLE users", "x")).await.is_err());
+    }
+
+}
@@ -0,0 +1,353 @@
+use crate::repositories::_database::Dialect;
+
+/// Types de colonnes portables entre PostgreSQL et SQLite
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnType {
+    /// Entier auto-incrémenté, toujours clé primaire
+    Serial,
+    Uuid,
+    Text,
+    Integer,
+    BigInt,
+    Boolean,
+    Double,
+    Timestamp,
+}
+
+/// Valeur par défaut d'une colonne
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefaultValue {
+    /// Horodatage courant au moment de l'insertion
+    Now,
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+/// Action appliquée aux lignes enfants lors de la suppression du parent
+#[derive(Clone, Debug, PartialEq)]
+pub enum OnDelete {
+    Cascade,
+    SetNull,
+    Restrict,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+    pub on_delete: Option<OnDelete>,
+}
+
+/// Définition d'une colonne, construite par chaînage
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+    pub primary_key: bool,
+    pub unique: bool,
+    pub default: Option<DefaultValue>,
+    pub references: Option<ForeignKey>,
+}
+
+impl Column {
+    pub fn new(name: &str, column_type: ColumnType) -> Self {
+        Self {
+            name: name.to_string(),
+            column_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            default: None,
+            references: None,
+        }
+    }
+
+    pub fn serial(name: &str) -> Self {
+        Self::new(name, ColumnType::Serial).primary_key()
+    }
+
+    pub fn uuid(name: &str) -> Self {
+        Self::new(name, ColumnType::Uuid)
+    }
+
+    pub fn text(name: &str) -> Self {
+        Self::new(name, ColumnType::Text)
+    }
+
+    pub fn integer(name: &str) -> Self {
+        Self::new(name, ColumnType::Integer)
+    }
+
+    pub fn big_int(name: &str) -> Self {
+        Self::new(name, ColumnType::BigInt)
+    }
+
+    pub fn boolean(name: &str) -> Self {
+        Self::new(name, ColumnType::Boolean)
+    }
+
+    pub fn double(name: &str) -> Self {
+        Self::new(name, ColumnType::Double)
+    }
+
+    pub fn timestamp(name: &str) -> Self {
+        Self::new(name, ColumnType::Timestamp)
+    }
+
+    pub fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self.nullable = false;
+        self
+    }
+
+    pub fn not_null(mut self) -> Self {
+        self.nullable = false;
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    pub fn default(mut self, value: DefaultValue) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Raccourci pour les colonnes `created_at` / `updated_at`
+    pub fn default_now(self) -> Self {
+        self.default(DefaultValue::Now)
+    }
+
+    pub fn references(mut self, table: &str, column: &str) -> Self {
+        self.references = Some(ForeignKey {
+            table: table.to_string(),
+            column: column.to_string(),
+            on_delete: None,
+        });
+        self
+    }
+
+    pub fn on_delete(mut self, action: OnDelete) -> Self {
+        if let Some(foreign_key) = self.references.as_mut() {
+            foreign_key.on_delete = Some(action);
+        }
+        self
+    }
+
+    /// Génère la définition SQL de la colonne pour le dialecte donné
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let mut sql = format!("{} {}", quote_identifier(&self.name), self.sql_type(dialect));
+
+        if self.primary_key {
+            sql.push_str(" PRIMARY KEY");
+            if self.column_type == ColumnType::Serial && dialect == Dialect::Sqlite {
+                sql.push_str(" AUTOINCREMENT");
+            }
+        }
+        if !self.nullable && !self.primary_key {
+            sql.push_str(" NOT NULL");
+        }
+        if self.unique && !self.primary_key {
+            sql.push_str(" UNIQUE");
+        }
+        if let Some(default) = &self.default {
+            sql.push_str(" DEFAULT ");
+            sql.push_str(&default_sql(default, dialect));
+        }
+        if let Some(foreign_key) = &self.references {
+            sql.push_str(&format!(" REFERENCES {}({})", foreign_key.table, foreign_key.column));
+            match foreign_key.on_delete {
+                Some(OnDelete::Cascade) => sql.push_str(" ON DELETE CASCADE"),
+                Some(OnDelete::SetNull) => sql.push_str(" ON DELETE SET NULL"),
+                Some(OnDelete::Restrict) => sql.push_str(" ON DELETE RESTRICT"),
+                None => {}
+            }
+        }
+
+        sql
+    }
+
+    fn sql_type(&self, dialect: Dialect) -> &'static str {
+        match (dialect, &self.column_type) {
+            (Dialect::Postgres, ColumnType::Serial) => "SERIAL",
+            (Dialect::Sqlite, ColumnType::Serial) => "INTEGER",
+            (Dialect::Postgres, ColumnType::Uuid) => "UUID",
+            // SQLite n'a pas de type UUID : stockage texte, cohérent avec DbValue::Uuid
+            (Dialect::Sqlite, ColumnType::Uuid) => "TEXT",
+            (_, ColumnType::Text) => "TEXT",
+            (_, ColumnType::Integer) => "INTEGER",
+            (_, ColumnType::BigInt) => "BIGINT",
+            (_, ColumnType::Boolean) => "BOOLEAN",
+            (Dialect::Postgres, ColumnType::Double) => "DOUBLE PRECISION",
+            (Dialect::Sqlite, ColumnType::Double) => "REAL",
+            (Dialect::Postgres, ColumnType::Timestamp) => "TIMESTAMPTZ",
+            // Texte RFC 3339, le format qu'utilise sqlx pour OffsetDateTime
+            (Dialect::Sqlite, ColumnType::Timestamp) => "TEXT",
+        }
+    }
+}
+
+/// Index secondaire d'une table
+#[derive(Clone, Debug, PartialEq)]
+pub struct Index {
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+impl Index {
+    pub fn name(&self, table: &str) -> String {
+        format!("idx_{}_{}", table, self.columns.join("_"))
+    }
+}
+
+/// Définition complète d'une table : colonnes et index
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub indexes: Vec<Index>,
+}
+
+impl TableSchema {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: Vec::new(),
+            indexes: Vec::new(),
+        }
+    }
+
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Ajoute les colonnes `created_at` et `updated_at` horodatées à l'insertion
+    pub fn timestamps(self) -> Self {
+        self.column(Column::timestamp("created_at").not_null().default_now())
+            .column(Column::timestamp("updated_at").not_null().default_now())
+    }
+
+    pub fn index(mut self, columns: &[&str]) -> Self {
+        self.indexes.push(Index {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique: false,
+        });
+        self
+    }
+
+    pub fn unique_index(mut self, columns: &[&str]) -> Self {
+        self.indexes.push(Index {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique: true,
+        });
+        self
+    }
+
+    /// Raccourci : un index simple par colonne listée
+    pub fn indexes(self, columns: &[&str]) -> Self {
+        columns.iter().fold(self, |schema, column| schema.index(&[column]))
+    }
+
+    pub fn create_table_sql(&self, dialect: Dialect) -> String {
+        let columns: Vec<String> = self.columns.iter()
+            .map(|column| column.to_sql(dialect))
+            .collect();
+
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            self.name,
+            columns.join(", ")
+        )
+    }
+
+    pub fn create_indexes_sql(&self) -> Vec<String> {
+        self.indexes.iter()
+            .map(|index| format!(
+                "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+                if index.unique { "UNIQUE " } else { "" },
+                index.name(&self.name),
+                self.name,
+                index.columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+            ))
+            .collect()
+    }
+
+    pub fn drop_indexes_sql(&self) -> Vec<String> {
+        self.indexes.iter()
+            .map(|index| format!("DROP INDEX IF EXISTS {}", index.name(&self.name)))
+            .collect()
+    }
+}
+
+fn default_sql(default: &DefaultValue, dialect: Dialect) -> String {
+    match (default, dialect) {
+        (DefaultValue::Now, Dialect::Postgres) => "NOW()".to_string(),
+        (DefaultValue::Now, Dialect::Sqlite) => "(strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))".to_string(),
+        (DefaultValue::Bool(value), _) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+        (DefaultValue::Int(value), _) => value.to_string(),
+        (DefaultValue::Text(value), _) => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+/// Met entre guillemets les noms réservés (`type`, `order`...) pour les deux dialectes
+pub(crate) fn quote_identifier(name: &str) -> String {
+    const RESERVED: &[&str] = &["type", "order", "group", "user", "key", "value", "references"];
+    if RESERVED.contains(&name.to_lowercase().as_str()) {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TableSchema {
+        TableSchema::new("files")
+            .column(Column::uuid("id").primary_key())
+            .column(Column::uuid("owner_id").references("users", "id").on_delete(OnDelete::Cascade))
+            .column(Column::text("name").not_null().unique())
+            .column(Column::boolean("public").not_null().default(DefaultValue::Bool(false)))
+            .timestamps()
+            .index(&["owner_id"])
+    }
+
+    #[test]
+    fn renders_postgres_ddl() {
+        assert_eq!(
+            sample().create_table_sql(Dialect::Postgres),
+            "CREATE TABLE IF NOT EXISTS files (id UUID PRIMARY KEY, owner_id UUID REFERENCES users(id) ON DELETE CASCADE, \
+             name TEXT NOT NULL UNIQUE, public BOOLEAN NOT NULL DEFAULT FALSE, \
+             created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW())"
+        );
+    }
+
+    #[test]
+    fn renders_sqlite_ddl() {
+        let sql = sample().create_table_sql(Dialect::Sqlite);
+        assert!(sql.contains("id TEXT PRIMARY KEY"));
+        assert!(sql.contains("created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))"));
+        assert_eq!(
+            Column::serial("id").to_sql(Dialect::Sqlite),
+            "id INTEGER PRIMARY KEY AUTOINCREMENT"
+        );
+    }
+
+    #[test]
+    fn renders_indexes() {
+        assert_eq!(
+            sample().create_indexes_sql(),
+            vec!["CREATE INDEX IF NOT EXISTS idx_files_owner_id ON files (owner_id)".to_string()]
+        );
+        assert_eq!(
+            sample().drop_indexes_sql(),
+            vec!["DROP INDEX IF EXISTS idx_files_owner_id".to_string()]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+
+/// Clé d'API d'un client machine (Android, ESP8266, scripts), rattachée à un utilisateur
+///
+/// Seule l'empreinte SHA-256 de la clé est stockée ; `prefix` permet de la reconnaître
+/// dans les listes et les journaux sans la révéler.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "api_keys", sort = "-created_at")]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    /// Portées séparées par des virgules (`read`, `write`)
+    pub scopes: String,
+    pub expires_at: Option<OffsetDateTime>,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+impl ApiKey {
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.split(',').map(str::trim).filter(|scope| !scope.is_empty())
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().any(|s| s == scope)
+    }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+pub struct ApiKeyRepository {
+    db: DatabaseQuery,
+    keys: Repository<ApiKey>,
+}
+
+impl ApiKeyRepository {
+    /// Intervalle minimal entre deux mises à jour de `last_used_at`, en secondes
+    pub const TOUCH_INTERVAL: i64 = 60;
+
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { keys: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    pub async fn create(&self, key: &ApiKey) -> Result<ApiKey> {
+        self.keys.insert(key).await
+    }
+
+    /// Clé correspondant à l'empreinte, révoquée ou expirée comprise
+    pub async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        Ok(self.keys.find_where(&Filter::new().eq("key_hash", key_hash)).await?.into_iter().next())
+    }
+
+    /// Toutes les clés, les plus récentes d'abord
+    pub async fn list(&self) -> Result<Vec<ApiKey>> {
+        self.keys.find_where(&Filter::new()).await
+    }
+
+    /// Révoque la clé désignée par son ID ou son préfixe ; nombre de clés révoquées
+    pub async fn revoke(&self, id_or_prefix: &str) -> Result<u64> {
+        let now = OffsetDateTime::now_utc();
+        match Uuid::parse_str(id_or_prefix) {
+            Ok(id) => self.db.run_query_with("UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL", &[now.into(), id.into()]).await,
+            Err(_) => self.db.run_query_with("UPDATE api_keys SET revoked_at = ? WHERE prefix = ? AND revoked_at IS NULL", &[now.into(), id_or_prefix.into()]).await,
+        }
+    }
+
+    /// Enregistre l'utilisation de la clé, au plus une écriture par `TOUCH_INTERVAL`
+    pub async fn touch(&self, id: Uuid) -> Result<()> {
+        let now = OffsetDateTime::now_utc();
+        let query = "UPDATE api_keys SET last_used_at = ? WHERE id = ? AND (last_used_at IS NULL OR last_used_at < ?)";
+        self.db.run_query_with(query, &[now.into(), id.into(), (now - time::Duration::seconds(Self::TOUCH_INTERVAL)).into()]).await?;
+        Ok(())
+    }
+}
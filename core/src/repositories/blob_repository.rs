@@ -0,0 +1,104 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use time::OffsetDateTime;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+
+/// Contenu stocké une seule fois, identifié par son SHA-256
+///
+/// `ref_count` compte les lignes de `files` qui y renvoient ; le contenu est supprimé
+/// quand la dernière référence disparaît.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "blobs", sort = "-created_at")]
+pub struct Blob {
+    #[database(primary_key)]
+    pub sha256: String,
+    pub size: i64,
+    pub ref_count: i32,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+/// Clé de verrou (`DatabaseTransaction::lock`) protégeant un contenu pendant son écriture ou sa suppression
+pub fn blob_lock_key(sha256: &str) -> String {
+    format!("blob:{}", sha256)
+}
+
+pub struct BlobRepository {
+    db: DatabaseQuery,
+    blobs: Repository<Blob>,
+}
+
+impl BlobRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { blobs: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    pub async fn get_blob(&self, sha256: &str) -> Result<Option<Blob>> {
+        self.blobs.find_by_id(sha256.to_string()).await
+    }
+
+    /// Ajoute une référence au contenu, en le créant au besoin ; `true` s'il est nouveau
+    ///
+    /// À appeler dans une transaction verrouillée sur `blob_lock_key`.
+    pub async fn acquire(&self, sha256: &str, size: u64) -> Result<bool> {
+        match self.get_blob(sha256).await? {
+            Some(blob) => {
+                self.blobs.update(&Blob { ref_count: blob.ref_count + 1, ..blob }).await?;
+                Ok(false)
+            },
+            None => {
+                let now = OffsetDateTime::now_utc();
+                self.blobs.insert(&Blob {
+                    sha256: sha256.to_string(),
+                    size: size as i64,
+                    ref_count: 1,
+                    created_at: now,
+                    updated_at: now,
+                }).await?;
+                Ok(true)
+            },
+        }
+    }
+
+    /// Retire une référence ; `true` si c'était la dernière, l'enregistrement est alors supprimé
+    ///
+    /// À appeler dans une transaction verrouillée sur `blob_lock_key`.
+    pub async fn release(&self, sha256: &str) -> Result<bool> {
+        match self.get_blob(sha256).await? {
+            Some(blob) if blob.ref_count > 1 => {
+                self.blobs.update(&Blob { ref_count: blob.ref_count - 1, ..blob }).await?;
+                Ok(false)
+            },
+            Some(_) => self.blobs.delete(sha256.to_string()).await,
+            None => Ok(true),
+        }
+    }
+
+    /// Recalcule les compteurs à partir de la table `files` et retourne le nombre de compteurs corrigés
+    pub async fn recount(&self) -> Result<u64> {
+        let query = "UPDATE blobs SET ref_count = (SELECT COUNT(*) FROM files WHERE files.sha256 = blobs.sha256) \
+                     WHERE ref_count <> (SELECT COUNT(*) FROM files WHERE files.sha256 = blobs.sha256)";
+        self.db.run_query_with(query, &[]).await
+    }
+
+    /// Contenus qui ne sont plus référencés
+    pub async fn unreferenced(&self) -> Result<Vec<Blob>> {
+        self.blobs.find_where(&Filter::new().le("ref_count", 0)).await
+    }
+
+    /// Supprime l'enregistrement d'un contenu s'il n'est toujours pas référencé
+    pub async fn delete_unreferenced(&self, sha256: &str) -> Result<bool> {
+        let query = "DELETE FROM blobs WHERE sha256 = ? AND ref_count <= 0";
+        Ok(self.db.run_query_with(query, &[sha256.into()]).await? > 0)
+    }
+
+    /// Empreintes de tous les contenus enregistrés
+    pub async fn digests(&self) -> Result<Vec<String>> {
+        Ok(self.blobs.find_where(&Filter::new()).await?
+            .into_iter()
+            .map(|blob| blob.sha256)
+            .collect())
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use std::future::Future;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+use crate::repositories::blob_repository::{blob_lock_key, BlobRepository};
+
+/// Fichier uploadé : métadonnées, le contenu restant dans le stockage sous la clé `stored_path`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "files", sort = "-created_at")]
+pub struct StoredFile {
+    pub id: Uuid,
+    /// Propriétaire, `None` si l'utilisateur n'a pas pu être enregistré ou a été supprimé
+    pub user_id: Option<Uuid>,
+    /// Nom envoyé par le client, restitué au téléchargement
+    pub original_name: String,
+    pub stored_path: String,
+    pub size: i64,
+    pub mime: String,
+    /// Empreinte SHA-256 du contenu, en hexadécimal ; sert aussi d'ETag
+    pub sha256: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl StoredFile {
+    pub fn new(original_name: &str, stored_path: &str, size: u64, mime: &str, sha256: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id: None,
+            original_name: original_name.to_string(),
+            stored_path: stored_path.to_string(),
+            size: size as i64,
+            mime: mime.to_string(),
+            sha256: sha256.to_string(),
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    pub fn with_owner(mut self, user_id: Option<Uuid>) -> Self {
+        self.user_id = user_id;
+        self
+    }
+}
+
+pub struct FileRepository {
+    db: DatabaseQuery,
+    files: Repository<StoredFile>,
+}
+
+impl FileRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { files: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    /// Enregistre un fichier et ajoute une référence à son contenu
+    pub async fn create_file(&self, file: &StoredFile) -> Result<StoredFile> {
+        self.create_file_with(file, || async { Ok(()) }).await
+    }
+
+    /// Comme `create_file`, `write` étant appelé d'abord pour placer le contenu
+    ///
+    /// Le tout se fait dans une transaction verrouillée sur le contenu : une suppression
+    /// concurrente du même contenu ne peut pas l'effacer entre son écriture et sa référence.
+    pub async fn create_file_with<F, Fut>(&self, file: &StoredFile, write: F) -> Result<StoredFile>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let file = file.clone();
+        self.db.transaction(|tx| async move {
+            tx.lock(&blob_lock_key(&file.sha256)).await?;
+            write().await?;
+
+            BlobRepository::new(tx.as_query()).acquire(&file.sha256, file.size as u64).await?;
+            FileRepository::new(tx.as_query()).files.insert(&file).await
+        }).await
+    }
+
+    /// Récupère un fichier par ID
+    pub async fn get_file(&self, id: Uuid) -> Result<Option<StoredFile>> {
+        self.files.find_by_id(id).await
+    }
+
+    /// Fichiers d'un utilisateur, du plus récent au plus ancien
+    pub async fn files_for_user(&self, user_id: Uuid) -> Result<Vec<StoredFile>> {
+        self.files.find_where(&Filter::new().eq("user_id", user_id)).await
+    }
+
+    /// Supprime l'enregistrement d'un fichier et retire sa référence au contenu ;
+    /// un contenu devenu orphelin reste dans le stockage jusqu'au prochain `storage gc`
+    pub async fn delete_file(&self, id: Uuid) -> Result<bool> {
+        Ok(self.delete_file_with(id, |_| async { Ok(()) }).await?.is_some())
+    }
+
+    /// Comme `delete_file`, `erase` étant appelé si c'était la dernière référence au contenu
+    ///
+    /// Retourne le fichier supprimé, `None` s'il n'existait pas. Si `erase` échoue,
+    /// rien n'est supprimé.
+    pub async fn delete_file_with<F, Fut>(&self, id: Uuid, erase: F) -> Result<Option<StoredFile>>
+    where
+        F: FnOnce(StoredFile) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let Some(file) = self.get_file(id).await? else {
+            return Ok(None);
+        };
+
+        self.db.transaction(|tx| async move {
+            tx.lock(&blob_lock_key(&file.sha256)).await?;
+            if !FileRepository::new(tx.as_query()).files.delete(file.id).await? {
+                return Ok(None);
+            }
+
+            if BlobRepository::new(tx.as_query()).release(&file.sha256).await? {
+                erase(file.clone()).await?;
+            }
+            Ok(Some(file))
+        }).await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::repositories::test_support::{database, user};
+
+    #[tokio::test]
+    async fn files_share_reference_counted_blobs() {
+        let db = database().await;
+        let alice = user(&db, "alice").await;
+
+        let files = FileRepository::new(db.clone());
+        let blobs = BlobRepository::new(db);
+        let report = StoredFile::new("rapport final.pdf", "blobs/ab/12/ab12", 2048, "application/pdf", "ab12")
+            .with_owner(Some(alice.id));
+        let copy = StoredFile::new("copie.pdf", "blobs/ab/12/ab12", 2048, "application/pdf", "ab12");
+        files.create_file(&report).await.unwrap();
+        files.create_file(&copy).await.unwrap();
+
+        assert_eq!(files.get_file(report.id).await.unwrap().unwrap().original_name, "rapport final.pdf");
+        assert_eq!(files.files_for_user(alice.id).await.unwrap(), vec![report.clone()]);
+        assert_eq!(blobs.get_blob("ab12").await.unwrap().unwrap().ref_count, 2);
+
+        // Le contenu n'est effacé qu'avec sa dernière référence
+        let erased = AtomicUsize::new(0);
+        let erase = |_: StoredFile| async { erased.fetch_add(1, Ordering::SeqCst); Ok(()) };
+        assert!(files.delete_file_with(report.id, erase).await.unwrap().is_some());
+        assert_eq!(erased.load(Ordering::SeqCst), 0);
+        assert!(files.delete_file_with(copy.id, erase).await.unwrap().is_some());
+        assert_eq!(erased.load(Ordering::SeqCst), 1);
+        assert!(blobs.get_blob("ab12").await.unwrap().is_none());
+        assert!(files.delete_file_with(copy.id, erase).await.unwrap().is_none());
+
+        // Compteurs faussés : `recount` les réaligne sur la table files
+        files.create_file(&copy).await.unwrap();
+        blobs.acquire("ab12", 2048).await.unwrap();
+        blobs.acquire("ffff", 1).await.unwrap();
+        assert_eq!(blobs.recount().await.unwrap(), 2);
+        assert_eq!(blobs.get_blob("ab12").await.unwrap().unwrap().ref_count, 1);
+        assert_eq!(blobs.unreferenced().await.unwrap().iter().map(|b| b.sha256.as_str()).collect::<Vec<_>>(), vec!["ffff"]);
+    }
+}
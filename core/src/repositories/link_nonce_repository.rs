@@ -0,0 +1,36 @@
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+
+/// Jetons des liens de téléchargement à usage unique
+///
+/// Un jeton est enregistré à sa première utilisation ; il peut être oublié une fois le lien
+/// expiré, la signature suffisant alors à refuser le lien.
+pub struct LinkNonceRepository {
+    db: DatabaseQuery,
+}
+
+impl LinkNonceRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { db: db_query }
+    }
+
+    /// Le jeton a-t-il déjà servi
+    pub async fn is_used(&self, nonce: &str) -> Result<bool> {
+        Ok(self.db.fetch_optional_with("SELECT nonce FROM link_nonces WHERE nonce = ?", &[nonce.into()]).await?.is_some())
+    }
+
+    /// Marque le jeton comme utilisé ; `false` s'il l'était déjà
+    pub async fn consume(&self, nonce: &str, file_id: Uuid, expires_at: OffsetDateTime) -> Result<bool> {
+        let query = "INSERT INTO link_nonces (nonce, file_id, expires_at, used_at) VALUES (?, ?, ?, ?) \
+                     ON CONFLICT (nonce) DO NOTHING";
+        let params = [nonce.into(), file_id.into(), expires_at.into(), OffsetDateTime::now_utc().into()];
+        Ok(self.db.run_query_with(query, &params).await? > 0)
+    }
+
+    /// Supprime les jetons des liens expirés et retourne leur nombre
+    pub async fn purge_expired(&self) -> Result<u64> {
+        self.db.run_query_with("DELETE FROM link_nonces WHERE expires_at < ?", &[OffsetDateTime::now_utc().into()]).await
+    }
+}
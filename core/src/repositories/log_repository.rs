@@ -1,11 +1,13 @@
-use sqlx::Row;
 use serde::{Serialize, Deserialize};
 use anyhow::Result;
 use time::OffsetDateTime;
 use uuid::Uuid;
 use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "logs", sort = "-created_at")]
 pub struct Log {
     pub id: Uuid,
     pub r#type: String,        
@@ -43,7 +45,7 @@ impl LogLevel {
 }
 
 pub struct LogRepository {
-    db: DatabaseQuery,
+    logs: Repository<Log>,
 }
 
 impl Log {
@@ -84,23 +86,17 @@ impl Log {
 
 impl LogRepository {
     pub fn new(db_query: DatabaseQuery) -> Self {
-        Self { db: db_query }
+        Self { logs: Repository::new(db_query) }
     }
 
     /// Crée un nouveau log
     pub async fn create_log(&self, log: &Log) -> Result<Log> {
-        let query = format!(
-            "INSERT INTO logs (id, type, level, message, context, created_at, updated_at) VALUES ('{}', '{}', {}, '{}', '{}', '{}', '{}')",
-            log.id,
-            log.r#type,
-            log.level,
-            log.message,
-            log.context.as_ref().unwrap_or(&"".to_string()),
-            log.created_at,
-            log.updated_at
-        );
-        self.db.run_query(&query).await?;
-        Ok(log.clone())
+        self.logs.insert(log).await
+    }
+
+    /// Enregistre un lot de logs en une seule requête et retourne leur nombre
+    pub async fn create_logs(&self, logs: &[Log]) -> Result<u64> {
+        self.logs.insert_many(logs).await
     }
 
     /// Méthode utilitaire pour log d'information
@@ -129,116 +125,67 @@ impl LogRepository {
 
     /// Récupère un log par ID
     pub async fn get_log_by_id(&self, id: Uuid) -> Result<Option<Log>> {
-        let query = format!("SELECT * FROM logs WHERE id = '{}'", id);
-        match self.db.run_query_fetch_optional(&query).await? {
-            Some(row) => {
-                let log = Log {
-                    id: row.get("id"),
-                    r#type: row.get("type"),
-                    level: row.get("level"),
-                    message: row.get("message"),
-                    context: row.get("context"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                };
-                Ok(Some(log))
-            },
-            None => Ok(None)
-        }
+        self.logs.find_by_id(id).await
     }
 
     /// Récupère tous les logs (sans filtrage)
     pub async fn get_all_logs(&self) -> Result<Vec<Log>> {
-        let query = "SELECT * FROM logs ORDER BY created_at DESC";
-        let rows = self.db.run_query_fetch_all(query).await?;
-        
-        let mut logs = Vec::new();
-        for row in rows {
-            let log = Log {
-                id: row.get("id"),
-                r#type: row.get("type"),
-                level: row.get("level"),
-                message: row.get("message"),
-                context: row.get("context"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            };
-            logs.push(log);
-        }
-        Ok(logs)
+        self.logs.find_where(&Filter::new()).await
     }
 
     /// Récupère les logs par type
     pub async fn get_logs_by_type(&self, log_type: &str) -> Result<Vec<Log>> {
-        let query = format!("SELECT * FROM logs WHERE type = '{}' ORDER BY created_at DESC", log_type);
-        let rows = self.db.run_query_fetch_all(&query).await?;
-        
-        let mut logs = Vec::new();
-        for row in rows {
-            let log = Log {
-                id: row.get("id"),
-                r#type: row.get("type"),
-                level: row.get("level"),
-                message: row.get("message"),
-                context: row.get("context"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            };
-            logs.push(log);
-        }
-        Ok(logs)
+        self.logs.find_where(&Filter::new().eq("type", log_type)).await
     }
 
     /// Récupère les logs par niveau
     pub async fn get_logs_by_level(&self, level: LogLevel) -> Result<Vec<Log>> {
-        let query = format!("SELECT * FROM logs WHERE level = {} ORDER BY created_at DESC", level.as_i32());
-        let rows = self.db.run_query_fetch_all(&query).await?;
-        
-        let mut logs = Vec::new();
-        for row in rows {
-            let log = Log {
-                id: row.get("id"),
-                r#type: row.get("type"),
-                level: row.get("level"),
-                message: row.get("message"),
-                context: row.get("context"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            };
-            logs.push(log);
-        }
-        Ok(logs)
+        self.logs.find_where(&Filter::new().eq("level", level.as_i32())).await
     }
 
     /// Supprime un log par ID
     pub async fn delete_log(&self, id: Uuid) -> Result<bool> {
-        let query = format!("DELETE FROM logs WHERE id = '{}'", id);
-        match self.db.run_query(&query).await {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false)
-        }
+        self.logs.delete(id).await
     }
 
     /// Supprime les logs plus anciens qu'une date donnée
     pub async fn cleanup_old_logs(&self, before_date: OffsetDateTime) -> Result<u64> {
-        let query = format!("DELETE FROM logs WHERE created_at < '{}'", before_date);
-        self.db.run_query(&query).await?;
-        Ok(1) // retourne 1 pour simuler le nombre de lignes supprimées
+        self.logs.delete_where(&Filter::new().lt("created_at", before_date)).await
     }
 
     /// Compte le nombre de logs par type
     pub async fn count_logs_by_type(&self, log_type: &str) -> Result<i64> {
-        let query = format!("SELECT COUNT(*) as count FROM logs WHERE type = '{}'", log_type);
-        let row = self.db.run_query_fetch_one(&query).await?;
-        let count: i64 = row.get("count");
-        Ok(count)
+        self.logs.count_where(&Filter::new().eq("type", log_type)).await
     }
 
     /// Compte le nombre total de logs
     pub async fn count_all_logs(&self) -> Result<i64> {
-        let query = "SELECT COUNT(*) as count FROM logs";
-        let row = self.db.run_query_fetch_one(query).await?;
-        let count: i64 = row.get("count");
-        Ok(count)
+        self.logs.count().await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::migrations::{migration_create_logs, migration_logs_type};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn writes_typed_logs_in_batches() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let db = DatabaseQuery::new_sqlite(pool);
+        migration_create_logs::migrate(&db).await.unwrap();
+        db.run_query_with("INSERT INTO logs (id, level, message, created_at, updated_at) VALUES (?, 2, 'before', ?, ?)",
+            &[Uuid::new_v4().into(), OffsetDateTime::now_utc().into(), OffsetDateTime::now_utc().into()]).await.unwrap();
+        migration_logs_type::migrate(&db).await.unwrap();
+
+        let logs = LogRepository::new(db);
+        let batch: Vec<Log> = (0..3).map(|i| Log::info("request", &format!("GET /api/ping {}", i))).collect();
+        assert_eq!(logs.create_logs(&batch).await.unwrap(), 3);
+        assert_eq!(logs.create_logs(&[]).await.unwrap(), 0);
+
+        assert_eq!(logs.count_logs_by_type("request").await.unwrap(), 3);
+        // Les lignes antérieures à la colonne reçoivent le type par défaut
+        assert_eq!(logs.get_logs_by_type("app").await.unwrap()[0].message, "before");
     }
 }
@@ -1,20 +1,30 @@
-use sqlx::Row;
 use serde::{Serialize, Deserialize};
 use anyhow::Result;
 use time::OffsetDateTime;
-use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_database::{DatabaseQuery, DbValue};
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+use crate::repositories::_schema::{Column, DefaultValue, TableSchema};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+const TABLE: &str = "migration";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "migration", sort = "-created_at")]
 pub struct Migration {
+    #[database(generated)]
     pub id: i32,
+    pub version: Option<i64>,
     pub name: String,
     pub description: Option<String>,
+    pub checksum: Option<String>,
+    pub dirty: bool,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
 
 pub struct MigrationRepository {
     db: DatabaseQuery,
+    migrations: Repository<Migration>,
 }
 
 impl Migration {
@@ -22,107 +32,113 @@ impl Migration {
         let now = OffsetDateTime::now_utc();
         Self {
             id: 0, // La valeur sera générée par PostgreSQL avec SERIAL
+            version: None,
             name: name.to_string(),
             description: description.map(|s| s.to_string()),
+            checksum: None,
+            dirty: false,
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Crée un enregistrement pour une migration versionnée du registre
+    pub fn versioned(version: i64, name: &str, description: Option<&str>, checksum: &str) -> Self {
+        Self {
+            version: Some(version),
+            checksum: Some(checksum.to_string()),
+            ..Self::new(name, description)
+        }
+    }
 }
 
 impl MigrationRepository {
     pub fn new(db_query: DatabaseQuery) -> Self {
-        Self { db: db_query }
+        Self { migrations: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    /// Crée la table de suivi des migrations et ajoute les colonnes de versionnement
+    /// si la table provient d'une ancienne installation
+    pub async fn create_table(&self) -> Result<()> {
+        let schema = TableSchema::new(TABLE)
+            .column(Column::serial("id"))
+            .column(Column::big_int("version").unique())
+            .column(Column::text("name").not_null().unique())
+            .column(Column::text("description"))
+            .column(Column::text("checksum"))
+            .column(Column::boolean("dirty").not_null().default(DefaultValue::Bool(false)))
+            .timestamps()
+            .index(&["created_at"]);
+        self.db.create_tables(&schema).await?;
+        self.db.create_indexes(&schema).await?;
+
+        let legacy_columns = [
+            Column::big_int("version"),
+            Column::text("checksum"),
+            Column::boolean("dirty").not_null().default(DefaultValue::Bool(false)),
+        ];
+        for column in legacy_columns {
+            let probe = format!("SELECT {} FROM {} LIMIT 1", column.name, TABLE);
+            if self.db.fetch_all_with(&probe, &[]).await.is_err() {
+                self.db.add_column(TABLE, &column).await?;
+            }
+        }
+
+        Ok(())
     }
 
     /// Recherche un enregistrement par nom
     pub async fn find_by_name(&self, name: &str) -> Result<Option<Migration>> {
-        let query = format!("SELECT * FROM migration WHERE name = '{}'", name);
-        match self.db.run_query_fetch_optional(&query).await? {
-            Some(row) => {
-                let migration = Migration {
-                    id: row.get("id"),
-                    name: row.get("name"),
-                    description: row.get("description"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                };
-                Ok(Some(migration))
-            },
-            None => Ok(None)
-        }
+        Ok(self.migrations.find_where(&Filter::new().eq("name", name)).await?.pop())
+    }
+
+    /// Recherche un enregistrement par version
+    pub async fn find_by_version(&self, version: i64) -> Result<Option<Migration>> {
+        Ok(self.migrations.find_where(&Filter::new().eq("version", version)).await?.pop())
     }
 
     /// Crée un nouvel enregistrement
     pub async fn create(&self, item: &Migration) -> Result<Migration> {
-        let query = format!(
-            "INSERT INTO migration (name, description, created_at, updated_at) VALUES ('{}', '{}', '{}', '{}')",
-            item.name,
-            item.description.as_ref().unwrap_or(&"".to_string()),
-            item.created_at,
-            item.updated_at
-        );
-        self.db.run_query(&query).await?;
-        Ok(item.clone())
+        self.migrations.insert(item).await
     }
 
     /// Recherche un enregistrement par ID
     pub async fn find_by_id(&self, id: i32) -> Result<Option<Migration>> {
-        let query = format!("SELECT * FROM migration WHERE id = {}", id);
-        match self.db.run_query_fetch_optional(&query).await? {
-            Some(row) => {
-                let migration = Migration {
-                    id: row.get("id"),
-                    name: row.get("name"),
-                    description: row.get("description"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                };
-                Ok(Some(migration))
-            },
-            None => Ok(None)
-        }
+        self.migrations.find_by_id(id).await
     }
 
     /// Récupère tous les enregistrements
     pub async fn find_all(&self) -> Result<Vec<Migration>> {
-        let query = "SELECT * FROM migration ORDER BY created_at DESC";
-        let rows = self.db.run_query_fetch_all(query).await?;
-        
-        let mut migrations = Vec::new();
-        for row in rows {
-            let migration = Migration {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            };
-            migrations.push(migration);
-        }
-        Ok(migrations)
+        self.migrations.find_where(&Filter::new()).await
+    }
+
+    /// Récupère les migrations versionnées, de la plus ancienne à la plus récente
+    pub async fn find_versioned(&self) -> Result<Vec<Migration>> {
+        let query = "SELECT * FROM migration WHERE version IS NOT NULL ORDER BY version ASC";
+        self.db.fetch_all_as(query, &[]).await
     }
 
     /// Met à jour un enregistrement
     pub async fn update(&self, item: &Migration) -> Result<Migration> {
-        let now = OffsetDateTime::now_utc();
-        let query = format!(
-            "UPDATE migration SET name = '{}', description = '{}', updated_at = '{}' WHERE id = {}",
-            item.name,
-            item.description.as_ref().unwrap_or(&"".to_string()),
-            now,
-            item.id
-        );
-        self.db.run_query(&query).await?;
-        Ok(item.clone())
+        self.migrations.update(item).await?
+            .ok_or_else(|| anyhow::Error::msg(format!("Migration '{}' not found", item.name)))
+    }
+
+    /// Marque une migration comme en cours (dirty) ou terminée
+    pub async fn set_dirty(&self, version: i64, dirty: bool) -> Result<bool> {
+        let query = "UPDATE migration SET dirty = ?, updated_at = ? WHERE version = ?";
+        let params: Vec<DbValue> = vec![dirty.into(), OffsetDateTime::now_utc().into(), version.into()];
+        let rows_affected = self.db.run_query_with(query, &params).await?;
+        Ok(rows_affected > 0)
     }
 
     /// Supprime un enregistrement
     pub async fn delete(&self, id: i32) -> Result<bool> {
-        let query = format!("DELETE FROM migration WHERE id = {}", id);
-        match self.db.run_query(&query).await {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false)
-        }
+        self.migrations.delete(id).await
     }
-}
\ No newline at end of file
+
+    /// Supprime un enregistrement par version
+    pub async fn delete_by_version(&self, version: i64) -> Result<bool> {
+        Ok(self.migrations.delete_where(&Filter::new().eq("version", version)).await? > 0)
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 10;
+const TABLE   : &str   = "api_keys";
+const INDEXES: &[&str] = &["user_id"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the api_keys table");
+const MIGRATION_NAME : &str = "create_api_keys";
+
+/// Schéma de la table "api_keys" : clés des clients machines, stockées par empreinte SHA-256
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::text("name").not_null())
+        .column(Column::text("prefix").not_null())
+        .column(Column::text("key_hash").not_null())
+        .column(Column::text("scopes").not_null())
+        .column(Column::timestamp("expires_at"))
+        .column(Column::timestamp("last_used_at"))
+        .column(Column::timestamp("revoked_at"))
+        .column(Column::timestamp("created_at").not_null().default_now())
+        .unique_index(&["key_hash"])
+        .indexes(INDEXES)
+}
+
+pub struct CreateApiKeys;
+
+impl Migration for CreateApiKeys {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "api_keys" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.create_tables(&schema()).await?;
+    repo.create_indexes(&schema()).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
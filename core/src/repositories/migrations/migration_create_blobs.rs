@@ -0,0 +1,69 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, DefaultValue, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 5;
+const TABLE   : &str   = "blobs";
+const INDEXES: &[&str] = &["ref_count"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the blobs table (content-addressed storage)");
+const MIGRATION_NAME : &str = "create_blobs";
+
+/// Schéma de la table "blobs" : un contenu stocké une seule fois, compté par référence
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::text("sha256").primary_key())
+        .column(Column::big_int("size").not_null())
+        .column(Column::integer("ref_count").not_null().default(DefaultValue::Int(0)))
+        .timestamps()
+        .indexes(INDEXES)
+}
+
+pub struct CreateBlobs;
+
+impl Migration for CreateBlobs {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "blobs", ses index, et compte les fichiers déjà enregistrés
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    // Création de la table blobs
+    repo.create_tables(&schema()).await?;
+
+
+    // Création des index
+    repo.create_indexes(&schema()).await?;
+
+    // Reprise des fichiers uploadés avant le stockage par contenu
+    repo.run_query(
+        "INSERT INTO blobs (sha256, size, ref_count, created_at, updated_at) \
+         SELECT sha256, MAX(size), COUNT(*), MIN(created_at), MIN(created_at) FROM files GROUP BY sha256"
+    ).await?;
+
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    // Suppression de la table blobs
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 4;
+const TABLE   : &str   = "files";
+const INDEXES: &[&str] = &["user_id", "sha256", "created_at"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the files table");
+const MIGRATION_NAME : &str = "create_files";
+
+/// Schéma de la table "files" : fichiers uploadés, rattachés à leur propriétaire
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::uuid("user_id").references("users", "id").on_delete(OnDelete::SetNull))
+        .column(Column::text("original_name").not_null())
+        .column(Column::text("stored_path").not_null())
+        .column(Column::big_int("size").not_null())
+        .column(Column::text("mime").not_null())
+        .column(Column::text("sha256").not_null())
+        .timestamps()
+        .indexes(INDEXES)
+}
+
+pub struct CreateFiles;
+
+impl Migration for CreateFiles {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "files" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    // Création de la table files
+    repo.create_tables(&schema()).await?;
+
+
+    // Création des index
+    repo.create_indexes(&schema()).await?;
+
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    // Suppression de la table files
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
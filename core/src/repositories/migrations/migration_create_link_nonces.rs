@@ -0,0 +1,57 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 7;
+const TABLE   : &str   = "link_nonces";
+const INDEXES: &[&str] = &["expires_at"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the link_nonces table (single-use download links)");
+const MIGRATION_NAME : &str = "create_link_nonces";
+
+/// Schéma de la table "link_nonces" : jetons des liens à usage unique déjà utilisés
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::text("nonce").primary_key())
+        .column(Column::uuid("file_id").not_null().references("files", "id").on_delete(OnDelete::Cascade))
+        .column(Column::timestamp("expires_at").not_null())
+        .column(Column::timestamp("used_at").not_null())
+        .indexes(INDEXES)
+}
+
+pub struct CreateLinkNonces;
+
+impl Migration for CreateLinkNonces {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "link_nonces" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.create_tables(&schema()).await?;
+    repo.create_indexes(&schema()).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
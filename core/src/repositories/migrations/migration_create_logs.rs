@@ -1,54 +1,59 @@
-use anyhow::{Result, bail};
+use anyhow::Result;
 use crate::repositories::_database::DatabaseQuery;
-use crate::repositories::_init_repository::InitRepository;
-use crate::repositories::migration_repository::{MigrationRepository, Migration};
+use crate::repositories::_schema::{Column, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
 
 
+const VERSION: i64 = 2;
 const TABLE   : &str   = "logs";
 const INDEXES: &[&str] = &["level", "created_at"];
 const DESCRIPTION: Option<&str> = Some("Migration to create the logs table");
 const MIGRATION_NAME : &str = "create_logs";
 
-pub async fn run(repo: &DatabaseQuery) -> Result<()> {
-    println!("Running migration '{}'...", MIGRATION_NAME);
-    let repo_migration = MigrationRepository::new(repo.clone());
+/// Schéma de la table "logs"
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::integer("level").not_null())
+        .column(Column::text("message").not_null())
+        .column(Column::text("context"))
+        .timestamps()
+        .indexes(INDEXES)
+}
+
+pub struct CreateLogs;
+
+impl Migration for CreateLogs {
+    fn version(&self) -> i64 { VERSION }
 
-      // Vérifie si la migration existe déjà en base de données
-    let migration_result = repo_migration.find_by_name(MIGRATION_NAME).await?;
+    fn name(&self) -> &'static str { MIGRATION_NAME }
 
-    if migration_result.is_none() {
-        // Exécuter la migration
-        migrate(repo).await?;
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
 
-        // Enregistrer la migration dans la base de données
-        let model = &Migration::new(&MIGRATION_NAME, DESCRIPTION);
-        let create_migration = repo_migration.create(model).await?;
-        println!("create migration '{:?}'", create_migration);
+    fn definition(&self) -> String {
+        schema_definition(&schema())
     }
 
-    Ok(())
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
 }
 
 
-/// Migration pour créer la table "posts" et ajouter des index
+/// Crée la table "logs" et ses index
 pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
     println!("Running migration to create logs table...");
 
-    let table_fields = r#"
-        id          UUID PRIMARY KEY,
-        level       INT NOT NULL,
-        message     TEXT NOT NULL,
-        context     TEXT,
-        created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-        updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-    "#;
-
-    // Création de la table posts
-    repo.create_tables(TABLE, table_fields).await?;
+    // Création de la table logs
+    repo.create_tables(&schema()).await?;
 
 
     // Création des index
-    repo.create_indexes(TABLE, INDEXES.to_vec()).await?;
+    repo.create_indexes(&schema()).await?;
 
     println!("Migration to create logs table completed successfully.");
    
@@ -59,10 +64,10 @@ pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
     println!("Rolling back migration by dropping logs table...");
 
 
-    // Suppression de la table posts
-    repo.drop_indexes(TABLE, INDEXES.to_vec()).await?;
+    // Suppression de la table logs
+    repo.drop_indexes(&schema()).await?;
     repo.drop_table(TABLE).await?;
 
     println!("Rollback completed successfully.");
     Ok(())
-}
\ No newline at end of file
+}
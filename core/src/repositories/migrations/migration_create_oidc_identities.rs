@@ -0,0 +1,58 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 16;
+const TABLE   : &str   = "oidc_identities";
+const DESCRIPTION: Option<&str> = Some("Migration to create the oidc_identities table (accounts linked to an OpenID Connect subject)");
+const MIGRATION_NAME : &str = "create_oidc_identities";
+
+/// Schéma de la table "oidc_identities" : couple émetteur et `sub` lié à un compte
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::text("issuer").not_null())
+        .column(Column::text("subject").not_null())
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::timestamp("created_at").not_null())
+        .unique_index(&["issuer", "subject"])
+        .index(&["user_id"])
+}
+
+pub struct CreateOidcIdentities;
+
+impl Migration for CreateOidcIdentities {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "oidc_identities" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.create_tables(&schema()).await?;
+    repo.create_indexes(&schema()).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
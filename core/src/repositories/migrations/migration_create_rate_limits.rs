@@ -0,0 +1,57 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 13;
+const TABLE   : &str   = "rate_limits";
+const INDEXES: &[&str] = &["updated_ms"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the rate_limits table (token buckets shared between instances)");
+const MIGRATION_NAME : &str = "create_rate_limits";
+
+/// Schéma de la table "rate_limits" : seaux de jetons du limiteur de débit, par groupe de routes et client
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::text("bucket").primary_key())
+        .column(Column::double("tokens").not_null())
+        .column(Column::big_int("updated_ms").not_null())
+        .column(Column::big_int("version").not_null())
+        .indexes(INDEXES)
+}
+
+pub struct CreateRateLimits;
+
+impl Migration for CreateRateLimits {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "rate_limits" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.create_tables(&schema()).await?;
+    repo.create_indexes(&schema()).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use time::OffsetDateTime;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 11;
+const DESCRIPTION: Option<&str> = Some("Migration to create the roles, role_permissions and user_roles tables");
+const MIGRATION_NAME : &str = "create_roles";
+
+/// Rôle créé par la migration, avec toutes les permissions connues
+pub const ADMIN_ROLE: &str = "admin";
+pub const ADMIN_PERMISSIONS: &[&str] = &["users:list", "users:read", "users:write", "users:delete"];
+
+/// Schéma de la table "roles"
+fn roles() -> TableSchema {
+    TableSchema::new("roles")
+        .column(Column::text("name").primary_key())
+        .column(Column::text("description"))
+        .column(Column::timestamp("created_at").not_null().default_now())
+}
+
+/// Schéma de la table "role_permissions" : permissions accordées par chaque rôle
+fn role_permissions() -> TableSchema {
+    TableSchema::new("role_permissions")
+        .column(Column::text("role").not_null().references("roles", "name").on_delete(OnDelete::Cascade))
+        .column(Column::text("permission").not_null())
+        .unique_index(&["role", "permission"])
+}
+
+/// Schéma de la table "user_roles" : rôles attribués aux utilisateurs
+fn user_roles() -> TableSchema {
+    TableSchema::new("user_roles")
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::text("role").not_null().references("roles", "name").on_delete(OnDelete::Cascade))
+        .column(Column::timestamp("created_at").not_null().default_now())
+        .unique_index(&["user_id", "role"])
+        .index(&["role"])
+}
+
+fn schemas() -> [TableSchema; 3] {
+    [roles(), role_permissions(), user_roles()]
+}
+
+pub struct CreateRoles;
+
+impl Migration for CreateRoles {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        let mut definition: Vec<String> = schemas().iter().map(schema_definition).collect();
+        definition.push(format!("{}: {}", ADMIN_ROLE, ADMIN_PERMISSIONS.join(",")));
+        definition.join(";\n")
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée les tables des rôles et le rôle "admin"
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    for schema in schemas() {
+        repo.create_tables(&schema).await?;
+        repo.create_indexes(&schema).await?;
+    }
+
+    repo.run_query_with(
+        "INSERT INTO roles (name, description, created_at) VALUES (?, ?, ?)",
+        &[ADMIN_ROLE.into(), "All permissions".into(), OffsetDateTime::now_utc().into()],
+    ).await?;
+    for permission in ADMIN_PERMISSIONS {
+        repo.run_query_with(
+            "INSERT INTO role_permissions (role, permission) VALUES (?, ?)",
+            &[ADMIN_ROLE.into(), (*permission).into()],
+        ).await?;
+    }
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    for schema in schemas().iter().rev() {
+        repo.drop_indexes(schema).await?;
+        repo.drop_table(&schema.name).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 9;
+const TABLE   : &str   = "sessions";
+const INDEXES: &[&str] = &["user_id", "expires_at"];
+const DESCRIPTION: Option<&str> = Some("Migration to create the sessions table");
+const MIGRATION_NAME : &str = "create_sessions";
+
+/// Schéma de la table "sessions" : sessions ouvertes par `POST /api/auth/login`,
+/// identifiées par l'empreinte SHA-256 du jeton du cookie
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::text("id").primary_key())
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::timestamp("created_at").not_null())
+        .column(Column::timestamp("expires_at").not_null())
+        .indexes(INDEXES)
+}
+
+pub struct CreateSessions;
+
+impl Migration for CreateSessions {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schema_definition(&schema())
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée la table "sessions" et ses index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.create_tables(&schema()).await?;
+    repo.create_indexes(&schema()).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&schema()).await?;
+    repo.drop_table(TABLE).await?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_schema::{Column, OnDelete, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
+
+
+const VERSION: i64 = 12;
+const DESCRIPTION: Option<&str> = Some("Migration to create the user_totp, totp_recovery_codes and login_challenges tables");
+const MIGRATION_NAME : &str = "create_totp";
+
+/// Schéma de la table "user_totp" : secret TOTP de chaque compte, actif une fois l'enrôlement confirmé
+fn user_totp() -> TableSchema {
+    TableSchema::new("user_totp")
+        .column(Column::uuid("user_id").primary_key().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::text("secret").not_null())
+        .column(Column::timestamp("enabled_at"))
+        .column(Column::big_int("last_step").not_null())
+        .column(Column::timestamp("created_at").not_null())
+}
+
+/// Schéma de la table "totp_recovery_codes" : empreintes des codes de secours, à usage unique
+fn totp_recovery_codes() -> TableSchema {
+    TableSchema::new("totp_recovery_codes")
+        .column(Column::uuid("id").primary_key())
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::text("code_hash").not_null())
+        .column(Column::timestamp("used_at"))
+        .column(Column::timestamp("created_at").not_null())
+        .unique_index(&["user_id", "code_hash"])
+}
+
+/// Schéma de la table "login_challenges" : connexions dont le mot de passe est vérifié
+/// et qui attendent le second facteur
+fn login_challenges() -> TableSchema {
+    TableSchema::new("login_challenges")
+        .column(Column::text("id").primary_key())
+        .column(Column::uuid("user_id").not_null().references("users", "id").on_delete(OnDelete::Cascade))
+        .column(Column::integer("attempts").not_null())
+        .column(Column::timestamp("expires_at").not_null())
+        .index(&["expires_at"])
+}
+
+fn schemas() -> [TableSchema; 3] {
+    [user_totp(), totp_recovery_codes(), login_challenges()]
+}
+
+pub struct CreateTotp;
+
+impl Migration for CreateTotp {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        schemas().iter().map(schema_definition).collect::<Vec<_>>().join(";\n")
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Crée les tables du second facteur
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    for schema in schemas() {
+        repo.create_tables(&schema).await?;
+        repo.create_indexes(&schema).await?;
+    }
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    for schema in schemas().iter().rev() {
+        repo.drop_indexes(schema).await?;
+        repo.drop_table(&schema.name).await?;
+    }
+    Ok(())
+}
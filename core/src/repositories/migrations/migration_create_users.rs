@@ -1,66 +1,70 @@
-use anyhow::{Result, bail};
+use anyhow::Result;
 use crate::repositories::_database::DatabaseQuery;
-use crate::repositories::_init_repository::InitRepository;
-use crate::repositories::migration_repository::{MigrationRepository, Migration};
+use crate::repositories::_schema::{Column, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
 
 
+const VERSION: i64 = 1;
 const TABLE   : &str   = "users";
 const INDEXES: &[&str] = &["login", "email", "created_at"];
 const DESCRIPTION: Option<&str> = Some("Migration to create the users table");
 const MIGRATION_NAME : &str = "create_users";
 
+/// Schéma de la table "users"
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::text("login"))
+        .column(Column::text("birthday"))
+        .column(Column::text("firstname"))
+        .column(Column::text("lastname"))
+        .column(Column::text("sexe"))
+        .column(Column::integer("age"))
+        .column(Column::text("info"))
+        .column(Column::text("email"))
+        .column(Column::text("files_info"))
+        .timestamps()
+        .indexes(INDEXES)
+}
+
+pub struct CreateUsers;
 
-pub async fn run(repo: &DatabaseQuery) -> Result<()> {
-    println!("Running migration '{}'...", MIGRATION_NAME);
-    let repo_migration = MigrationRepository::new(repo.clone());
+impl Migration for CreateUsers {
+    fn version(&self) -> i64 { VERSION }
 
-      // Vérifie si la migration existe déjà en base de données
-    let migration_result = repo_migration.find_by_name(MIGRATION_NAME).await?;
+    fn name(&self) -> &'static str { MIGRATION_NAME }
 
-    if migration_result.is_none() {
-        // Exécuter la migration
-        migrate(repo).await?;
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
 
-        // Enregistrer la migration dans la base de données
-        let model = &Migration::new(&MIGRATION_NAME, DESCRIPTION);
-        let create_migration = repo_migration.create(model).await?;
-        println!("create migration '{:?}'", create_migration);
+    fn definition(&self) -> String {
+        schema_definition(&schema())
     }
 
-    Ok(())
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
 }
 
 
-/// users pour créer la table "posts" et ajouter des index
+/// Crée la table "users" et ses index
 pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
-    let table_fields = r#"
-                    id          UUID PRIMARY KEY,
-                    login       TEXT,
-                    birthday    TEXT,
-                    firstname   TEXT,
-                    lastname    TEXT,
-                    sexe        TEXT,
-                    age         INTEGER,
-                    info        TEXT,
-                    email       TEXT,
-                    files_info  TEXT,
-                    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-    "#;
-
-    // Création de la table posts
-    repo.create_tables(TABLE,table_fields).await?;
+    // Création de la table users
+    repo.create_tables(&schema()).await?;
 
 
     // Création des index
-    repo.create_indexes(TABLE, INDEXES.to_vec()).await?;
+    repo.create_indexes(&schema()).await?;
 
     Ok(())
 }
 
 pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
-    // Suppression de la table posts
-    repo.drop_indexes(TABLE, INDEXES.to_vec()).await?;
+    // Suppression de la table users
+    repo.drop_indexes(&schema()).await?;
     repo.drop_table(TABLE).await?;
     Ok(())
-}
\ No newline at end of file
+}
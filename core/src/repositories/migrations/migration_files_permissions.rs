@@ -0,0 +1,56 @@
+use anyhow::Result;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::migrations::migration_create_roles::ADMIN_ROLE;
+use crate::repositories::migrations::{Migration, MigrationFuture};
+
+
+const VERSION: i64 = 15;
+const DESCRIPTION: Option<&str> = Some("Migration to grant the files permissions to the admin role");
+const MIGRATION_NAME : &str = "files_permissions";
+
+/// Accès aux fichiers des autres utilisateurs ; chacun garde l'accès aux siens
+pub const FILES_PERMISSIONS: &[&str] = &["files:read", "files:delete"];
+
+pub struct FilesPermissions;
+
+impl Migration for FilesPermissions {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        format!("{}: {}", ADMIN_ROLE, FILES_PERMISSIONS.join(","))
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Accorde les permissions des fichiers au rôle "admin"
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    for permission in FILES_PERMISSIONS {
+        repo.run_query_with(
+            "INSERT INTO role_permissions (role, permission) VALUES (?, ?)",
+            &[ADMIN_ROLE.into(), (*permission).into()],
+        ).await?;
+    }
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    for permission in FILES_PERMISSIONS {
+        repo.run_query_with(
+            "DELETE FROM role_permissions WHERE role = ? AND permission = ?",
+            &[ADMIN_ROLE.into(), (*permission).into()],
+        ).await?;
+    }
+    Ok(())
+}
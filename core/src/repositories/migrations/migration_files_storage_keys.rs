@@ -0,0 +1,51 @@
+use anyhow::Result;
+use crate::config::UploadConfig;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::migrations::{Migration, MigrationFuture};
+
+
+const VERSION: i64 = 6;
+const DESCRIPTION: Option<&str> = Some("Migration to store content keys instead of disk paths in files.stored_path");
+const MIGRATION_NAME : &str = "files_storage_keys";
+
+/// `<upload_dir>/blobs/ab/cd/<sha256>` devient la clé `blobs/ab/cd/<sha256>`, lisible par tous les backends
+const UP: &str = "UPDATE files SET stored_path = 'blobs/' || substr(sha256, 1, 2) || '/' || substr(sha256, 3, 2) || '/' || sha256 \
+                  WHERE stored_path LIKE '%blobs/%' AND stored_path NOT LIKE 'blobs/%'";
+
+/// Retour aux chemins sur le disque, sous le dossier d'upload configuré
+const DOWN: &str = "UPDATE files SET stored_path = ? || '/' || stored_path WHERE stored_path LIKE 'blobs/%'";
+
+pub struct FilesStorageKeys;
+
+impl Migration for FilesStorageKeys {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        format!("{};\n{}", UP, DOWN)
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Remplace les chemins des contenus par leurs clés de stockage
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.run_query(UP).await?;
+    Ok(())
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    let upload_dir = UploadConfig::from_env().upload_dir;
+    repo.run_query_with(DOWN, &[upload_dir.trim_end_matches('/').into()]).await?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use crate::repositories::_database::{DatabaseQuery, Dialect};
+use crate::repositories::_schema::{Column, DefaultValue, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture};
+
+
+const VERSION: i64 = 14;
+const TABLE   : &str   = "logs";
+const DESCRIPTION: Option<&str> = Some("Migration to add the type column (app, request...) to the logs table");
+const MIGRATION_NAME : &str = "logs_type";
+
+/// Origine du journal, lue par `Log::r#type` ; les lignes existantes deviennent `app`
+fn column() -> Column {
+    Column::text("type").not_null().default(DefaultValue::Text("app".to_string()))
+}
+
+/// Index de la colonne, pour filtrer les journaux par type
+fn indexes() -> TableSchema {
+    TableSchema::new(TABLE).index(&["type"])
+}
+
+pub struct LogsType;
+
+impl Migration for LogsType {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        let mut statements = vec![format!("ALTER TABLE {} ADD COLUMN {}", TABLE, column().to_sql(Dialect::Postgres))];
+        statements.extend(indexes().create_indexes_sql());
+        statements.join(";\n")
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Ajoute la colonne "type" à la table "logs" et son index
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.add_column(TABLE, &column()).await?;
+    repo.create_indexes(&indexes()).await
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_indexes(&indexes()).await?;
+    repo.drop_column(TABLE, &column().name).await
+}
@@ -1,63 +1,66 @@
 use anyhow::Result;
 use crate::repositories::_database::DatabaseQuery;
 use crate::repositories::_init_repository::InitRepository;
-use crate::repositories::migration_repository::{MigrationRepository, Migration};
+use crate::repositories::_schema::{Column, TableSchema};
+use crate::repositories::migrations::{Migration, MigrationFuture, schema_definition};
 
 
+const VERSION: i64 = 3;
 const TABLE   : &str   = "tests";
 const INDEXES: &[&str] = &["created_at"];
 const DESCRIPTION: Option<&str> = Some("TESTS description");
 const MIGRATION_NAME : &str = "create_tests";
 
-pub async fn run(repo: &DatabaseQuery) -> Result<()> {
-    println!("Running migration '{}'...", MIGRATION_NAME);
-    let repo_migration = MigrationRepository::new(repo.clone());
+/// Schéma de la table "tests"
+fn schema() -> TableSchema {
+    TableSchema::new(TABLE)
+        .column(Column::uuid("id").primary_key())
+        .column(Column::text("description").not_null())
+        .timestamps()
+        .indexes(INDEXES)
+}
+
+pub struct CreateTests;
+
+impl Migration for CreateTests {
+    fn version(&self) -> i64 { VERSION }
 
-      // Vérifie si la migration existe déjà en base de données
-    let migration_result = repo_migration.find_by_name(MIGRATION_NAME).await?;
+    fn name(&self) -> &'static str { MIGRATION_NAME }
 
-    if migration_result.is_none() {
-        // Exécuter la migration
-        migrate(repo).await?;
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
 
-        // Enregistrer la migration dans la base de données
-        let model = &Migration::new(&MIGRATION_NAME, DESCRIPTION);
-        let create_migration = repo_migration.create(model).await?;
-        println!("create migration '{:?}'", create_migration);
+    fn definition(&self) -> String {
+        schema_definition(&schema())
     }
 
-    Ok(())
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
 }
 
-/// Migration pour créer la table "posts" et ajouter des index
+/// Crée la table "tests", ses index et le repository associé
 pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
-    let table_fields = r#"
-        id          UUID PRIMARY KEY,
-        description TEXT NOT NULL,
-        created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-        updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-    "#;
-
-    // Création de la table posts
-    repo.create_tables(
-        TABLE,
-        table_fields
-    ).await?;
+    // Création de la table tests
+    repo.create_tables(&schema()).await?;
 
 
     // Création des index
-    repo.create_indexes(TABLE, INDEXES.to_vec()).await?;
+    repo.create_indexes(&schema()).await?;
 
     // Create Repository
    let init_repo = InitRepository::new(repo.clone());
-   init_repo.init_repository(TABLE, table_fields).await?;
+   init_repo.init_repository(TABLE, &schema()).await?;
    
    Ok(())
 }
 
 pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
     // Suppression de la table
-    repo.drop_indexes(TABLE, INDEXES.to_vec()).await?;
+    repo.drop_indexes(&schema()).await?;
     repo.drop_table(TABLE).await?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use crate::repositories::_database::{DatabaseQuery, Dialect};
+use crate::repositories::_schema::Column;
+use crate::repositories::migrations::{Migration, MigrationFuture};
+
+
+const VERSION: i64 = 8;
+const TABLE   : &str   = "users";
+const DESCRIPTION: Option<&str> = Some("Migration to add password hashes to the users table");
+const MIGRATION_NAME : &str = "users_password_hash";
+
+/// Empreinte argon2 (format PHC) ; `NULL` pour les comptes sans mot de passe
+fn column() -> Column {
+    Column::text("password_hash")
+}
+
+pub struct UsersPasswordHash;
+
+impl Migration for UsersPasswordHash {
+    fn version(&self) -> i64 { VERSION }
+
+    fn name(&self) -> &'static str { MIGRATION_NAME }
+
+    fn description(&self) -> Option<&'static str> { DESCRIPTION }
+
+    fn definition(&self) -> String {
+        format!("ALTER TABLE {} ADD COLUMN {}", TABLE, column().to_sql(Dialect::Postgres))
+    }
+
+    fn up<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(migrate(repo))
+    }
+
+    fn down<'a>(&'a self, repo: &'a DatabaseQuery) -> MigrationFuture<'a> {
+        Box::pin(rollback(repo))
+    }
+}
+
+
+/// Ajoute la colonne "password_hash" à la table "users"
+pub async fn migrate(repo: &DatabaseQuery) -> Result<()> {
+    repo.add_column(TABLE, &column()).await
+}
+
+pub async fn rollback(repo: &DatabaseQuery) -> Result<()> {
+    repo.drop_column(TABLE, &column().name).await
+}
@@ -1,4 +1,348 @@
 pub mod migration_create_logs;
+pub mod migration_create_files;
+pub mod migration_create_blobs;
+pub mod migration_files_storage_keys;
+pub mod migration_create_link_nonces;
+pub mod migration_users_password_hash;
+pub mod migration_create_sessions;
+pub mod migration_create_api_keys;
+pub mod migration_create_roles;
+pub mod migration_create_totp;
+pub mod migration_create_rate_limits;
+pub mod migration_logs_type;
+pub mod migration_files_permissions;
+pub mod migration_create_oidc_identities;
 pub mod migration_create_users;
-pub mod migration_create_migration;
 pub mod migration_test;
+
+use anyhow::{Error, Result};
+use std::future::Future;
+use std::pin::Pin;
+use sha2::{Digest, Sha256};
+use crate::repositories::_database::{DatabaseQuery, Dialect};
+use crate::repositories::_schema::TableSchema;
+use crate::repositories::migration_repository::{self, MigrationRepository};
+
+/// Future renvoyée par `Migration::up` / `Migration::down`
+pub type MigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Migration de schéma versionnée
+///
+/// Chaque migration déclare une version unique, croissante dans le registre,
+/// et une définition dont le checksum est enregistré une fois appliquée.
+pub trait Migration: Send + Sync {
+    /// Numéro de version, détermine l'ordre d'application
+    fn version(&self) -> i64;
+
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Texte décrivant le schéma produit, utilisé pour le checksum
+    fn definition(&self) -> String;
+
+    fn checksum(&self) -> String {
+        let digest = Sha256::digest(self.definition().as_bytes());
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn up<'a>(&'a self, db: &'a DatabaseQuery) -> MigrationFuture<'a>;
+
+    fn down<'a>(&'a self, db: &'a DatabaseQuery) -> MigrationFuture<'a>;
+}
+
+/// Définition canonique d'un schéma pour le checksum : le DDL PostgreSQL,
+/// indépendant du dialecte réellement utilisé
+pub fn schema_definition(schema: &TableSchema) -> String {
+    let mut statements = vec![schema.create_table_sql(Dialect::Postgres)];
+    statements.extend(schema.create_indexes_sql());
+    statements.join(";\n")
+}
+
+/// Registre ordonné de toutes les migrations connues
+pub fn registry() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(migration_create_users::CreateUsers),
+        Box::new(migration_create_logs::CreateLogs),
+        Box::new(migration_test::CreateTests),
+        Box::new(migration_create_files::CreateFiles),
+        Box::new(migration_create_blobs::CreateBlobs),
+        Box::new(migration_files_storage_keys::FilesStorageKeys),
+        Box::new(migration_create_link_nonces::CreateLinkNonces),
+        Box::new(migration_users_password_hash::UsersPasswordHash),
+        Box::new(migration_create_sessions::CreateSessions),
+        Box::new(migration_create_api_keys::CreateApiKeys),
+        Box::new(migration_create_roles::CreateRoles),
+        Box::new(migration_create_totp::CreateTotp),
+        Box::new(migration_create_rate_limits::CreateRateLimits),
+        Box::new(migration_logs_type::LogsType),
+        Box::new(migration_files_permissions::FilesPermissions),
+        Box::new(migration_create_oidc_identities::CreateOidcIdentities),
+    ]
+}
+
+/// État d'une migration du registre par rapport à la base
+#[derive(Clone, Debug, PartialEq)]
+pub enum MigrationState {
+    Pending,
+    Applied,
+    /// Interrompue pendant son application ou son annulation
+    Dirty,
+    /// Appliquée, mais sa définition a changé depuis
+    ChecksumMismatch,
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<time::OffsetDateTime>,
+}
+
+/// Moteur de migrations : applique, annule et inspecte le registre
+pub struct Migrator {
+    db: DatabaseQuery,
+    repository: MigrationRepository,
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl Migrator {
+    pub fn new(db: DatabaseQuery) -> Self {
+        Self::with_migrations(db, registry())
+    }
+
+    pub fn with_migrations(db: DatabaseQuery, migrations: Vec<Box<dyn Migration>>) -> Self {
+        Self {
+            repository: MigrationRepository::new(db.clone()),
+            db,
+            migrations,
+        }
+    }
+
+    /// Prépare la table de suivi et rattache les enregistrements créés avant le versionnement
+    async fn prepare(&self) -> Result<()> {
+        for pair in self.migrations.windows(2) {
+            if pair[0].version() >= pair[1].version() {
+                return Err(Error::msg(format!(
+                    "Migration registry is not strictly ordered: {} ({}) before {} ({})",
+                    pair[0].name(), pair[0].version(), pair[1].name(), pair[1].version()
+                )));
+            }
+        }
+
+        self.repository.create_table().await?;
+
+        for migration in &self.migrations {
+            if let Some(mut legacy) = self.repository.find_by_name(migration.name()).await? {
+                if legacy.version.is_none() {
+                    println!("Adopting legacy migration record '{}' as version {}", migration.name(), migration.version());
+                    legacy.version = Some(migration.version());
+                    legacy.checksum = Some(migration.checksum());
+                    self.repository.update(&legacy).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retourne l'état de chaque migration du registre
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>> {
+        self.prepare().await?;
+        let applied = self.repository.find_versioned().await?;
+
+        let statuses = self.migrations.iter().map(|migration| {
+            let record = applied.iter().find(|m| m.version == Some(migration.version()));
+            let state = match record {
+                None => MigrationState::Pending,
+                Some(r) if r.dirty => MigrationState::Dirty,
+                Some(r) if r.checksum.as_deref() != Some(migration.checksum().as_str()) => MigrationState::ChecksumMismatch,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: migration.version(),
+                name: migration.name().to_string(),
+                state,
+                applied_at: record.map(|r| r.created_at),
+            }
+        }).collect();
+
+        Ok(statuses)
+    }
+
+    /// Refuse de continuer si le schéma est à moitié migré ou a divergé du code
+    pub async fn ensure_consistent(&self) -> Result<()> {
+        let broken: Vec<String> = self.status().await?
+            .into_iter()
+            .filter(|s| matches!(s.state, MigrationState::Dirty | MigrationState::ChecksumMismatch))
+            .map(|s| format!("{} {} ({:?})", s.version, s.name, s.state))
+            .collect();
+
+        if broken.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::msg(format!(
+                "Database schema is in an inconsistent state: {}. Run `server migrate status` and repair before starting",
+                broken.join(", ")
+            )))
+        }
+    }
+
+    /// Applique toutes les migrations en attente, dans l'ordre du registre
+    pub async fn up(&self) -> Result<Vec<String>> {
+        self.ensure_consistent().await?;
+        let mut applied = Vec::new();
+
+        for migration in &self.migrations {
+            if self.repository.find_by_version(migration.version()).await?.is_some() {
+                continue;
+            }
+            self.apply(migration.as_ref()).await?;
+            applied.push(migration.name().to_string());
+        }
+
+        Ok(applied)
+    }
+
+    /// Annule les `steps` dernières migrations appliquées
+    pub async fn down(&self, steps: usize) -> Result<Vec<String>> {
+        self.ensure_consistent().await?;
+        let mut reverted = Vec::new();
+
+        let applied = self.repository.find_versioned().await?;
+        for record in applied.iter().rev().take(steps) {
+            let migration = self.migrations.iter()
+                .find(|m| Some(m.version()) == record.version)
+                .ok_or_else(|| Error::msg(format!("Migration '{}' is applied but missing from the registry", record.name)))?;
+            self.revert(migration.as_ref()).await?;
+            reverted.push(migration.name().to_string());
+        }
+
+        Ok(reverted)
+    }
+
+    /// Annule puis réapplique la dernière migration
+    pub async fn redo(&self) -> Result<Vec<String>> {
+        let reverted = self.down(1).await?;
+        self.up().await?;
+        Ok(reverted)
+    }
+
+    /// Applique une migration et l'enregistre dans une même transaction
+    async fn apply(&self, migration: &dyn Migration) -> Result<()> {
+        println!("Applying migration {} '{}'...", migration.version(), migration.name());
+
+        let record = migration_repository::Migration::versioned(
+            migration.version(),
+            migration.name(),
+            migration.description(),
+            &migration.checksum(),
+        );
+
+        self.db.transaction(|tx| async move {
+            migration.up(&tx).await?;
+            MigrationRepository::new(tx.as_query()).create(&record).await?;
+            Ok(())
+        }).await.map_err(|err| {
+            eprintln!("\x1b[31mMigration '{}' failed: {}\x1b[0m", migration.name(), err);
+            err
+        })?;
+
+        println!("\x1b[32mMigration '{}' applied\x1b[0m", migration.name());
+        Ok(())
+    }
+
+    /// Annule une migration et supprime son enregistrement dans une même transaction
+    async fn revert(&self, migration: &dyn Migration) -> Result<()> {
+        println!("Reverting migration {} '{}'...", migration.version(), migration.name());
+
+        self.db.transaction(|tx| async move {
+            migration.down(&tx).await?;
+            MigrationRepository::new(tx.as_query()).delete_by_version(migration.version()).await?;
+            Ok(())
+        }).await?;
+
+        println!("\x1b[32mMigration '{}' reverted\x1b[0m", migration.name());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn migrator() -> Migrator {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let migrations: Vec<Box<dyn Migration>> = vec![
+            Box::new(migration_create_users::CreateUsers),
+            Box::new(migration_create_logs::CreateLogs),
+        ];
+        Migrator::with_migrations(DatabaseQuery::new_sqlite(pool), migrations)
+    }
+
+    fn states(statuses: &[MigrationStatus]) -> Vec<MigrationState> {
+        statuses.iter().map(|s| s.state.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn up_down_and_redo_on_sqlite() {
+        let migrator = migrator().await;
+
+        assert_eq!(migrator.up().await.unwrap(), vec!["create_users", "create_logs"]);
+        assert!(migrator.up().await.unwrap().is_empty());
+        assert_eq!(states(&migrator.status().await.unwrap()), vec![MigrationState::Applied, MigrationState::Applied]);
+
+        assert_eq!(migrator.down(1).await.unwrap(), vec!["create_logs"]);
+        assert_eq!(states(&migrator.status().await.unwrap()), vec![MigrationState::Applied, MigrationState::Pending]);
+
+        assert_eq!(migrator.redo().await.unwrap(), vec!["create_users"]);
+        assert_eq!(states(&migrator.status().await.unwrap()), vec![MigrationState::Applied, MigrationState::Applied]);
+    }
+
+    #[tokio::test]
+    async fn refuses_half_migrated_schema() {
+        let migrator = migrator().await;
+        migrator.up().await.unwrap();
+        migrator.repository.set_dirty(2, true).await.unwrap();
+
+        assert_eq!(states(&migrator.status().await.unwrap())[1], MigrationState::Dirty);
+        assert!(migrator.ensure_consistent().await.is_err());
+        assert!(migrator.up().await.is_err());
+    }
+
+    struct Broken;
+
+    impl Migration for Broken {
+        fn version(&self) -> i64 { 10 }
+
+        fn name(&self) -> &'static str { "broken" }
+
+        fn definition(&self) -> String { String::from("broken") }
+
+        fn up<'a>(&'a self, db: &'a DatabaseQuery) -> MigrationFuture<'a> {
+            Box::pin(async move {
+                db.run_query("CREATE TABLE half_done (id INTEGER)").await?;
+                db.run_query("INSERT INTO missing_table VALUES (1)").await
+            })
+        }
+
+        fn down<'a>(&'a self, db: &'a DatabaseQuery) -> MigrationFuture<'a> {
+            Box::pin(db.drop_table("half_done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_migration_leaves_nothing_behind() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let db = DatabaseQuery::new_sqlite(pool);
+        let migrator = Migrator::with_migrations(db.clone(), vec![Box::new(Broken)]);
+
+        assert!(migrator.up().await.is_err());
+        assert_eq!(states(&migrator.status().await.unwrap()), vec![MigrationState::Pending]);
+        assert!(db.run_query("SELECT * FROM half_done").await.is_err());
+    }
+}
@@ -1,10 +1,34 @@
 pub mod _database;
+pub mod _schema;
+pub mod _from_row;
+pub mod _repository;
 pub mod _init_repository;
 pub mod migrations;
 
 pub mod user_repository;
 pub mod migration_repository;
 pub mod log_repository;
+pub mod file_repository;
+pub mod blob_repository;
+pub mod link_nonce_repository;
+pub mod session_repository;
+pub mod api_key_repository;
+pub mod role_repository;
+pub mod totp_repository;
+pub mod rate_limit_repository;
+pub mod oidc_identity_repository;
+pub mod tests_repository;
+#[cfg(all(test, feature = "sqlite"))]
+pub(crate) mod test_support;
 
-pub use user_repository::UserRepository;
+pub use user_repository::{UserRepository, User, LoginTaken};
 pub use log_repository::{LogRepository, Log, LogLevel};
+pub use file_repository::{FileRepository, StoredFile};
+pub use blob_repository::{BlobRepository, Blob, blob_lock_key};
+pub use link_nonce_repository::LinkNonceRepository;
+pub use session_repository::{SessionRepository, Session};
+pub use api_key_repository::{ApiKeyRepository, ApiKey};
+pub use role_repository::{RoleRepository, RoleSummary};
+pub use totp_repository::{TotpRepository, TotpSecret, RecoveryCode, LoginChallenge};
+pub use rate_limit_repository::{RateLimitRepository, RateBucket};
+pub use oidc_identity_repository::{OidcIdentityRepository, OidcIdentity};
@@ -0,0 +1,66 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+
+/// Compte lié à un sujet OpenID Connect
+///
+/// Le couple `issuer`/`subject` est le seul identifiant stable fourni par le fournisseur :
+/// login et e-mail peuvent changer ou être choisis par l'utilisateur.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "oidc_identities", sort = "created_at")]
+pub struct OidcIdentity {
+    pub id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub user_id: Uuid,
+    pub created_at: OffsetDateTime,
+}
+
+pub struct OidcIdentityRepository {
+    db: DatabaseQuery,
+    identities: Repository<OidcIdentity>,
+}
+
+impl OidcIdentityRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { identities: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    /// Compte lié au sujet de cet émetteur
+    pub async fn find(&self, issuer: &str, subject: &str) -> Result<Option<OidcIdentity>> {
+        let filter = Filter::new().eq("issuer", issuer).eq("subject", subject);
+        Ok(self.identities.find_where(&filter).await?.into_iter().next())
+    }
+
+    /// Lie le sujet au compte ; `false` s'il était déjà lié, à ce compte ou à un autre
+    pub async fn link(&self, issuer: &str, subject: &str, user_id: Uuid) -> Result<bool> {
+        let query = "INSERT INTO oidc_identities (id, issuer, subject, user_id, created_at) VALUES (?, ?, ?, ?, ?) \
+                     ON CONFLICT (issuer, subject) DO NOTHING";
+        let params = [Uuid::new_v4().into(), issuer.into(), subject.into(), user_id.into(), OffsetDateTime::now_utc().into()];
+        Ok(self.db.run_query_with(query, &params).await? > 0)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::{database, user};
+
+    #[tokio::test]
+    async fn links_a_subject_to_a_single_account() {
+        let db = database().await;
+        let (alice, bob) = (user(&db, "alice").await.id, user(&db, "bob").await.id);
+
+        let identities = OidcIdentityRepository::new(db);
+        assert!(identities.find("https://idp.example", "user-1").await.unwrap().is_none());
+        assert!(identities.link("https://idp.example", "user-1", alice).await.unwrap());
+        assert!(!identities.link("https://idp.example", "user-1", bob).await.unwrap());
+        assert!(identities.link("https://other.example", "user-1", bob).await.unwrap());
+        assert_eq!(identities.find("https://idp.example", "user-1").await.unwrap().map(|i| i.user_id), Some(alice));
+        assert_eq!(identities.find("https://other.example", "user-1").await.unwrap().map(|i| i.user_id), Some(bob));
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Error, Result};
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Repository};
+
+/// Écritures concurrentes tentées avant d'abandonner la mise à jour d'un seau
+const MAX_ATTEMPTS: usize = 5;
+
+/// Seau de jetons d'un client pour un groupe de routes
+///
+/// Le seau se remplit de `per_second` jetons par seconde jusqu'à `capacity` ;
+/// chaque requête en consomme un. `version` sert aux écritures concurrentes en base.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "rate_limits", sort = "updated_ms")]
+pub struct RateBucket {
+    #[database(primary_key)]
+    pub bucket: String,
+    pub tokens: f64,
+    /// Dernier remplissage, en millisecondes depuis l'époque Unix
+    pub updated_ms: i64,
+    pub version: i64,
+}
+
+impl RateBucket {
+    /// Seau plein
+    pub fn full(bucket: &str, capacity: f64, now_ms: i64) -> Self {
+        Self { bucket: bucket.to_string(), tokens: capacity, updated_ms: now_ms, version: 0 }
+    }
+
+    /// Remplit le seau du temps écoulé puis consomme un jeton ; à défaut, retourne
+    /// l'attente en secondes avant le prochain jeton
+    pub fn take(&mut self, capacity: f64, per_second: f64, now_ms: i64) -> Result<(), f64> {
+        let elapsed = (now_ms - self.updated_ms).max(0) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated_ms = self.updated_ms.max(now_ms);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err((1.0 - self.tokens) / per_second)
+        }
+    }
+
+    /// Attente en secondes avant le prochain jeton, sans en consommer ; `None` s'il en reste
+    pub fn wait_at(&self, per_second: f64, now_ms: i64) -> Option<f64> {
+        let tokens = self.tokens + (now_ms - self.updated_ms).max(0) as f64 / 1000.0 * per_second;
+        (tokens < 1.0).then(|| (1.0 - tokens) / per_second)
+    }
+
+    /// Seau redevenu plein à l'instant `now_ms` : inutile de le garder
+    pub fn is_full_at(&self, capacity: f64, per_second: f64, now_ms: i64) -> bool {
+        self.tokens + (now_ms - self.updated_ms).max(0) as f64 / 1000.0 * per_second >= capacity
+    }
+}
+
+/// Seaux du limiteur de débit partagés entre plusieurs instances du serveur
+///
+/// Les mises à jour sont optimistes : un seau n'est réécrit que si sa `version` n'a pas
+/// changé depuis sa lecture, sans transaction ni verrou.
+pub struct RateLimitRepository {
+    db: DatabaseQuery,
+    buckets: Repository<RateBucket>,
+}
+
+impl RateLimitRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self {
+            buckets: Repository::new(db_query.clone()),
+            db: db_query,
+        }
+    }
+
+    /// Consomme un jeton du seau ; `Some(attente en secondes)` s'il est vide
+    pub async fn take(&self, bucket: &str, capacity: f64, per_second: f64, now_ms: i64) -> Result<Option<f64>> {
+        for _ in 0..MAX_ATTEMPTS {
+            let (mut state, previous) = match self.buckets.find_by_id(bucket.to_string()).await? {
+                Some(state) => {
+                    let version = state.version;
+                    (state, Some(version))
+                },
+                None => (RateBucket::full(bucket, capacity, now_ms), None),
+            };
+            let result = state.take(capacity, per_second, now_ms);
+
+            let written = match previous {
+                None => {
+                    let query = "INSERT INTO rate_limits (bucket, tokens, updated_ms, version) VALUES (?, ?, ?, 0) \
+                                 ON CONFLICT (bucket) DO NOTHING";
+                    self.db.run_query_with(query, &[bucket.into(), state.tokens.into(), state.updated_ms.into()]).await?
+                },
+                Some(version) => {
+                    let query = "UPDATE rate_limits SET tokens = ?, updated_ms = ?, version = version + 1 \
+                                 WHERE bucket = ? AND version = ?";
+                    self.db.run_query_with(query, &[state.tokens.into(), state.updated_ms.into(), bucket.into(), version.into()]).await?
+                },
+            };
+            if written > 0 {
+                return Ok(result.err());
+            }
+        }
+        Err(Error::msg(format!("Rate limit bucket '{}' is too contended", bucket)))
+    }
+
+    pub async fn find(&self, bucket: &str) -> Result<Option<RateBucket>> {
+        self.buckets.find_by_id(bucket.to_string()).await
+    }
+
+    /// Supprime les seaux inchangés depuis `before_ms` et retourne leur nombre
+    pub async fn purge_idle(&self, before_ms: i64) -> Result<u64> {
+        self.db.run_query_with("DELETE FROM rate_limits WHERE updated_ms < ?", &[before_ms.into()]).await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::database;
+
+    #[tokio::test]
+    async fn shares_buckets_through_the_database() {
+        let db = database().await;
+        // Deux instances, un seul seau : 2 jetons, 1 jeton par seconde
+        let (first, second) = (RateLimitRepository::new(db.clone()), RateLimitRepository::new(db));
+
+        assert_eq!(first.take("auth:ip:10.0.0.1", 2.0, 1.0, 1_000).await.unwrap(), None);
+        assert_eq!(second.take("auth:ip:10.0.0.1", 2.0, 1.0, 1_000).await.unwrap(), None);
+        let wait = first.take("auth:ip:10.0.0.1", 2.0, 1.0, 1_250).await.unwrap().unwrap();
+        assert!((wait - 0.75).abs() < 1e-9);
+        assert_eq!(second.take("auth:ip:10.0.0.2", 2.0, 1.0, 1_250).await.unwrap(), None);
+        assert_eq!(second.take("auth:ip:10.0.0.1", 2.0, 1.0, 2_000).await.unwrap(), None);
+        let state = first.find("auth:ip:10.0.0.1").await.unwrap().unwrap();
+        assert!((state.wait_at(1.0, 2_000).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(state.wait_at(1.0, 3_000), None);
+
+        assert_eq!(first.purge_idle(1_500).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::{DatabaseQuery, DbValue};
+
+/// Rôle, ses permissions et les logins qui l'ont reçu
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoleSummary {
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub members: Vec<String>,
+}
+
+/// Rôles des utilisateurs et permissions qu'ils accordent
+pub struct RoleRepository {
+    db: DatabaseQuery,
+}
+
+impl RoleRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { db: db_query }
+    }
+
+    async fn strings(&self, query: &str, params: &[DbValue], column: &str) -> Result<Vec<String>> {
+        let rows = self.db.fetch_all_with(query, params).await?;
+        Ok(rows.iter().map(|row| row.try_get(column)).collect::<Result<Vec<String>, _>>()?)
+    }
+
+    pub async fn role_exists(&self, role: &str) -> Result<bool> {
+        Ok(self.db.fetch_optional_with("SELECT name FROM roles WHERE name = ?", &[role.into()]).await?.is_some())
+    }
+
+    /// Rôles de l'utilisateur, par ordre alphabétique
+    pub async fn roles_of(&self, user_id: Uuid) -> Result<Vec<String>> {
+        self.strings("SELECT role FROM user_roles WHERE user_id = ? ORDER BY role", &[user_id.into()], "role").await
+    }
+
+    /// Permissions accordées par l'ensemble des rôles de l'utilisateur
+    pub async fn permissions_of(&self, user_id: Uuid) -> Result<Vec<String>> {
+        let query = "SELECT DISTINCT rp.permission FROM user_roles ur \
+                     JOIN role_permissions rp ON rp.role = ur.role \
+                     WHERE ur.user_id = ? ORDER BY rp.permission";
+        self.strings(query, &[user_id.into()], "permission").await
+    }
+
+    /// Attribue le rôle ; `false` si l'utilisateur l'avait déjà
+    pub async fn grant(&self, user_id: Uuid, role: &str) -> Result<bool> {
+        let query = "INSERT INTO user_roles (user_id, role, created_at) VALUES (?, ?, ?) ON CONFLICT (user_id, role) DO NOTHING";
+        Ok(self.db.run_query_with(query, &[user_id.into(), role.into(), OffsetDateTime::now_utc().into()]).await? > 0)
+    }
+
+    /// Retire le rôle ; `false` si l'utilisateur ne l'avait pas
+    pub async fn revoke(&self, user_id: Uuid, role: &str) -> Result<bool> {
+        let query = "DELETE FROM user_roles WHERE user_id = ? AND role = ?";
+        Ok(self.db.run_query_with(query, &[user_id.into(), role.into()]).await? > 0)
+    }
+
+    /// Tous les rôles, avec leurs permissions et leurs membres
+    pub async fn list_roles(&self) -> Result<Vec<RoleSummary>> {
+        let rows = self.db.fetch_all_with("SELECT name, description FROM roles ORDER BY name", &[]).await?;
+        let mut roles = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            roles.push(RoleSummary {
+                permissions: self.strings("SELECT permission FROM role_permissions WHERE role = ? ORDER BY permission", &[name.as_str().into()], "permission").await?,
+                members: self.strings(
+                    "SELECT u.login FROM user_roles ur JOIN users u ON u.id = ur.user_id WHERE ur.role = ? ORDER BY u.login",
+                    &[name.as_str().into()],
+                    "login",
+                ).await?,
+                description: row.try_get("description")?,
+                name,
+            });
+        }
+        Ok(roles)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::{database, user};
+
+    #[tokio::test]
+    async fn grants_permissions_through_roles() {
+        let db = database().await;
+        let alice = user(&db, "alice").await;
+        let roles = RoleRepository::new(db);
+
+        assert!(roles.permissions_of(alice.id).await.unwrap().is_empty());
+        assert!(roles.grant(alice.id, "admin").await.unwrap());
+        assert!(!roles.grant(alice.id, "admin").await.unwrap());
+        assert_eq!(roles.roles_of(alice.id).await.unwrap(), vec!["admin"]);
+        assert_eq!(roles.permissions_of(alice.id).await.unwrap(), vec!["files:delete", "files:read", "users:delete", "users:list", "users:read", "users:write"]);
+        assert_eq!(roles.list_roles().await.unwrap()[0].members, vec!["alice"]);
+
+        assert!(roles.revoke(alice.id, "admin").await.unwrap());
+        assert!(!roles.revoke(alice.id, "admin").await.unwrap());
+        assert!(roles.permissions_of(alice.id).await.unwrap().is_empty());
+        assert!(!roles.role_exists("auditor").await.unwrap());
+    }
+}
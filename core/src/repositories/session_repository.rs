@@ -0,0 +1,99 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+
+/// Session ouverte côté serveur
+///
+/// Le jeton remis dans le cookie n'est jamais stocké : `id` en est l'empreinte SHA-256,
+/// une fuite de la table ne permet donc pas de reprendre une session.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "sessions", sort = "-created_at")]
+pub struct Session {
+    #[database(primary_key)]
+    pub id: String,
+    pub user_id: Uuid,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+impl Session {
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+pub struct SessionRepository {
+    db: DatabaseQuery,
+    sessions: Repository<Session>,
+}
+
+impl SessionRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self { sessions: Repository::new(db_query.clone()), db: db_query }
+    }
+
+    pub async fn create(&self, session: &Session) -> Result<Session> {
+        self.sessions.insert(session).await
+    }
+
+    /// Session encore valide pour cette empreinte de jeton
+    pub async fn find_active(&self, id: &str) -> Result<Option<Session>> {
+        let session = self.sessions.find_by_id(id.to_string()).await?;
+        Ok(session.filter(|s| !s.is_expired(OffsetDateTime::now_utc())))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        self.sessions.delete(id.to_string()).await
+    }
+
+    /// Ferme toutes les sessions d'un utilisateur, par exemple après un changement de mot de passe
+    pub async fn delete_for_user(&self, user_id: Uuid) -> Result<u64> {
+        self.sessions.delete_where(&Filter::new().eq("user_id", user_id)).await
+    }
+
+    /// Supprime les sessions expirées et retourne leur nombre
+    pub async fn purge_expired(&self) -> Result<u64> {
+        self.db.run_query_with("DELETE FROM sessions WHERE expires_at <= ?", &[OffsetDateTime::now_utc().into()]).await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::migrations::migration_users_password_hash;
+    use crate::repositories::test_support::{database, user};
+    use crate::repositories::UserRepository;
+
+    #[tokio::test]
+    async fn finds_only_active_sessions() {
+        let db = database().await;
+        let users = UserRepository::new(db.clone());
+        let alice = user(&db, "alice").await;
+        assert!(users.set_password_hash(alice.id, "$argon2id$hash").await.unwrap());
+        let (user, hash) = users.get_credentials("alice").await.unwrap().unwrap();
+        assert_eq!((user.id, hash.as_deref()), (alice.id, Some("$argon2id$hash")));
+
+        let sessions = SessionRepository::new(db.clone());
+        let now = OffsetDateTime::now_utc();
+        let session = |id: &str, ttl: i64| Session {
+            id: id.to_string(),
+            user_id: alice.id,
+            created_at: now,
+            expires_at: now + time::Duration::seconds(ttl),
+        };
+        sessions.create(&session("active", 60)).await.unwrap();
+        sessions.create(&session("expired", -60)).await.unwrap();
+
+        assert_eq!(sessions.find_active("active").await.unwrap().map(|s| s.user_id), Some(alice.id));
+        assert!(sessions.find_active("expired").await.unwrap().is_none());
+        assert_eq!(sessions.purge_expired().await.unwrap(), 1);
+        assert_eq!(sessions.delete_for_user(alice.id).await.unwrap(), 1);
+
+        migration_users_password_hash::rollback(&db).await.unwrap();
+        assert!(db.run_query("SELECT password_hash FROM users").await.is_err());
+    }
+}
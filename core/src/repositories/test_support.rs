@@ -0,0 +1,25 @@
+//! Outils communs aux tests des dépôts
+
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::migrations::{registry, Migrator};
+use crate::repositories::{User, UserRepository};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::collections::HashMap;
+
+/// Base SQLite en mémoire au schéma complet, construit par les migrations du registre
+///
+/// Une seule connexion : chaque connexion à `sqlite::memory:` ouvre une base distincte.
+/// `create_tests` est écartée : elle génère un fichier source du dépôt, chemin relatif à la racine.
+pub async fn database() -> DatabaseQuery {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    let db = DatabaseQuery::new_sqlite(pool);
+    let migrations = registry().into_iter().filter(|migration| migration.name() != "create_tests").collect();
+    Migrator::with_migrations(db.clone(), migrations).up().await.unwrap();
+    db
+}
+
+/// Utilisateur `login` enregistré dans la base
+pub async fn user(db: &DatabaseQuery, login: &str) -> User {
+    let fields = HashMap::from([("login".to_string(), login.to_string())]);
+    UserRepository::new(db.clone()).create_user(&User::from_form_fields(&fields, &Vec::new())).await.unwrap()
+}
@@ -0,0 +1,224 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Repository};
+
+/// Secret TOTP d'un compte
+///
+/// Tant que `enabled_at` est vide, l'enrôlement attend la confirmation d'un premier code.
+/// `last_step` retient le dernier pas de temps accepté : un code n'est jamais accepté deux fois.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "user_totp", sort = "-created_at")]
+pub struct TotpSecret {
+    #[database(primary_key)]
+    pub user_id: Uuid,
+    /// Secret partagé en base32, tel que saisi dans l'application d'authentification
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub enabled_at: Option<OffsetDateTime>,
+    pub last_step: i64,
+    pub created_at: OffsetDateTime,
+}
+
+impl TotpSecret {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+/// Code de secours, dont seule l'empreinte SHA-256 est stockée
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "totp_recovery_codes", sort = "created_at")]
+pub struct RecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub code_hash: String,
+    pub used_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+/// Connexion dont le mot de passe est vérifié, en attente du code TOTP
+///
+/// Comme pour les sessions, `id` est l'empreinte SHA-256 du jeton remis au client.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "login_challenges", sort = "expires_at")]
+pub struct LoginChallenge {
+    #[database(primary_key)]
+    pub id: String,
+    pub user_id: Uuid,
+    /// Codes présentés jusqu'ici, essai en cours compris
+    pub attempts: i32,
+    pub expires_at: OffsetDateTime,
+}
+
+pub struct TotpRepository {
+    db: DatabaseQuery,
+    secrets: Repository<TotpSecret>,
+    recovery_codes: Repository<RecoveryCode>,
+    challenges: Repository<LoginChallenge>,
+}
+
+impl TotpRepository {
+    pub fn new(db_query: DatabaseQuery) -> Self {
+        Self {
+            secrets: Repository::new(db_query.clone()),
+            recovery_codes: Repository::new(db_query.clone()),
+            challenges: Repository::new(db_query.clone()),
+            db: db_query,
+        }
+    }
+
+    pub async fn find(&self, user_id: Uuid) -> Result<Option<TotpSecret>> {
+        self.secrets.find_by_id(user_id).await
+    }
+
+    /// Second facteur actif pour ce compte
+    pub async fn is_enabled(&self, user_id: Uuid) -> Result<bool> {
+        Ok(self.find(user_id).await?.is_some_and(|secret| secret.is_enabled()))
+    }
+
+    /// Remplace un enrôlement en attente par un nouveau secret ; un secret actif n'est jamais remplacé
+    pub async fn begin_enrollment(&self, user_id: Uuid, secret: &str) -> Result<TotpSecret> {
+        let secret = TotpSecret { user_id, secret: secret.to_string(), enabled_at: None, last_step: 0, created_at: OffsetDateTime::now_utc() };
+        self.db.transaction(|tx| async move {
+            let query = tx.as_query();
+            query.run_query_with("DELETE FROM user_totp WHERE user_id = ? AND enabled_at IS NULL", &[user_id.into()]).await?;
+            Repository::<TotpSecret>::new(query).insert(&secret).await
+        }).await
+    }
+
+    /// Active le secret en attente et remplace les codes de secours ; `false` s'il n'y a
+    /// pas d'enrôlement en attente. `step` est le pas du code qui a confirmé l'enrôlement.
+    pub async fn enable(&self, user_id: Uuid, step: i64, code_hashes: &[String]) -> Result<bool> {
+        let code_hashes = code_hashes.to_vec();
+        self.db.transaction(|tx| async move {
+            let query = tx.as_query();
+            let now = OffsetDateTime::now_utc();
+            let enabled = query.run_query_with(
+                "UPDATE user_totp SET enabled_at = ?, last_step = ? WHERE user_id = ? AND enabled_at IS NULL",
+                &[now.into(), step.into(), user_id.into()],
+            ).await?;
+            if enabled == 0 {
+                return Ok(false);
+            }
+            let codes = Repository::<RecoveryCode>::new(query);
+            codes.delete_where(&Filter::new().eq("user_id", user_id)).await?;
+            for code_hash in code_hashes {
+                codes.insert(&RecoveryCode { id: Uuid::new_v4(), user_id, code_hash, used_at: None, created_at: now }).await?;
+            }
+            Ok(true)
+        }).await
+    }
+
+    /// Retire le second facteur et ses codes de secours ; `false` s'il n'y en avait pas
+    pub async fn disable(&self, user_id: Uuid) -> Result<bool> {
+        self.recovery_codes.delete_where(&Filter::new().eq("user_id", user_id)).await?;
+        self.secrets.delete(user_id).await
+    }
+
+    /// Retient le pas de temps d'un code accepté ; `false` si ce pas, ou un plus récent,
+    /// a déjà servi (code rejoué)
+    pub async fn use_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        let query = "UPDATE user_totp SET last_step = ? WHERE user_id = ? AND last_step < ? AND enabled_at IS NOT NULL";
+        Ok(self.db.run_query_with(query, &[step.into(), user_id.into(), step.into()]).await? > 0)
+    }
+
+    /// Consomme un code de secours ; `false` s'il est inconnu ou déjà utilisé
+    pub async fn use_recovery_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool> {
+        let query = "UPDATE totp_recovery_codes SET used_at = ? WHERE user_id = ? AND code_hash = ? AND used_at IS NULL";
+        Ok(self.db.run_query_with(query, &[OffsetDateTime::now_utc().into(), user_id.into(), code_hash.into()]).await? > 0)
+    }
+
+    pub async fn remaining_recovery_codes(&self, user_id: Uuid) -> Result<i64> {
+        self.recovery_codes.count_where(&Filter::new().eq("user_id", user_id).is_null("used_at")).await
+    }
+
+    pub async fn create_challenge(&self, challenge: &LoginChallenge) -> Result<LoginChallenge> {
+        self.challenges.insert(challenge).await
+    }
+
+    /// Réserve un essai de code sur une connexion en attente encore valide, en une seule
+    /// requête : des requêtes simultanées ne peuvent pas dépasser `max_attempts` essais.
+    /// `None` si la connexion n'existe pas, a expiré ou a épuisé ses essais.
+    pub async fn take_attempt(&self, id: &str, max_attempts: i32) -> Result<Option<LoginChallenge>> {
+        let query = "UPDATE login_challenges SET attempts = attempts + 1 \
+                     WHERE id = ? AND attempts < ? AND expires_at > ? RETURNING *";
+        self.db.fetch_optional_as(query, &[id.into(), max_attempts.into(), OffsetDateTime::now_utc().into()]).await
+    }
+
+    pub async fn delete_challenge(&self, id: &str) -> Result<bool> {
+        self.challenges.delete(id.to_string()).await
+    }
+
+    /// Supprime les connexions en attente expirées et retourne leur nombre
+    pub async fn purge_expired_challenges(&self) -> Result<u64> {
+        self.db.run_query_with("DELETE FROM login_challenges WHERE expires_at <= ?", &[OffsetDateTime::now_utc().into()]).await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::{database, user};
+
+    #[tokio::test]
+    async fn enrolls_and_consumes_codes_once() {
+        let db = database().await;
+        let alice = user(&db, "alice").await;
+        let totp = TotpRepository::new(db);
+
+        // Un nouvel enrôlement remplace celui en attente
+        totp.begin_enrollment(alice.id, "FIRST").await.unwrap();
+        totp.begin_enrollment(alice.id, "SECOND").await.unwrap();
+        assert_eq!(totp.find(alice.id).await.unwrap().unwrap().secret, "SECOND");
+        assert!(!totp.is_enabled(alice.id).await.unwrap());
+        assert!(!totp.use_step(alice.id, 10).await.unwrap());
+
+        assert!(totp.enable(alice.id, 10, &["h1".to_string(), "h2".to_string()]).await.unwrap());
+        assert!(!totp.enable(alice.id, 11, &[]).await.unwrap());
+        assert!(totp.is_enabled(alice.id).await.unwrap());
+        assert!(totp.begin_enrollment(alice.id, "THIRD").await.is_err());
+
+        // Pas de temps et codes de secours ne servent qu'une fois
+        assert!(!totp.use_step(alice.id, 10).await.unwrap());
+        assert!(totp.use_step(alice.id, 11).await.unwrap());
+        assert!(totp.use_recovery_code(alice.id, "h1").await.unwrap());
+        assert!(!totp.use_recovery_code(alice.id, "h1").await.unwrap());
+        assert_eq!(totp.remaining_recovery_codes(alice.id).await.unwrap(), 1);
+
+        assert!(totp.disable(alice.id).await.unwrap());
+        assert!(totp.find(alice.id).await.unwrap().is_none());
+        assert_eq!(totp.remaining_recovery_codes(alice.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn limits_attempts_on_login_challenges() {
+        let db = database().await;
+        let alice = user(&db, "alice").await;
+        let totp = TotpRepository::new(db);
+        let challenge = |id: &str, ttl: i64| LoginChallenge {
+            id: id.to_string(),
+            user_id: alice.id,
+            attempts: 0,
+            expires_at: OffsetDateTime::now_utc() + time::Duration::seconds(ttl),
+        };
+        totp.create_challenge(&challenge("pending", 60)).await.unwrap();
+        totp.create_challenge(&challenge("expired", -60)).await.unwrap();
+
+        for attempt in 1..=3 {
+            let taken = totp.take_attempt("pending", 3).await.unwrap().unwrap();
+            assert_eq!((taken.user_id, taken.attempts), (alice.id, attempt));
+        }
+        assert!(totp.take_attempt("pending", 3).await.unwrap().is_none());
+        assert!(totp.take_attempt("expired", 3).await.unwrap().is_none());
+        assert!(totp.take_attempt("unknown", 3).await.unwrap().is_none());
+
+        assert!(totp.delete_challenge("pending").await.unwrap());
+        assert!(!totp.delete_challenge("pending").await.unwrap());
+        assert_eq!(totp.purge_expired_challenges().await.unwrap(), 1);
+    }
+}
@@ -1,13 +1,15 @@
-use sqlx::Row;
 use serde::{Serialize, Deserialize};
 use anyhow::Result;
 use time::OffsetDateTime;
 use std::collections::HashMap;
 use uuid::Uuid;
-use crate::repositories::_database::DatabaseQuery;
+use crate::repositories::_database::{DatabaseQuery, DbValue};
+use crate::repositories::_from_row::FromDatabaseRow;
+use crate::repositories::_repository::{Entity, Filter, Page, Paginated, Repository, Sort};
 
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, FromDatabaseRow, Entity)]
+#[database(table = "users", sort = "-created_at")]
 pub struct User {
     pub id: Uuid,
     pub login: Option<String>,
@@ -19,11 +21,25 @@ pub struct User {
     pub info: Option<String>,
     pub email: Option<String>,
     pub files_info: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
 }
 
+/// Le login demandé appartient déjà à un autre utilisateur
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginTaken(pub String);
+
+impl std::fmt::Display for LoginTaken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Login '{}' is already taken", self.0)
+    }
+}
+
+impl std::error::Error for LoginTaken {}
+
 pub struct UserRepository {
     db: DatabaseQuery,
+    users: Repository<User>,
 }
 
 impl User {
@@ -53,136 +69,201 @@ impl User {
 
 impl UserRepository {
     pub fn new(db_query: DatabaseQuery) -> Self {
-        Self { db: db_query }
+        Self { users: Repository::new(db_query.clone()), db: db_query }
     }
 
     /// Vérifie si un utilisateur existe déjà par login
     pub async fn user_exists(&self, login: &str) -> Result<bool> {
-        let query = format!("SELECT COUNT(*) as count FROM users WHERE login = '{}'", login);
-        let row = self.db.run_query_fetch_one(&query).await?;
-        let count: i64 = row.get("count");
+        let query = "SELECT COUNT(*) as count FROM users WHERE login = ?";
+        let row = self.db.fetch_one_with(query, &[login.into()]).await?;
+        let count: i64 = row.decode("count")?;
         Ok(count > 0)
     }
 
     /// Récupère un utilisateur par login
     pub async fn get_user(&self, login: &str) -> Result<Option<User>> {
-        let query = format!("SELECT * FROM users WHERE login = '{}' ORDER BY created_at DESC LIMIT 1", login);
-        match self.db.run_query_fetch_optional(&query).await? {
-            Some(row) => {
-                let user = User {
-                    id: row.get("id"),
-                    login: row.get("login"),
-                    birthday: row.get("birthday"),
-                    firstname: row.get("firstname"),
-                    lastname: row.get("lastname"),
-                    sexe: row.get("sexe"),
-                    age: row.get("age"),
-                    info: row.get("info"),
-                    email: row.get("email"),
-                    files_info: row.get("files_info"),
-                    created_at: row.get("created_at"),
-                };
-                Ok(Some(user))
-            },
-            None => Ok(None)
+        let query = "SELECT * FROM users WHERE login = ? ORDER BY created_at DESC LIMIT 1";
+        self.db.fetch_optional_as(query, &[login.into()]).await
+    }
+
+    /// Récupère un utilisateur par ID
+    pub async fn get_user_by_id(&self, id: Uuid) -> Result<Option<User>> {
+        self.users.find_by_id(id).await
+    }
+
+    /// Utilisateur et empreinte de son mot de passe, pour l'authentification ;
+    /// l'empreinte ne fait pas partie de `User` et n'est donc jamais renvoyée par l'API
+    pub async fn get_credentials(&self, login: &str) -> Result<Option<(User, Option<String>)>> {
+        let query = "SELECT * FROM users WHERE login = ? ORDER BY created_at DESC LIMIT 1";
+        match self.db.fetch_optional_with(query, &[login.into()]).await? {
+            Some(row) => Ok(Some((User::from_row(&row)?, row.try_get("password_hash")?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Enregistre l'empreinte du mot de passe ; `false` si l'utilisateur n'existe pas
+    pub async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> Result<bool> {
+        let query = "UPDATE users SET password_hash = ? WHERE id = ?";
+        Ok(self.db.run_query_with(query, &[password_hash.into(), id.into()]).await? > 0)
+    }
+
+    /// Met à jour l'adresse e-mail ; `false` si l'utilisateur n'existe pas
+    pub async fn set_email(&self, id: Uuid, email: &str) -> Result<bool> {
+        let query = "UPDATE users SET email = ? WHERE id = ?";
+        Ok(self.db.run_query_with(query, &[email.into(), id.into()]).await? > 0)
+    }
+
+    /// Récupère un utilisateur par ID si la clé est un UUID, sinon par login
+    pub async fn find_user(&self, key: &str) -> Result<Option<User>> {
+        match Uuid::parse_str(key) {
+            Ok(id) => self.get_user_by_id(id).await,
+            Err(_) => self.get_user(key).await,
+        }
+    }
+
+    /// Vérifie si le login est utilisé par un autre utilisateur que `except`
+    async fn login_taken(&self, login: &str, except: Option<Uuid>) -> Result<bool> {
+        let mut filter = Filter::new().eq("login", login);
+        if let Some(id) = except {
+            filter = filter.ne("id", id);
         }
+        Ok(self.users.count_where(&filter).await? > 0)
+    }
+
+    /// Crée un utilisateur dont le login doit être libre ; sinon erreur `LoginTaken`
+    pub async fn insert_unique(&self, user: &User) -> Result<User> {
+        let user = user.clone();
+        let login = user.login.clone().unwrap_or_default();
+
+        self.db.transaction(|tx| async move {
+            tx.lock(&login).await?;
+            let repository = UserRepository::new(tx.as_query());
+
+            if repository.login_taken(&login, None).await? {
+                return Err(LoginTaken(login).into());
+            }
+            repository.create_user(&user).await
+        }).await
+    }
+
+    /// Met à jour un utilisateur par ID, le login devant rester unique ; sinon erreur `LoginTaken`.
+    /// `None` si l'utilisateur n'existe pas.
+    pub async fn update_unique(&self, user: &User) -> Result<Option<User>> {
+        let user = user.clone();
+        let login = user.login.clone().unwrap_or_default();
+
+        self.db.transaction(|tx| async move {
+            tx.lock(&login).await?;
+            let repository = UserRepository::new(tx.as_query());
+
+            if repository.login_taken(&login, Some(user.id)).await? {
+                return Err(LoginTaken(login).into());
+            }
+            repository.users.update(&user).await
+        }).await
     }
 
     /// Crée un nouvel utilisateur
     pub async fn create_user(&self, user: &User) -> Result<User> {
-        let query = format!(
-            "INSERT INTO users (id, login, birthday, firstname, lastname, sexe, age, info, email, files_info, created_at) VALUES ('{}', '{}', '{}', '{}', '{}', '{}', {}, '{}', '{}', '{}', '{}')",
-            user.id,
-            user.login.as_ref().unwrap_or(&"".to_string()),
-            user.birthday.as_ref().unwrap_or(&"".to_string()),
-            user.firstname.as_ref().unwrap_or(&"".to_string()),
-            user.lastname.as_ref().unwrap_or(&"".to_string()),
-            user.sexe.as_ref().unwrap_or(&"".to_string()),
-            user.age.unwrap_or(0),
-            user.info.as_ref().unwrap_or(&"".to_string()),
-            user.email.as_ref().unwrap_or(&"".to_string()),
-            user.files_info.as_ref().unwrap_or(&"".to_string()),
-            user.created_at
-        );
-
-        self.db.run_query(&query).await?;
-        Ok(user.clone())
-    }
-
-    /// Met à jour un utilisateur existant
+        self.users.insert(user).await
+    }
+
+    /// Met à jour un utilisateur existant et retourne la ligne enregistrée (avec son ID d'origine)
     pub async fn update_user(&self, login: &str, user: &User) -> Result<User> {
-        let query = format!(
-            "UPDATE users SET birthday = '{}', firstname = '{}', lastname = '{}', sexe = '{}', age = {}, info = '{}', email = '{}', files_info = '{}', created_at = '{}' WHERE login = '{}'",
-            user.birthday.as_ref().unwrap_or(&"".to_string()),
-            user.firstname.as_ref().unwrap_or(&"".to_string()),
-            user.lastname.as_ref().unwrap_or(&"".to_string()),
-            user.sexe.as_ref().unwrap_or(&"".to_string()),
-            user.age.unwrap_or(0),
-            user.info.as_ref().unwrap_or(&"".to_string()),
-            user.email.as_ref().unwrap_or(&"".to_string()),
-            user.files_info.as_ref().unwrap_or(&"".to_string()),
-            OffsetDateTime::now_utc(),
-            login
-        );
-
-        self.db.run_query(&query).await?;
-        Ok(user.clone())
-    }
-
-    /// Crée ou met à jour un utilisateur (méthode principale)
-    pub async fn upsert_user(&self, form_fields: &HashMap<String, String>, files: &Vec<String>) -> Result<User> {
+        let query = "UPDATE users SET birthday = ?, firstname = ?, lastname = ?, sexe = ?, age = ?, info = ?, email = ?, files_info = ?, created_at = ? WHERE login = ?";
+        let params: Vec<DbValue> = vec![
+            (&user.birthday).into(),
+            (&user.firstname).into(),
+            (&user.lastname).into(),
+            (&user.sexe).into(),
+            user.age.into(),
+            (&user.info).into(),
+            (&user.email).into(),
+            (&user.files_info).into(),
+            OffsetDateTime::now_utc().into(),
+            login.into(),
+        ];
+
+        self.db.run_query_with(query, &params).await?;
+        self.get_user(login).await?
+            .ok_or_else(|| anyhow::Error::msg(format!("User '{}' vanished during update", login)))
+    }
+
+    /// Crée l'utilisateur, ou met à jour celui qui a déjà ce login s'il s'agit de `owner` ;
+    /// sinon erreur `LoginTaken`
+    pub async fn upsert_user(&self, form_fields: &HashMap<String, String>, files: &Vec<String>, owner: Option<Uuid>) -> Result<User> {
         let user = User::from_form_fields(form_fields, files);
 
         // Vérifier si l'utilisateur a un login
         let login = match &user.login {
-            Some(l) if !l.is_empty() => l,
+            Some(l) if !l.is_empty() => l.clone(),
             _ => return Err(anyhow::Error::msg("Login is required")),
         };
 
-        // Vérifier si l'utilisateur existe déjà
-        if self.user_exists(login).await? {
-            println!("User with login '{}' exists, updating...", login);
-            self.update_user(login, &user).await
-        } else {
-            println!("Creating new user with login '{}'...", login);
-            self.create_user(&user).await
-        }
+        // Vérification et écriture dans une même transaction, verrouillée sur le login
+        self.db.transaction(|tx| async move {
+            tx.lock(&login).await?;
+            let repository = UserRepository::new(tx.as_query());
+
+            match repository.get_user(&login).await? {
+                Some(existing) if owner == Some(existing.id) => {
+                    println!("User with login '{}' exists, updating...", login);
+                    repository.update_user(&login, &user).await
+                },
+                Some(_) => Err(LoginTaken(login).into()),
+                None => {
+                    println!("Creating new user with login '{}'...", login);
+                    repository.create_user(&user).await
+                },
+            }
+        }).await
     }
 
 
     /// Récupérer toutes les données de formulaire
     pub async fn get_all(&self) -> Result<Vec<User>> {
-        let query = "SELECT id, login, birthday, firstname, lastname, sexe, age, info, email, files_info, created_at FROM users ORDER BY created_at DESC";
-
-        let rows = self.db.run_query_fetch_all(query).await?;
-        
-        let mut users_list = Vec::new();
-        for row in rows {
-            let user = User {
-                id: row.get("id"),
-                login: row.get("login"),
-                birthday: row.get("birthday"),
-                firstname: row.get("firstname"),
-                lastname: row.get("lastname"),
-                sexe: row.get("sexe"),
-                age: row.get("age"),
-                info: row.get("info"),
-                email: row.get("email"),
-                files_info: row.get("files_info"),
-                created_at: row.get("created_at"),
-            };
-            users_list.push(user);
-        }
+        self.users.find_where(&Filter::new()).await
+    }
 
-        Ok(users_list)
+    /// Récupère une page d'utilisateurs filtrés et triés
+    pub async fn list(&self, filter: &Filter, page: Page, sort: &Sort) -> Result<Paginated<User>> {
+        self.users.list_where(filter, page, sort).await
     }
 
     /// Supprimer une donnée de formulaire par ID
     pub async fn delete_user(&self, id: Uuid) -> Result<bool> {
-        let query = format!("DELETE FROM users WHERE id = '{}'", id);
-        match self.db.run_query(&query).await {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false)
-        }
+        self.users.delete(id).await
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repositories::test_support::database;
+
+    fn user(login: &str) -> User {
+        let fields = HashMap::from([("login".to_string(), login.to_string())]);
+        User::from_form_fields(&fields, &Vec::new())
+    }
+
+    #[tokio::test]
+    async fn unique_writes_reject_taken_login() {
+        let users = UserRepository::new(database().await);
+
+        let alice = users.insert_unique(&user("alice")).await.unwrap();
+        let bob = users.insert_unique(&user("bob")).await.unwrap();
+        let err = users.insert_unique(&user("alice")).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<LoginTaken>(), Some(&LoginTaken("alice".to_string())));
+
+        let renamed = User { login: Some("alice".to_string()), ..bob.clone() };
+        assert!(users.update_unique(&renamed).await.unwrap_err().is::<LoginTaken>());
+
+        let aged = User { age: Some(42), ..alice.clone() };
+        assert_eq!(users.update_unique(&aged).await.unwrap().unwrap().age, Some(42));
+        assert_eq!(users.find_user(&alice.id.to_string()).await.unwrap().unwrap().age, Some(42));
+        assert_eq!(users.find_user("bob").await.unwrap().unwrap().id, bob.id);
+
+        users.delete_user(bob.id).await.unwrap();
+        assert!(users.update_unique(&bob).await.unwrap().is_none());
     }
 }
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::table::cell_text;
+
+/// Mise en forme d'une cellule selon sa colonne
+pub enum Formatter {
+    /// Date RFC 3339 affichée `AAAA-MM-JJ`
+    Date,
+    /// Date RFC 3339 affichée `AAAA-MM-JJ HH:MM` (UTC)
+    DateTime,
+    /// Booléen affiché `Oui` / `Non`
+    Boolean,
+    /// Nombre d'octets affiché en unité lisible (`1.5 KB`)
+    Bytes,
+    /// Fichiers uploadés `nom (/api/files/{id}, N bytes)`, en liste ou séparés par des virgules ;
+    /// en HTML, liens de téléchargement avec miniature pour les images
+    Attachments,
+    Custom(Box<dyn Fn(&Value) -> String>),
+}
+
+impl Formatter {
+    /// Texte formaté ; une valeur que le formateur ne comprend pas est rendue telle quelle
+    pub fn format(&self, value: &Value) -> String {
+        match (self, value) {
+            (_, Value::Null) => String::new(),
+            (Formatter::Date, Value::String(text)) => chrono::DateTime::parse_from_rfc3339(text)
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|_| text.clone()),
+            (Formatter::DateTime, Value::String(text)) => chrono::DateTime::parse_from_rfc3339(text)
+                .map(|date| date.with_timezone(&chrono::Utc).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| text.clone()),
+            (Formatter::Boolean, Value::Bool(flag)) => if *flag { "Oui" } else { "Non" }.to_string(),
+            (Formatter::Bytes, Value::Number(number)) => match number.as_f64() {
+                Some(bytes) => format_bytes(bytes),
+                None => number.to_string(),
+            },
+            (Formatter::Attachments, value) => attachment_entries(value).join(", "),
+            (Formatter::Custom(format), value) => format(value),
+            (_, value) => cell_text(value),
+        }
+    }
+
+    /// Cellule HTML : le texte formaté échappé, ou le balisage propre au formateur
+    pub fn format_html(&self, value: &Value) -> String {
+        match self {
+            Formatter::Attachments => attachment_entries(value).iter()
+                .map(|entry| attachment_html(entry))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => html_escape::encode_text(&self.format(value)).to_string(),
+        }
+    }
+}
+
+/// Entrées d'une liste de fichiers : éléments d'un tableau ou chaîne séparée par des virgules
+///
+/// Seule la virgule qui suit `bytes)` sépare deux entrées, un nom de fichier pouvant en contenir.
+fn attachment_entries(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().map(cell_text).collect(),
+        Value::String(text) if !text.is_empty() => {
+            let mut entries = Vec::new();
+            let mut rest = text.as_str();
+            while let Some(end) = rest.find(" bytes),") {
+                entries.push(rest[..end + 7].to_string());
+                rest = &rest[end + 8..];
+            }
+            entries.push(rest.to_string());
+            entries
+        },
+        Value::Null | Value::String(_) => Vec::new(),
+        other => vec![cell_text(other)],
+    }
+}
+
+/// Nom et URL d'une entrée `nom (/api/files/{id}, N bytes)`
+fn parse_attachment(entry: &str) -> Option<(&str, &str)> {
+    let entry = entry.trim().strip_suffix(" bytes)")?;
+    let start = entry.rfind(" (/api/files/")?;
+    let url = entry[start + 2..].split(", ").next()?;
+    Some((&entry[..start], url))
+}
+
+/// Les formats que le serveur sait réduire en miniature
+fn is_previewable(name: &str) -> bool {
+    let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    name.contains('.') && matches!(extension.as_str(), "jpg" | "jpeg" | "png")
+}
+
+fn attachment_html(entry: &str) -> String {
+    let Some((name, url)) = parse_attachment(entry) else {
+        return html_escape::encode_text(entry).to_string();
+    };
+    let href = html_escape::encode_single_quoted_attribute(url);
+    if is_previewable(name) {
+        format!(
+            "<a href='{}' class='attachment'><img src='{}/thumbnail' alt='{}' class='attachment-thumbnail' loading='lazy'></a>",
+            href, href, html_escape::encode_single_quoted_attribute(name)
+        )
+    } else {
+        format!("<a href='{}' class='attachment'>{}</a>", href, html_escape::encode_text(name))
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes;
+    let mut unit = 0;
+    while size.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Réglages des colonnes d'une table : ordre, colonnes masquées, libellés et formateurs
+///
+/// Une colonne désigne une clé ou, pour les rendus à plat, un chemin pointé ;
+/// masquer ou ordonner `adresse` s'applique aussi à `adresse.ville`.
+#[derive(Default)]
+pub struct ColumnOptions {
+    order: Vec<String>,
+    hidden: Vec<String>,
+    labels: HashMap<String, String>,
+    formatters: HashMap<String, Formatter>,
+}
+
+impl ColumnOptions {
+    pub fn set_order(&mut self, columns: &[&str]) {
+        self.order = columns.iter().map(|c| c.to_string()).collect();
+    }
+
+    pub fn hide(&mut self, column: &str) {
+        self.hidden.push(column.to_string());
+    }
+
+    pub fn set_label(&mut self, column: &str, label: &str) {
+        self.labels.insert(column.to_string(), label.to_string());
+    }
+
+    pub fn set_formatter(&mut self, column: &str, formatter: Formatter) {
+        self.formatters.insert(column.to_string(), formatter);
+    }
+
+    pub fn is_hidden(&self, column: &str) -> bool {
+        self.hidden.iter().any(|hidden| covers(hidden, column))
+    }
+
+    /// Colonnes visibles : celles de l'ordre configuré d'abord, les autres ensuite
+    /// dans leur ordre d'apparition
+    pub fn arrange(&self, columns: Vec<String>) -> Vec<String> {
+        let rank = |column: &str| self.order.iter()
+            .position(|ordered| covers(ordered, column))
+            .unwrap_or(self.order.len());
+
+        let mut visible: Vec<String> = columns.into_iter().filter(|c| !self.is_hidden(c)).collect();
+        // Tri stable : l'ordre d'apparition est conservé à rang égal
+        visible.sort_by_key(|column| rank(column));
+        visible
+    }
+
+    pub fn label<'a>(&'a self, column: &'a str) -> &'a str {
+        self.labels.get(column).map(String::as_str).unwrap_or(column)
+    }
+
+    /// Texte de la cellule si un formateur est défini pour la colonne
+    pub fn format(&self, column: &str, value: &Value) -> Option<String> {
+        self.formatters.get(column).map(|formatter| formatter.format(value))
+    }
+
+    /// Cellule HTML si un formateur est défini pour la colonne
+    pub fn format_html(&self, column: &str, value: &Value) -> Option<String> {
+        self.formatters.get(column).map(|formatter| formatter.format_html(value))
+    }
+}
+
+/// `adresse` couvre `adresse` et `adresse.ville`
+fn covers(column: &str, path: &str) -> bool {
+    path == column || path.strip_prefix(column).is_some_and(|rest| rest.starts_with('.'))
+}
@@ -0,0 +1,41 @@
+use crate::table::{Table, TableRenderer};
+
+/// Rendu CSV (RFC 4180) des données mises à plat, colonnes en chemins pointés
+pub struct CsvRenderer {
+    pub delimiter: char,
+}
+
+impl Default for CsvRenderer {
+    fn default() -> Self {
+        Self { delimiter: ',' }
+    }
+}
+
+impl CsvRenderer {
+    /// Ligne CSV terminée par CRLF ; un champ contenant le séparateur, un guillemet
+    /// ou un saut de ligne est mis entre guillemets
+    pub fn record<S: AsRef<str>>(&self, fields: &[S]) -> String {
+        let cells: Vec<String> = fields.iter()
+            .map(|field| {
+                let field = field.as_ref();
+                if field.contains([self.delimiter, '"', '\r', '\n']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.to_string()
+                }
+            })
+            .collect();
+        format!("{}\r\n", cells.join(&self.delimiter.to_string()))
+    }
+}
+
+impl TableRenderer for CsvRenderer {
+    fn render(&self, table: &Table) -> String {
+        let flat = table.flatten();
+        let mut output = self.record(&flat.headers);
+        for row in &flat.rows {
+            output.push_str(&self.record(row));
+        }
+        output
+    }
+}
@@ -0,0 +1,172 @@
+use serde_json::Value;
+use serde_json::Map;
+use crate::table::{cell_text, ColumnOptions, Table, TableRenderer};
+
+/// Rendu HTML : objets en tables clé/valeur, imbriquées pour les objets et tableaux ;
+/// en mode grille, tableaux d'objets en lignes
+pub struct HtmlRenderer;
+
+impl TableRenderer for HtmlRenderer {
+    fn render(&self, table: &Table) -> String {
+        HtmlWriter {
+            css_class: table.css_class(),
+            grid: table.is_grid(),
+            columns: table.columns(),
+        }.to_html(table.data())
+    }
+}
+
+struct HtmlWriter<'a> {
+    css_class: &'a str,
+    grid: bool,
+    columns: &'a ColumnOptions,
+}
+
+/// Éléments d'un tableau s'ils sont tous des objets (tableau non vide)
+fn records(arr: &[Value]) -> Option<Vec<&Map<String, Value>>> {
+    if arr.is_empty() {
+        return None;
+    }
+    arr.iter().map(Value::as_object).collect()
+}
+
+impl HtmlWriter<'_> {
+    /// Génère le code HTML de la table en fonction du type de données JSON
+    /// Crée une table simple clé-valeur avec tables imbriquées pour les tableaux
+    fn to_html(&self, data: &Value) -> String {
+        match data {
+            Value::Object(map) 
+                => self.generate_table(map),
+            Value::Array(arr) if self.grid => match records(arr) {
+                Some(records) => self.generate_grid(&records, self.css_class),
+                None if arr.is_empty() => format!("<table class='{}'><tr><td>Empty array</td></tr></table>", self.css_class),
+                None => self.create_array_table(arr),
+            },
+                _ => format!(
+                    "<table class='{}'><tr><td>Simple Value</td><td>{}</td></tr></table>", 
+                    self.css_class, 
+                    html_escape::encode_text(&data.to_string())
+                )
+        }
+    }    
+    
+    /// Génère une table avec les clés comme en-têtes de colonnes
+    fn generate_table(&self, map: &serde_json::Map<String, Value>) -> String {
+        let keys = self.columns.arrange(map.keys().cloned().collect());
+
+        // Créer les en-têtes à partir des clés
+        let headers: Vec<String> = keys.iter()
+            .map(|key| format!("<th>{}</th>", html_escape::encode_text(self.columns.label(key))))
+            .collect();
+
+        // Créer une seule rangée avec toutes les valeurs
+        let cells: Vec<String> = keys.iter()
+            .map(|key| {
+                let value = &map[key.as_str()];
+                let value_html = self.columns.format_html(key, value)
+                    .unwrap_or_else(|| self.format_value(value));
+                format!("<td>{}</td>", value_html)
+            })
+            .collect();
+
+        format!(
+            "<table class='{}'><thead><tr>{}</tr></thead><tbody><tr>{}</tr></tbody></table>",
+            self.css_class,
+            headers.join(""),
+            cells.join("")
+        )
+    }
+    
+    /// Formate une valeur - récursion pour objets et tableaux
+    fn format_value(&self, value: &Value) -> String {
+        match value {
+            Value::Object(obj) => {
+                if obj.is_empty() {
+                    "Empty object".to_string()
+                } else {
+                    // Récursion : réutilise generate_table pour les objets imbriqués
+                    format!("<span class='nested-table'>{}</span>", self.generate_table(obj))
+                }
+            }
+            Value::Array(arr) => {
+                if arr.is_empty() {
+                    "Empty array".to_string()
+                } else if let Some(records) = records(arr).filter(|_| self.grid) {
+                    format!("<div class='nested-table'>{}</div>", self.generate_grid(&records, "sub-table"))
+                } else {
+                    self.create_array_table(arr)
+                }
+            }
+            _ => html_escape::encode_text(&value.to_string()).to_string()
+        }
+    }
+
+    /// Crée une table pour un tableau avec récursion pour les éléments complexes
+    fn create_array_table(&self, arr: &[Value]) -> String {
+        let rows: Vec<String> = arr.iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let item_html = match item {
+                    Value::Object(obj) => {
+                        if obj.is_empty() {
+                            "Empty object".to_string()
+                        } else {
+                            // Récursion : réutilise generate_table pour les objets dans le tableau
+                            format!("<div class='nested-table'>{}</div>", self.generate_table(obj))
+                        }
+                    }
+                    _ => self.format_value(item) // Récursion pour autres types
+                };
+                
+                format!("<tr><td>{}</td><td>{}</td></tr>", i + 1, item_html)
+            })
+            .collect();
+
+        format!(
+            "<table class='sub-table'><tbody>{}</tbody></table>",
+            rows.join("")
+        )
+    }
+
+    /// Grille : l'union des clés en en-têtes, une ligne par objet
+    fn generate_grid(&self, records: &[&Map<String, Value>], css_class: &str) -> String {
+        let mut keys: Vec<String> = Vec::new();
+        for record in records {
+            for key in record.keys() {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        let keys = self.columns.arrange(keys);
+
+        let headers: Vec<String> = keys.iter()
+            .map(|key| format!("<th>{}</th>", html_escape::encode_text(self.columns.label(key))))
+            .collect();
+
+        let rows: Vec<String> = records.iter()
+            .map(|record| {
+                let cells: Vec<String> = keys.iter()
+                    .map(|key| format!("<td>{}</td>", record.get(key).map(|value| self.grid_cell(key, value)).unwrap_or_default()))
+                    .collect();
+                format!("<tr>{}</tr>", cells.join(""))
+            })
+            .collect();
+
+        format!(
+            "<table class='{}'><thead><tr>{}</tr></thead><tbody>{}</tbody></table>",
+            css_class,
+            headers.join(""),
+            rows.join("")
+        )
+    }
+
+    /// Cellule de grille : texte brut pour les scalaires, tables imbriquées sinon
+    fn grid_cell(&self, key: &str, value: &Value) -> String {
+        match (self.columns.format_html(key, value), value) {
+            (Some(html), _) => html,
+            (None, Value::Object(_) | Value::Array(_)) => self.format_value(value),
+            (None, scalar) => html_escape::encode_text(&cell_text(scalar)).to_string(),
+        }
+    }
+}
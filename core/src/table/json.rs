@@ -0,0 +1,23 @@
+use crate::table::{Table, TableRenderer};
+
+/// Rendu JSON des données telles quelles, sans mise à plat
+pub struct JsonRenderer {
+    pub pretty: bool,
+}
+
+impl Default for JsonRenderer {
+    fn default() -> Self {
+        Self { pretty: true }
+    }
+}
+
+impl TableRenderer for JsonRenderer {
+    fn render(&self, table: &Table) -> String {
+        let rendered = if self.pretty {
+            serde_json::to_string_pretty(table.data())
+        } else {
+            serde_json::to_string(table.data())
+        };
+        rendered.unwrap_or_default()
+    }
+}
@@ -0,0 +1,27 @@
+use crate::table::{Table, TableRenderer};
+
+/// Rendu en tableau Markdown (GitHub) des données mises à plat
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    fn cell(text: &str) -> String {
+        text.replace('|', "\\|").replace("\r\n", " ").replace('\n', " ")
+    }
+
+    fn line(cells: &[String]) -> String {
+        let cells: Vec<String> = cells.iter().map(|cell| Self::cell(cell)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    }
+}
+
+impl TableRenderer for MarkdownRenderer {
+    fn render(&self, table: &Table) -> String {
+        let flat = table.flatten();
+        let mut output = Self::line(&flat.headers);
+        output.push_str(&format!("|{}\n", " --- |".repeat(flat.headers.len())));
+        for row in &flat.rows {
+            output.push_str(&Self::line(row));
+        }
+        output
+    }
+}
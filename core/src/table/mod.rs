@@ -0,0 +1,305 @@
+pub mod html;
+pub mod csv;
+pub mod markdown;
+pub mod text;
+pub mod json;
+pub mod columns;
+
+use serde_json::Value;
+
+pub use columns::{ColumnOptions, Formatter};
+pub use html::HtmlRenderer;
+pub use csv::CsvRenderer;
+pub use markdown::MarkdownRenderer;
+pub use text::TextRenderer;
+pub use json::JsonRenderer;
+
+/// Rendu d'une `Table` dans un format de sortie
+///
+/// Les formats hiérarchiques (HTML, JSON) parcourent directement `Table::data` ;
+/// les formats à plat (CSV, Markdown, texte) partent de `Table::flatten`.
+pub trait TableRenderer {
+    fn render(&self, table: &Table) -> String;
+}
+
+pub struct Table<'a> {
+    data: &'a Value,
+    css_class: String,
+    grid: bool,
+    columns: ColumnOptions,
+}
+
+impl<'a> Table<'a> {
+    /// Crée une table à partir de données JSON
+    pub fn create(data: &'a Value, css_class: &str) -> Self {
+        Self {
+            data,
+            css_class : css_class.to_string(),
+            grid: false,
+            columns: ColumnOptions::default(),
+        }
+    }
+
+    pub fn with_css_class(mut self, css_class: &str) -> Self {
+        self.css_class = css_class.to_string();
+        self
+    }
+
+    /// Rend les tableaux d'objets en grille : une ligne par objet, l'union des clés en en-têtes
+    ///
+    /// Sans ce mode, le rendu HTML affiche chaque élément dans une sous-table indexée.
+    pub fn with_grid(mut self) -> Self {
+        self.grid = true;
+        self
+    }
+
+    /// Place ces colonnes en premier, dans cet ordre ; les autres suivent
+    pub fn with_columns(mut self, columns: &[&str]) -> Self {
+        self.columns.set_order(columns);
+        self
+    }
+
+    pub fn hide_column(mut self, column: &str) -> Self {
+        self.columns.hide(column);
+        self
+    }
+
+    /// Libellé d'en-tête à la place du nom de la colonne
+    pub fn with_label(mut self, column: &str, label: &str) -> Self {
+        self.columns.set_label(column, label);
+        self
+    }
+
+    pub fn with_formatter(mut self, column: &str, formatter: Formatter) -> Self {
+        self.columns.set_formatter(column, formatter);
+        self
+    }
+
+    pub fn is_grid(&self) -> bool {
+        self.grid
+    }
+
+    pub fn columns(&self) -> &ColumnOptions {
+        &self.columns
+    }
+
+    pub fn data(&self) -> &Value {
+        self.data
+    }
+
+    pub fn css_class(&self) -> &str {
+        &self.css_class
+    }
+
+    pub fn render(&self, renderer: &dyn TableRenderer) -> String {
+        renderer.render(self)
+    }
+
+    /// Table HTML, tables imbriquées pour les objets et tableaux
+    pub fn to_html(&self) -> String {
+        self.render(&HtmlRenderer)
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.render(&CsvRenderer::default())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.render(&MarkdownRenderer)
+    }
+
+    /// Texte aligné en colonnes, pour le terminal et les logs
+    pub fn to_text(&self) -> String {
+        self.render(&TextRenderer)
+    }
+
+    pub fn to_json(&self) -> String {
+        self.render(&JsonRenderer::default())
+    }
+
+    /// Met les données à plat : une ligne par élément d'un tableau (une seule pour un objet),
+    /// une colonne par chemin pointé (`adresse.ville`, `tags.0`)
+    ///
+    /// Les réglages de colonnes s'appliquent : ordre, colonnes masquées, libellés et formateurs.
+    pub fn flatten(&self) -> FlatTable {
+        let records: Vec<&Value> = match self.data {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+
+        let mut paths = Vec::new();
+        let mut cells_by_row = Vec::new();
+        for record in records {
+            let mut cells = Vec::new();
+            flatten_value(record, "", &mut cells);
+            for (path, _) in &cells {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+            cells_by_row.push(cells);
+        }
+        let paths = self.columns.arrange(paths);
+
+        let rows = cells_by_row.into_iter()
+            .map(|cells| paths.iter()
+                .map(|path| match cells.iter().find(|(cell_path, _)| cell_path == path) {
+                    Some((_, value)) => self.columns.format(path, value).unwrap_or_else(|| cell_text(value)),
+                    None => String::new(),
+                })
+                .collect())
+            .collect();
+
+        FlatTable {
+            headers: paths.iter().map(|path| self.columns.label(path).to_string()).collect(),
+            rows,
+        }
+    }
+}
+
+/// Données mises à plat : en-têtes et lignes de même longueur
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlatTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl FlatTable {
+    /// Largeur d'affichage de chaque colonne, en caractères
+    pub fn column_widths(&self) -> Vec<usize> {
+        self.headers.iter().enumerate()
+            .map(|(i, header)| {
+                self.rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(header.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+/// Texte d'une valeur scalaire : chaîne brute, vide pour `null`
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn flatten_value(value: &Value, path: &str, cells: &mut Vec<(String, Value)>) {
+    let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                flatten_value(item, &child(key), cells);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, item) in items.iter().enumerate() {
+                flatten_value(item, &child(&index.to_string()), cells);
+            }
+        }
+        Value::Object(_) | Value::Array(_) => cells.push((path_or_value(path), Value::Null)),
+        scalar => cells.push((path_or_value(path), scalar.clone())),
+    }
+}
+
+/// Une valeur scalaire à la racine est placée dans une colonne `value`
+fn path_or_value(path: &str) -> String {
+    if path.is_empty() { "value".to_string() } else { path.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_flattened_records_in_every_format() {
+        let data = json!([
+            {"login": "alice", "address": {"city": "Liège"}, "tags": ["a", "b"]},
+            {"login": "bob | \"b\"", "age": 30}
+        ]);
+        let table = Table::create(&data, "users");
+
+        let flat = table.flatten();
+        assert_eq!(flat.headers, vec!["login", "address.city", "tags.0", "tags.1", "age"]);
+        assert_eq!(flat.rows[1], vec!["bob | \"b\"", "", "", "", "30"]);
+
+        assert_eq!(table.to_csv(), concat!(
+            "login,address.city,tags.0,tags.1,age\r\n",
+            "alice,Liège,a,b,\r\n",
+            "\"bob | \"\"b\"\"\",,,,30\r\n",
+        ));
+        assert_eq!(table.to_markdown(), concat!(
+            "| login | address.city | tags.0 | tags.1 | age |\n",
+            "| --- | --- | --- | --- | --- |\n",
+            "| alice | Liège | a | b |  |\n",
+            "| bob \\| \"b\" |  |  |  | 30 |\n",
+        ));
+        assert_eq!(table.to_text(), concat!(
+            "login      address.city  tags.0  tags.1  age\n",
+            "---------  ------------  ------  ------  ---\n",
+            "alice      Liège         a       b\n",
+            "bob | \"b\"                                30\n",
+        ));
+
+        let scalar = json!("hello");
+        assert_eq!(Table::create(&scalar, "").to_csv(), "value\r\nhello\r\n");
+    }
+
+    #[test]
+    fn renders_records_as_grid_with_column_options() {
+        let data = json!([
+            {"id": 1, "login": "alice", "active": true, "size": 1536, "created_at": "2024-03-05T10:20:00Z"},
+            {"id": 2, "login": "<bob>", "active": false, "extra": {"a": 1}}
+        ]);
+        let table = Table::create(&data, "users")
+            .with_grid()
+            .with_columns(&["login", "created_at"])
+            .hide_column("id")
+            .with_label("login", "Login")
+            .with_formatter("created_at", Formatter::DateTime)
+            .with_formatter("active", Formatter::Boolean)
+            .with_formatter("size", Formatter::Bytes);
+
+        assert_eq!(table.to_html(), concat!(
+            "<table class='users'><thead><tr>",
+            "<th>Login</th><th>created_at</th><th>active</th><th>size</th><th>extra</th>",
+            "</tr></thead><tbody>",
+            "<tr><td>alice</td><td>2024-03-05 10:20</td><td>Oui</td><td>1.5 KB</td><td></td></tr>",
+            "<tr><td>&lt;bob&gt;</td><td></td><td>Non</td><td></td>",
+            "<td><span class='nested-table'><table class='users'><thead><tr><th>a</th></tr></thead>",
+            "<tbody><tr><td>1</td></tr></tbody></table></span></td></tr>",
+            "</tbody></table>",
+        ));
+
+        let flat = table.flatten();
+        assert_eq!(flat.headers, vec!["Login", "created_at", "active", "size", "extra.a"]);
+        assert_eq!(flat.rows[0], vec!["alice", "2024-03-05 10:20", "Oui", "1.5 KB", ""]);
+
+        // Sans le mode grille, le rendu historique en sous-tables indexées est conservé
+        let nested = json!({"rows": [{"a": 1}]});
+        assert!(Table::create(&nested, "t").to_html().contains("<table class='sub-table'><tbody><tr><td>1</td>"));
+        assert!(Table::create(&nested, "t").with_grid().to_html().contains("<table class='sub-table'><thead><tr><th>a</th>"));
+    }
+
+    #[test]
+    fn renders_image_attachments_as_thumbnails() {
+        let data = json!({"files_info": "photo, été.JPG (/api/files/1a, 2048 bytes),notes.pdf (/api/files/2b, 10 bytes),Error saving x: disk full"});
+        let table = Table::create(&data, "files").with_formatter("files_info", Formatter::Attachments);
+
+        assert_eq!(table.to_html(), concat!(
+            "<table class='files'><thead><tr><th>files_info</th></tr></thead><tbody><tr><td>",
+            "<a href='/api/files/1a' class='attachment'><img src='/api/files/1a/thumbnail' alt='photo, été.JPG' ",
+            "class='attachment-thumbnail' loading='lazy'></a> ",
+            "<a href='/api/files/2b' class='attachment'>notes.pdf</a> ",
+            "Error saving x: disk full",
+            "</td></tr></tbody></table>",
+        ));
+        assert_eq!(table.flatten().rows[0][0], "photo, été.JPG (/api/files/1a, 2048 bytes), notes.pdf (/api/files/2b, 10 bytes), Error saving x: disk full");
+    }
+}
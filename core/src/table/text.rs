@@ -0,0 +1,28 @@
+use crate::table::{Table, TableRenderer};
+
+/// Rendu texte aligné en colonnes, pour le terminal et les logs
+pub struct TextRenderer;
+
+impl TextRenderer {
+    fn line(cells: &[String], widths: &[usize]) -> String {
+        let padded: Vec<String> = cells.iter().zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell.replace(['\r', '\n'], " "), width = width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    }
+}
+
+impl TableRenderer for TextRenderer {
+    fn render(&self, table: &Table) -> String {
+        let flat = table.flatten();
+        let widths = flat.column_widths();
+
+        let mut output = Self::line(&flat.headers, &widths);
+        let underline: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        output.push_str(&Self::line(&underline, &widths));
+        for row in &flat.rows {
+            output.push_str(&Self::line(row, &widths));
+        }
+        output
+    }
+}
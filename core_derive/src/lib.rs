@@ -0,0 +1,224 @@
+//! Macros de dérivation du crate `core`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Field, Fields, Ident, LitStr, Path, Type};
+
+/// Dérive `FromDatabaseRow` : chaque champ est lu dans la colonne du même nom
+///
+/// Attributs `#[database(...)]` reconnus :
+/// - sur la struct : `crate = "core"`, chemin du crate `core` (par défaut `crate`)
+/// - sur un champ : `rename = "colonne"` pour un nom de colonne différent du champ,
+///   `default` pour un champ non lu, initialisé avec `Default::default()`
+#[proc_macro_derive(FromDatabaseRow, attributes(database))]
+pub fn derive_from_database_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_database_row(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Dérive `Entity` : table, clé primaire et colonnes déduites de la struct
+///
+/// En plus des attributs de `FromDatabaseRow` :
+/// - sur la struct : `table = "nom"` (obligatoire), `sort = "-created_at"` pour le tri par défaut
+/// - sur un champ : `primary_key` (par défaut le champ `id`), `generated` pour une
+///   colonne remplie par la base (`SERIAL`...), jamais écrite
+#[proc_macro_derive(Entity, attributes(database))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_entity(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Attributs `#[database(...)]` de la struct
+struct ContainerAttributes {
+    krate: Path,
+    table: Option<String>,
+    sort: Option<String>,
+}
+
+/// Attributs `#[database(...)]` d'un champ, et colonne associée
+struct FieldAttributes {
+    column: String,
+    default: bool,
+    primary_key: bool,
+    generated: bool,
+}
+
+fn container_attributes(attrs: &[Attribute]) -> syn::Result<ContainerAttributes> {
+    let mut attributes = ContainerAttributes {
+        krate: syn::parse_quote!(crate),
+        table: None,
+        sort: None,
+    };
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("database")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                attributes.krate = meta.value()?.parse::<LitStr>()?.parse()?;
+            } else if meta.path.is_ident("table") {
+                attributes.table = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("sort") {
+                attributes.sort = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("unsupported database attribute, expected `crate`, `table` or `sort`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(attributes)
+}
+
+fn field_attributes(field: &Field) -> syn::Result<FieldAttributes> {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut attributes = FieldAttributes {
+        // `r#type` est lu dans la colonne `type`
+        column: ident.unraw().to_string(),
+        default: false,
+        primary_key: false,
+        generated: false,
+    };
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("database")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attributes.column = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("default") {
+                attributes.default = true;
+            } else if meta.path.is_ident("primary_key") {
+                attributes.primary_key = true;
+            } else if meta.path.is_ident("generated") {
+                attributes.generated = true;
+            } else {
+                return Err(meta.error(
+                    "unsupported database attribute, expected `rename`, `default`, `primary_key` or `generated`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(attributes)
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<&Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(&input.ident, "database derives require named fields")),
+        },
+        _ => Err(syn::Error::new_spanned(&input.ident, "database derives can only be used on structs")),
+    }
+}
+
+fn expand_from_database_row(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let container = container_attributes(&input.attrs)?;
+    let krate = &container.krate;
+
+    let mut initializers = Vec::new();
+    for field in named_fields(&input)? {
+        let ident = field.ident.as_ref().expect("named field");
+        let attributes = field_attributes(field)?;
+        let column = &attributes.column;
+
+        initializers.push(if attributes.default {
+            quote! { #ident: ::std::default::Default::default() }
+        } else {
+            quote! { #ident: row.decode(#column)? }
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::repositories::_from_row::FromDatabaseRow for #name #ty_generics #where_clause {
+            fn from_row(
+                row: &#krate::repositories::_database::DatabaseRow,
+            ) -> ::std::result::Result<Self, #krate::repositories::_from_row::RowError> {
+                ::std::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
+
+fn expand_entity(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let container = container_attributes(&input.attrs)?;
+    let krate = &container.krate;
+    let table = container.table.as_ref().ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "Entity requires #[database(table = \"...\")]")
+    })?;
+
+    let mut columns = Vec::new();
+    let mut values = Vec::new();
+    let mut primary_key: Option<(String, Ident, Type)> = None;
+    let explicit_key = input_has_explicit_key(&input);
+
+    for field in named_fields(&input)? {
+        let ident = field.ident.as_ref().expect("named field");
+        let attributes = field_attributes(field)?;
+        if attributes.default {
+            continue;
+        }
+
+        let column = attributes.column.clone();
+        if attributes.primary_key || (!explicit_key && ident.unraw() == "id") {
+            primary_key = Some((column.clone(), ident.clone(), field.ty.clone()));
+        }
+        if !attributes.generated {
+            values.push(quote! { (#column, #krate::repositories::_database::DbValue::from(::std::clone::Clone::clone(&self.#ident))) });
+        }
+        columns.push(column);
+    }
+
+    let (key_column, key_ident, key_type) = primary_key.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "Entity requires an `id` field or a #[database(primary_key)] field")
+    })?;
+
+    let default_sort = container.sort.map(|sort| quote! {
+        fn default_sort() -> #krate::repositories::_repository::Sort {
+            #krate::repositories::_repository::Sort::parse(#sort)
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::repositories::_repository::Entity for #name #ty_generics #where_clause {
+            type Id = #key_type;
+
+            const TABLE: &'static str = #table;
+            const PRIMARY_KEY: &'static str = #key_column;
+            const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+
+            fn id(&self) -> Self::Id {
+                ::std::clone::Clone::clone(&self.#key_ident)
+            }
+
+            fn values(&self) -> ::std::vec::Vec<(&'static str, #krate::repositories::_database::DbValue)> {
+                ::std::vec![#(#values),*]
+            }
+
+            #default_sort
+        }
+    })
+}
+
+/// Indique si un champ porte explicitement `#[database(primary_key)]`
+fn input_has_explicit_key(input: &DeriveInput) -> bool {
+    named_fields(input)
+        .map(|fields| fields.iter().any(|field| {
+            field_attributes(field).map(|attributes| attributes.primary_key).unwrap_or(false)
+        }))
+        .unwrap_or(false)
+}
@@ -0,0 +1,148 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use core::repositories::ApiKeyRepository;
+use core::_database::DatabaseQuery;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use super::AuthError;
+use crate::rate_limit;
+
+/// Préfixe des clés, pour les reconnaître dans un fichier de configuration ou un journal
+pub const KEY_PREFIX: &str = "ck_";
+
+/// Portées reconnues : `read` pour GET/HEAD/OPTIONS, `write` pour les autres méthodes
+pub const SCOPES: &[&str] = &["read", "write"];
+
+/// Nouvelle clé : la valeur complète n'est montrée qu'une fois, à la création
+pub struct GeneratedKey {
+    pub key: String,
+    /// Début de la clé, stocké en clair pour l'identifier
+    pub prefix: String,
+    pub hash: String,
+}
+
+/// Clé aléatoire de 256 bits, `ck_` suivi de 64 caractères hexadécimaux
+pub fn generate() -> GeneratedKey {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let key = format!("{}{}", KEY_PREFIX, hex::encode(bytes));
+    GeneratedKey { prefix: key[..KEY_PREFIX.len() + 8].to_string(), hash: key_hash(&key), key }
+}
+
+/// Empreinte stockée en base ; la clé étant aléatoire, un SHA-256 suffit (pas de dictionnaire possible)
+pub fn key_hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Valide une liste de portées séparées par des virgules, sans doublon
+pub fn parse_scopes(scopes: &str) -> Result<String, String> {
+    let mut parsed: Vec<&str> = Vec::new();
+    for scope in scopes.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if !SCOPES.contains(&scope) {
+            return Err(format!("Unknown scope '{}' (expected one of: {})", scope, SCOPES.join(", ")));
+        }
+        if !parsed.contains(&scope) {
+            parsed.push(scope);
+        }
+    }
+    if parsed.is_empty() {
+        return Err("At least one scope is required".to_string());
+    }
+    Ok(parsed.join(","))
+}
+
+/// Portée nécessaire pour la méthode de la requête
+pub fn required_scope(method: &Method) -> &'static str {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) { "read" } else { "write" }
+}
+
+/// Clé d'API ayant authentifié la requête, placée dans les extensions pour l'autorisation et les journaux
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKeyIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+}
+
+/// Jeton de l'en-tête `Authorization: Bearer …`
+fn bearer_token(req: &ServiceRequest) -> Option<String> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim().to_string())
+}
+
+/// Clé inconnue, révoquée ou expirée (401), comptée contre l'adresse IP par le limiteur de débit
+async fn refuse_key(req: ServiceRequest, reason: &'static str) -> ServiceResponse {
+    rate_limit::record_failed_key(&req).await;
+    req.error_response(AuthError::InvalidApiKey(reason))
+}
+
+/// Middleware de l'API : une requête avec `Authorization: Bearer` doit porter une clé valide,
+/// non révoquée, non expirée et dont les portées couvrent la méthode ; sans en-tête, elle passe
+/// telle quelle (session ou route publique)
+///
+/// Une adresse qui a épuisé son débit, par exemple à force de clés refusées, reçoit 429 avant
+/// toute recherche de la clé en base.
+pub async fn authenticate_bearer(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    let Some(token) = bearer_token(&req) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if let Some(response) = rate_limit::before_key_check(&req).await {
+        return Ok(req.into_response(response));
+    }
+    let Some(db) = req.app_data::<web::Data<DatabaseQuery>>().cloned() else {
+        return Ok(req.error_response(AuthError::Unavailable("database is not configured".to_string())));
+    };
+
+    let keys = ApiKeyRepository::new(db.get_ref().clone());
+    let key = match keys.find_by_hash(&key_hash(&token)).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return Ok(refuse_key(req, "unknown API key").await),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return Ok(req.error_response(AuthError::Unavailable(e.to_string())));
+        }
+    };
+    if key.revoked_at.is_some() {
+        return Ok(refuse_key(req, "API key has been revoked").await);
+    }
+    if key.is_expired(OffsetDateTime::now_utc()) {
+        return Ok(refuse_key(req, "API key has expired").await);
+    }
+    let scope = required_scope(req.method());
+    if !key.has_scope(scope) {
+        return Ok(req.error_response(AuthError::InsufficientScope(scope)));
+    }
+
+    if let Err(e) = keys.touch(key.id).await {
+        println!("Database error while recording API key use: {}", e);
+    }
+    req.extensions_mut().insert(ApiKeyIdentity { id: key.id, user_id: key.user_id, name: key.name, prefix: key.prefix });
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_keys_and_parses_scopes() {
+        let generated = generate();
+        assert!(generated.key.starts_with(KEY_PREFIX) && generated.key.len() == KEY_PREFIX.len() + 64);
+        assert!(generated.key.starts_with(&generated.prefix));
+        assert_eq!(generated.hash, key_hash(&generated.key));
+        assert_ne!(generated.key, generate().key);
+
+        assert_eq!(parse_scopes("read, write,read"), Ok("read,write".to_string()));
+        assert!(parse_scopes("admin").is_err());
+        assert!(parse_scopes(" , ").is_err());
+        assert_eq!(required_scope(&Method::GET), "read");
+        assert_eq!(required_scope(&Method::DELETE), "write");
+    }
+}
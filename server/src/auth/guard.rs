@@ -0,0 +1,144 @@
+use actix_web::body::BoxBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error, HttpMessage, HttpRequest};
+use core::repositories::{RoleRepository, UserRepository};
+use core::_database::DatabaseQuery;
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use super::{authenticate, AuthError, AuthenticatedUser};
+
+/// Permission vérifiée par une route ; accordée par les rôles (table `role_permissions`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Permission(&'static str);
+
+impl Permission {
+    /// Lister tous les utilisateurs
+    pub const USERS_LIST: Permission = Permission("users:list");
+    /// Lire la fiche d'un autre utilisateur
+    pub const USERS_READ: Permission = Permission("users:read");
+    /// Créer un utilisateur ou modifier la fiche d'un autre
+    pub const USERS_WRITE: Permission = Permission("users:write");
+    pub const USERS_DELETE: Permission = Permission("users:delete");
+    /// Télécharger les fichiers d'un autre utilisateur, en voir la miniature ou les partager
+    pub const FILES_READ: Permission = Permission("files:read");
+    /// Supprimer les fichiers d'un autre utilisateur
+    pub const FILES_DELETE: Permission = Permission("files:delete");
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// Permissions de l'utilisateur de la requête, chargées une fois puis gardées dans les extensions
+#[derive(Clone, Debug)]
+pub struct Permissions(pub Vec<String>);
+
+impl Permissions {
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.0.iter().any(|p| p == permission.as_str())
+    }
+}
+
+/// Garde déclarative d'une route : `web::get().to(handler).wrap(Authorize::permission(...))`
+///
+/// Sans session ni clé d'API : 401 ; sans la permission : 403, dans l'enveloppe `HttpSendResponse`.
+#[derive(Clone, Copy, Debug)]
+pub struct Authorize {
+    permission: Permission,
+    /// Paramètre de chemin (ID ou login) qui, s'il désigne l'utilisateur lui-même, dispense de la permission
+    self_param: Option<&'static str>,
+}
+
+impl Authorize {
+    pub fn permission(permission: Permission) -> Self {
+        Self { permission, self_param: None }
+    }
+
+    /// Accepte aussi la requête qui porte sur l'utilisateur connecté (`/users/{key}` sur sa propre fiche)
+    pub fn or_self(self, param: &'static str) -> Self {
+        Self { self_param: Some(param), ..self }
+    }
+
+    /// La clé est résolue comme dans les handlers (`find_user`), puis comparée par ID :
+    /// un login qui ressemble à l'ID d'un autre ne désigne pas ce dernier
+    async fn is_self(&self, req: &ServiceRequest, auth: &AuthenticatedUser) -> Result<bool, AuthError> {
+        let Some(key) = self.self_param.and_then(|param| req.match_info().get(param)) else {
+            return Ok(false);
+        };
+        let Some(db) = req.app_data::<web::Data<DatabaseQuery>>() else {
+            return Err(AuthError::Unavailable("database is not configured".to_string()));
+        };
+        let target = UserRepository::new(db.get_ref().clone()).find_user(key).await.map_err(super::unavailable)?;
+        Ok(target.is_some_and(|user| user.id == auth.user.id))
+    }
+
+    async fn check(&self, req: &ServiceRequest) -> Result<(), AuthError> {
+        let auth = authenticate(req.request()).await?;
+        if self.is_self(req, &auth).await? || permissions(req.request(), &auth).await?.allows(self.permission) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden(self.permission.as_str()))
+        }
+    }
+}
+
+/// Permissions de l'utilisateur, pour les handlers qui décident eux-mêmes de l'accès
+pub(crate) async fn permissions(req: &HttpRequest, auth: &AuthenticatedUser) -> Result<Permissions, AuthError> {
+    if let Some(permissions) = req.extensions().get::<Permissions>() {
+        return Ok(permissions.clone());
+    }
+    let Some(db) = req.app_data::<web::Data<DatabaseQuery>>() else {
+        return Err(AuthError::Unavailable("database is not configured".to_string()));
+    };
+    let permissions = RoleRepository::new(db.get_ref().clone()).permissions_of(auth.user.id).await
+        .map(Permissions)
+        .map_err(super::unavailable)?;
+    req.extensions_mut().insert(permissions.clone());
+    Ok(permissions)
+}
+
+impl<S> Transform<S, ServiceRequest> for Authorize
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = AuthorizeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthorizeMiddleware { service: Rc::new(service), rule: *self }))
+    }
+}
+
+pub struct AuthorizeMiddleware<S> {
+    service: Rc<S>,
+    rule: Authorize,
+}
+
+impl<S> Service<ServiceRequest> for AuthorizeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    // `forward_ready!` passe par `::core`, masqué ici par le crate du projet
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let rule = self.rule;
+        Box::pin(async move {
+            match rule.check(&req).await {
+                Ok(()) => service.call(req).await,
+                Err(e) => Ok(req.error_response(e)),
+            }
+        })
+    }
+}
@@ -0,0 +1,138 @@
+pub mod api_key;
+pub mod guard;
+pub mod oidc;
+pub mod password;
+pub mod session;
+pub mod totp;
+
+pub use api_key::ApiKeyIdentity;
+pub use guard::{Authorize, Permission};
+pub use oidc::OidcClient;
+pub use session::SessionManager;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use actix_web::http::header;
+use core::repositories::{Session, User, UserRepository};
+use core::{HttpSendResponse, _database::DatabaseQuery};
+use futures::future::LocalBoxFuture;
+
+/// Utilisateur de la requête, authentifié par le cookie de session ou par une clé d'API
+///
+/// En argument d'un handler, refuse la requête (401) sans session ni clé valide ;
+/// `Option<AuthenticatedUser>` accepte aussi les requêtes anonymes.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub user: User,
+    /// `None` pour une requête authentifiée par clé d'API
+    pub session: Option<Session>,
+    pub api_key: Option<ApiKeyIdentity>,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    /// Pas de cookie, session inconnue ou expirée (401)
+    Unauthenticated,
+    /// Clé d'API inconnue, révoquée ou expirée (401)
+    InvalidApiKey(&'static str),
+    /// Clé d'API valide sans la portée nécessaire (403)
+    InsufficientScope(&'static str),
+    /// Utilisateur authentifié sans la permission nécessaire (403)
+    Forbidden(&'static str),
+    /// Base ou configuration indisponible (500)
+    Unavailable(String),
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Unauthenticated => write!(f, "Authentication required"),
+            AuthError::InvalidApiKey(reason) => write!(f, "Invalid API key: {}", reason),
+            AuthError::InsufficientScope(scope) => write!(f, "API key lacks the '{}' scope", scope),
+            AuthError::Forbidden(permission) => write!(f, "Permission '{}' required", permission),
+            AuthError::Unavailable(reason) => write!(f, "Authentication unavailable: {}", reason),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Unauthenticated | AuthError::InvalidApiKey(_) => StatusCode::UNAUTHORIZED,
+            AuthError::InsufficientScope(_) | AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AuthError::Unavailable(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        // RFC 6750 : le client machine sait s'il doit changer de clé ou de portées
+        match self {
+            AuthError::InvalidApiKey(_) => {
+                response.insert_header((header::WWW_AUTHENTICATE, "Bearer error=\"invalid_token\""));
+            },
+            AuthError::InsufficientScope(scope) => {
+                response.insert_header((header::WWW_AUTHENTICATE, format!("Bearer error=\"insufficient_scope\", scope=\"{}\"", scope)));
+            },
+            _ => {},
+        }
+        response.json(HttpSendResponse {
+            status: self.status_code().as_u16(),
+            message: Some(self.to_string()),
+            data: None,
+        })
+    }
+}
+
+pub(crate) fn unavailable(e: anyhow::Error) -> AuthError {
+    println!("Database error: {}", e);
+    AuthError::Unavailable(e.to_string())
+}
+
+/// Retrouve l'utilisateur de la clé d'API (vérifiée par `api_key::authenticate_bearer`)
+/// ou, à défaut, de la session du cookie ; le résultat est gardé dans les extensions de la requête
+pub(crate) async fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, AuthError> {
+    if let Some(authenticated) = req.extensions().get::<AuthenticatedUser>() {
+        return Ok(authenticated.clone());
+    }
+    let (Some(sessions), Some(db)) = (req.app_data::<web::Data<SessionManager>>(), req.app_data::<web::Data<DatabaseQuery>>()) else {
+        return Err(AuthError::Unavailable("sessions are not configured".to_string()));
+    };
+
+    let api_key = req.extensions().get::<ApiKeyIdentity>().cloned();
+    let authenticated = match api_key {
+        Some(api_key) => {
+            let user = UserRepository::new(db.get_ref().clone()).get_user_by_id(api_key.user_id).await.map_err(unavailable)?;
+            let user = user.ok_or(AuthError::InvalidApiKey("API key owner no longer exists"))?;
+            AuthenticatedUser { user, session: None, api_key: Some(api_key) }
+        },
+        None => {
+            let token = sessions.token(req).ok_or(AuthError::Unauthenticated)?;
+            let (session, user) = sessions.resolve(db, &token).await.map_err(unavailable)?.ok_or(AuthError::Unauthenticated)?;
+            AuthenticatedUser { user, session: Some(session), api_key: None }
+        },
+    };
+    req.extensions_mut().insert(authenticated.clone());
+    Ok(authenticated)
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate(&req).await })
+    }
+}
+
+/// Middleware des routes réservées aux utilisateurs connectés (`server_lib`)
+pub async fn require_login(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    match authenticate(req.request()).await {
+        Ok(_) => Ok(next.call(req).await?.map_into_boxed_body()),
+        Err(e) => Ok(req.error_response(e)),
+    }
+}
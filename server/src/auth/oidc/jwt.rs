@@ -0,0 +1,95 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Clé publique d'un fournisseur, telle que publiée dans son JWKS
+#[derive(Clone, Debug, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: Option<String>,
+    pub alg: Option<String>,
+    #[serde(rename = "use")]
+    pub usage: Option<String>,
+    /// Module et exposant d'une clé RSA
+    pub n: Option<String>,
+    pub e: Option<String>,
+    /// Courbe et coordonnées d'une clé EC
+    pub crv: Option<String>,
+    pub x: Option<String>,
+    pub y: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Header {
+    pub alg: String,
+    pub kid: Option<String>,
+}
+
+/// JWS compact (`en-tête.contenu.signature`) décodé, signature non vérifiée
+pub struct Jws<'a> {
+    pub header: Header,
+    pub claims: Value,
+    signing_input: &'a str,
+    signature: Vec<u8>,
+}
+
+pub fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl<'a> Jws<'a> {
+    pub fn decode(token: &'a str) -> Result<Self, &'static str> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(claims), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err("malformed token");
+        };
+        let header: Header = decode_base64(header)
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or("malformed token header")?;
+        let claims: Value = decode_base64(claims)
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .filter(Value::is_object)
+            .ok_or("malformed token claims")?;
+        let signature = decode_base64(signature).ok_or("malformed token signature")?;
+        Ok(Jws { header, claims, signing_input: &token[..token.rfind('.').unwrap_or(0)], signature })
+    }
+
+    /// Vérifie la signature avec une clé du JWKS ; seuls RS256 et ES256 sont acceptés,
+    /// ce qui écarte `none` et les algorithmes HMAC
+    pub fn verify(&self, key: &Jwk) -> bool {
+        if key.alg.as_deref().is_some_and(|alg| alg != self.header.alg) || key.usage.as_deref().is_some_and(|usage| usage != "sig") {
+            return false;
+        }
+        let message = self.signing_input.as_bytes();
+        match (self.header.alg.as_str(), key.kty.as_str()) {
+            ("RS256", "RSA") => {
+                let (Some(n), Some(e)) = (key.n.as_deref().and_then(decode_base64), key.e.as_deref().and_then(decode_base64)) else {
+                    return false;
+                };
+                RsaPublicKeyComponents { n, e }.verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, &self.signature).is_ok()
+            },
+            ("ES256", "EC") if key.crv.as_deref() == Some("P-256") => {
+                let (Some(x), Some(y)) = (key.x.as_deref().and_then(decode_base64), key.y.as_deref().and_then(decode_base64)) else {
+                    return false;
+                };
+                // Point non compressé : 0x04 || x || y
+                let mut point = vec![4u8];
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point).verify(message, &self.signature).is_ok()
+            },
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Fournisseur OpenID Connect minimal pour les tests : découverte, JWKS, autorisation
+//! avec PKCE et émission d'ID tokens ES256, en HTTP sur 127.0.0.1
+
+use super::jwt::encode_base64;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use url::Url;
+
+const KEY_ID: &str = "mock-key";
+
+/// Autorisation accordée, en attente de l'échange du code
+struct Grant {
+    nonce: String,
+    challenge: String,
+    redirect_uri: String,
+}
+
+struct State {
+    issuer: String,
+    client_id: String,
+    key: EcdsaKeyPair,
+    codes: Mutex<HashMap<String, Grant>>,
+}
+
+/// Le fournisseur tourne sur son propre thread jusqu'à la fin des tests ;
+/// il connecte toujours le même utilisateur, `alice`
+pub struct MockProvider {
+    state: Arc<State>,
+}
+
+impl MockProvider {
+    pub fn start(client_id: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let state = Arc::new(State {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            client_id: client_id.to_string(),
+            key: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap(),
+            codes: Mutex::new(HashMap::new()),
+        });
+
+        let server = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                server.handle(stream);
+            }
+        });
+        MockProvider { state }
+    }
+
+    pub fn issuer(&self) -> String {
+        self.state.issuer.clone()
+    }
+
+    /// ID token signé pour `audience`, émis à `now`
+    pub fn id_token(&self, audience: &str, nonce: &str, now: i64) -> String {
+        self.state.id_token(audience, nonce, now, json!({}))
+    }
+
+    /// ID token dont `overrides` remplace ou ajoute des claims
+    pub fn id_token_with(&self, audience: &str, nonce: &str, now: i64, overrides: serde_json::Value) -> String {
+        self.state.id_token(audience, nonce, now, overrides)
+    }
+}
+
+impl State {
+    fn id_token(&self, audience: &str, nonce: &str, now: i64, overrides: serde_json::Value) -> String {
+        let header = json!({ "alg": "ES256", "typ": "JWT", "kid": KEY_ID });
+        let mut claims = json!({
+            "iss": self.issuer,
+            "sub": "user-1",
+            "aud": audience,
+            "iat": now,
+            "exp": now + 300,
+            "nonce": nonce,
+            "preferred_username": "alice",
+            "email": "alice@example.com",
+            "email_verified": true,
+            "given_name": "Alice",
+            "family_name": "Liddell",
+        });
+        if let (Some(claims), Some(overrides)) = (claims.as_object_mut(), overrides.as_object()) {
+            claims.extend(overrides.clone());
+        }
+        let signing_input = format!("{}.{}", encode_base64(header.to_string().as_bytes()), encode_base64(claims.to_string().as_bytes()));
+        let signature = self.key.sign(&SystemRandom::new(), signing_input.as_bytes()).unwrap();
+        format!("{}.{}", signing_input, encode_base64(signature.as_ref()))
+    }
+
+    fn jwks(&self) -> serde_json::Value {
+        // Point non compressé : 0x04 || x || y
+        let point = self.key.public_key().as_ref();
+        json!({ "keys": [{
+            "kty": "EC", "crv": "P-256", "use": "sig", "alg": "ES256", "kid": KEY_ID,
+            "x": encode_base64(&point[1..33]),
+            "y": encode_base64(&point[33..65]),
+        }] })
+    }
+
+    fn handle(&self, stream: TcpStream) {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0u8; length];
+        let _ = reader.read_exact(&mut body);
+
+        let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let url = Url::parse(&format!("http://mock{}", target)).unwrap();
+        let params: HashMap<String, String> = match url.path() {
+            "/token" => url::form_urlencoded::parse(&body).into_owned().collect(),
+            _ => url.query_pairs().into_owned().collect(),
+        };
+        let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+
+        let response = match url.path() {
+            "/.well-known/openid-configuration" => reply(200, &[], json!({
+                "issuer": self.issuer,
+                "authorization_endpoint": format!("{}/authorize", self.issuer),
+                "token_endpoint": format!("{}/token", self.issuer),
+                "jwks_uri": format!("{}/jwks", self.issuer),
+            })),
+            "/jwks" => reply(200, &[], self.jwks()),
+            "/authorize" if param("client_id") == self.client_id && param("code_challenge_method") == "S256" => {
+                let code = uuid::Uuid::new_v4().simple().to_string();
+                let mut location = Url::parse(&param("redirect_uri")).unwrap();
+                location.query_pairs_mut().append_pair("code", &code).append_pair("state", &param("state"));
+                self.codes.lock().unwrap().insert(code, Grant {
+                    nonce: param("nonce"),
+                    challenge: param("code_challenge"),
+                    redirect_uri: param("redirect_uri"),
+                });
+                reply(302, &[("Location", location.as_str())], json!({}))
+            },
+            "/token" => {
+                let mut codes = self.codes.lock().unwrap();
+                let challenge = encode_base64(&Sha256::digest(param("code_verifier").as_bytes()));
+                match codes.get(&param("code")) {
+                    Some(grant) if grant.challenge == challenge && grant.redirect_uri == param("redirect_uri") && param("client_id") == self.client_id => {
+                        let grant = codes.remove(&param("code")).unwrap();
+                        let now = OffsetDateTime::now_utc().unix_timestamp();
+                        reply(200, &[], json!({
+                            "access_token": "mock-access-token",
+                            "token_type": "Bearer",
+                            "id_token": self.id_token(&self.client_id, &grant.nonce, now, json!({})),
+                        }))
+                    },
+                    _ => reply(400, &[], json!({ "error": "invalid_grant", "error_description": "invalid code or verifier" })),
+                }
+            },
+            _ => reply(400, &[], json!({ "error": "invalid_request" })),
+        };
+        let _ = reader.into_inner().write_all(&response);
+    }
+}
+
+fn reply(status: u16, headers: &[(&str, &str)], body: serde_json::Value) -> Vec<u8> {
+    let body = body.to_string();
+    let mut response = format!("HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+    response.into_bytes()
+}
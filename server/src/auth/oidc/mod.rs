@@ -0,0 +1,595 @@
+pub mod jwt;
+#[cfg(test)]
+mod mock_provider;
+
+use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
+use actix_web::http::StatusCode;
+use actix_web::HttpRequest;
+use anyhow::Result;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use core::config::OidcConfig;
+use core::repositories::{LoginTaken, OidcIdentityRepository, User, UserRepository};
+use core::_database::DatabaseQuery;
+use jwt::{Jwk, JwkSet, Jws};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use url::Url;
+
+/// Cookie qui garde `state`, `nonce` et vérificateur PKCE entre la redirection et le retour
+pub const FLOW_COOKIE: &str = "oidc_flow";
+/// Délai laissé pour s'authentifier chez le fournisseur, en secondes
+const FLOW_TTL: i64 = 600;
+/// Tolérance sur les horloges pour `exp` et `iat`, en secondes
+const CLOCK_LEEWAY: i64 = 60;
+/// Intervalle minimal entre deux rechargements du JWKS provoqués par une clé inconnue
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// Échec d'une connexion OIDC
+#[derive(Debug, PartialEq)]
+pub enum OidcError {
+    /// Fournisseur injoignable ou réponse inattendue (502)
+    Provider(String),
+    /// Cookie de connexion absent, expiré ou `state` différent (400)
+    InvalidState,
+    /// Le fournisseur a refusé l'autorisation (401)
+    Denied(String),
+    /// ID token mal signé, expiré ou destiné à un autre client (401)
+    InvalidToken(String),
+}
+
+impl OidcError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            OidcError::Provider(_) => StatusCode::BAD_GATEWAY,
+            OidcError::InvalidState => StatusCode::BAD_REQUEST,
+            OidcError::Denied(_) | OidcError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+impl std::fmt::Display for OidcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OidcError::Provider(reason) => write!(f, "Identity provider error: {}", reason),
+            OidcError::InvalidState => write!(f, "Invalid or expired login state"),
+            OidcError::Denied(reason) => write!(f, "Login denied by the identity provider: {}", reason),
+            OidcError::InvalidToken(reason) => write!(f, "Invalid ID token: {}", reason),
+        }
+    }
+}
+
+fn invalid_token(reason: &str) -> OidcError {
+    OidcError::InvalidToken(reason.to_string())
+}
+
+/// Extrait de `/.well-known/openid-configuration`
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    jwt::encode_base64(&bytes)
+}
+
+/// Connexion en cours, le temps de l'aller-retour chez le fournisseur
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoginFlow {
+    /// Lie le retour du fournisseur au navigateur qui a commencé la connexion
+    pub state: String,
+    /// Recopié dans l'ID token, qui ne peut donc pas être rejoué
+    pub nonce: String,
+    /// Vérificateur PKCE, dont seule l'empreinte part avec la redirection
+    pub verifier: String,
+    /// Liaison de l'identité au compte déjà connecté plutôt que connexion
+    pub link: bool,
+}
+
+impl LoginFlow {
+    pub fn generate() -> Self {
+        LoginFlow { state: random_token(), nonce: random_token(), verifier: random_token(), link: false }
+    }
+
+    /// Connexion qui lie l'identité au compte de la session, voir `link`
+    pub fn generate_link() -> Self {
+        LoginFlow { link: true, ..Self::generate() }
+    }
+
+    /// `code_challenge` de la méthode S256
+    pub fn challenge(&self) -> String {
+        jwt::encode_base64(&Sha256::digest(self.verifier.as_bytes()))
+    }
+
+    /// Connexion en cours d'après le cookie de la requête
+    pub fn from_request(req: &HttpRequest) -> Option<Self> {
+        let cookie = req.cookie(FLOW_COOKIE)?;
+        let mut parts = cookie.value().split('.');
+        let (state, nonce, verifier) = (parts.next()?, parts.next()?, parts.next()?);
+        let link = match (parts.next(), parts.next()) {
+            (None, None) => false,
+            (Some("link"), None) => true,
+            _ => return None,
+        };
+        if state.is_empty() || nonce.is_empty() || verifier.is_empty() {
+            return None;
+        }
+        Some(LoginFlow { state: state.to_string(), nonce: nonce.to_string(), verifier: verifier.to_string(), link })
+    }
+
+    /// Cookie `HttpOnly` limité aux routes OIDC ; `SameSite=Lax` le laisse revenir
+    /// avec la redirection du fournisseur
+    pub fn cookie(&self, secure: bool) -> Cookie<'static> {
+        let purpose = if self.link { ".link" } else { "" };
+        Cookie::build(FLOW_COOKIE, format!("{}.{}.{}{}", self.state, self.nonce, self.verifier, purpose))
+            .path("/api/auth/oidc")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(secure)
+            .max_age(CookieDuration::seconds(FLOW_TTL))
+            .finish()
+    }
+
+    pub fn removal_cookie(secure: bool) -> Cookie<'static> {
+        let mut cookie = LoginFlow { state: String::new(), nonce: String::new(), verifier: String::new(), link: false }.cookie(secure);
+        cookie.make_removal();
+        cookie
+    }
+}
+
+/// Identité validée par l'ID token
+#[derive(Clone, Debug, PartialEq)]
+pub struct Identity {
+    /// `iss` et `sub` : seul identifiant qui désigne un compte, voir `sign_in`
+    pub issuer: String,
+    pub subject: String,
+    /// Claim `OIDC_LOGIN_CLAIM`, sinon l'e-mail vérifié, sinon `sub`
+    pub login: String,
+    /// Absent tant que le fournisseur ne le déclare pas vérifié (`email_verified`)
+    pub email: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+}
+
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched_at: Instant,
+}
+
+/// Client OpenID Connect : découverte, PKCE, échange du code et validation de l'ID token
+///
+/// Le client HTTP est bloquant ; les handlers l'appellent depuis `web::block`.
+/// La configuration du fournisseur est lue au premier usage puis gardée, ses clés
+/// pendant `OidcConfig::jwks_ttl`.
+pub struct OidcClient {
+    config: OidcConfig,
+    agent: ureq::Agent,
+    metadata: Mutex<Option<ProviderMetadata>>,
+    keys: Mutex<Option<KeyCache>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .redirects(0)
+            .build();
+        Self { config, agent, metadata: Mutex::new(None), keys: Mutex::new(None) }
+    }
+
+    /// Erreur si `OIDC_ISSUER`, `OIDC_CLIENT_ID` ou `OIDC_REDIRECT_URI` manque
+    pub fn from_env() -> Result<Self, String> {
+        OidcConfig::from_env().map(Self::new)
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, OidcError> {
+        let response = self.agent.get(url).call()
+            .map_err(|e| OidcError::Provider(format!("GET {} failed: {}", url, e)))?;
+        response.into_json()
+            .map_err(|e| OidcError::Provider(format!("GET {} returned invalid JSON: {}", url, e)))
+    }
+
+    /// Configuration publiée par le fournisseur, dont l'émetteur doit être celui configuré
+    pub fn metadata(&self) -> Result<ProviderMetadata, OidcError> {
+        let mut cached = self.metadata.lock().unwrap();
+        if let Some(metadata) = cached.as_ref() {
+            return Ok(metadata.clone());
+        }
+        let metadata: ProviderMetadata = self.get_json(&format!("{}/.well-known/openid-configuration", self.config.issuer))?;
+        if metadata.issuer.trim_end_matches('/') != self.config.issuer {
+            return Err(OidcError::Provider(format!("discovery document is for issuer '{}'", metadata.issuer)));
+        }
+        *cached = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Clés du JWKS, rechargées à expiration ou, sur demande, si la copie a plus de 30 secondes
+    fn keys(&self, refresh: bool) -> Result<Vec<Jwk>, OidcError> {
+        let mut cached = self.keys.lock().unwrap();
+        if let Some(cache) = cached.as_ref() {
+            let age = cache.fetched_at.elapsed();
+            if age < Duration::from_secs(self.config.jwks_ttl) && !(refresh && age >= JWKS_MIN_REFRESH) {
+                return Ok(cache.keys.clone());
+            }
+        }
+        let set: JwkSet = self.get_json(&self.metadata()?.jwks_uri)?;
+        *cached = Some(KeyCache { keys: set.keys.clone(), fetched_at: Instant::now() });
+        Ok(set.keys)
+    }
+
+    /// Adresse de la page de connexion du fournisseur
+    pub fn authorization_url(&self, flow: &LoginFlow) -> Result<String, OidcError> {
+        let metadata = self.metadata()?;
+        let mut url = Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| OidcError::Provider(format!("invalid authorization endpoint: {}", e)))?;
+        let scopes = if self.config.scopes.split_whitespace().any(|scope| scope == "openid") {
+            self.config.scopes.clone()
+        } else {
+            format!("openid {}", self.config.scopes)
+        };
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("scope", &scopes)
+            .append_pair("state", &flow.state)
+            .append_pair("nonce", &flow.nonce)
+            .append_pair("code_challenge", &flow.challenge())
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.to_string())
+    }
+
+    /// Échange le code contre les jetons et valide l'ID token
+    pub fn exchange_code(&self, code: &str, flow: &LoginFlow) -> Result<Identity, OidcError> {
+        let metadata = self.metadata()?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", flow.verifier.as_str()),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let response = match self.agent.post(&metadata.token_endpoint).send_form(&form) {
+            Ok(response) => response,
+            // Code expiré, déjà utilisé ou vérificateur PKCE refusé
+            Err(ureq::Error::Status(400 | 401, response)) => {
+                let error: Option<TokenError> = response.into_json().ok();
+                return Err(OidcError::Denied(error
+                    .map(|e| e.error_description.unwrap_or(e.error))
+                    .unwrap_or_else(|| "authorization code rejected".to_string())));
+            },
+            Err(e) => return Err(OidcError::Provider(format!("token request failed: {}", e))),
+        };
+        let tokens: TokenResponse = response.into_json()
+            .map_err(|e| OidcError::Provider(format!("token endpoint returned invalid JSON: {}", e)))?;
+        let id_token = tokens.id_token.ok_or_else(|| OidcError::Provider("token response has no id_token".to_string()))?;
+        self.validate_id_token(&id_token, &flow.nonce, OffsetDateTime::now_utc().unix_timestamp())
+    }
+
+    /// Signature, émetteur, audience, dates et nonce de l'ID token
+    pub fn validate_id_token(&self, token: &str, nonce: &str, now: i64) -> Result<Identity, OidcError> {
+        let jws = Jws::decode(token).map_err(invalid_token)?;
+        let candidates = |keys: Vec<Jwk>| -> Vec<Jwk> {
+            keys.into_iter().filter(|key| jws.header.kid.is_none() || key.kid == jws.header.kid).collect()
+        };
+        let mut keys = candidates(self.keys(false)?);
+        if keys.is_empty() {
+            // Rotation des clés chez le fournisseur
+            keys = candidates(self.keys(true)?);
+        }
+        if !keys.iter().any(|key| jws.verify(key)) {
+            return Err(invalid_token("signature does not match the provider keys"));
+        }
+
+        let claims = &jws.claims;
+        let text = |name: &str| claims.get(name).and_then(Value::as_str).filter(|v| !v.is_empty()).map(str::to_string);
+        let number = |name: &str| claims.get(name).and_then(Value::as_i64);
+
+        let issuer = self.metadata()?.issuer;
+        if text("iss").as_deref() != Some(issuer.as_str()) {
+            return Err(invalid_token("unexpected issuer"));
+        }
+        let audiences: Vec<&str> = match claims.get("aud") {
+            Some(Value::String(audience)) => vec![audience.as_str()],
+            Some(Value::Array(audiences)) => audiences.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let client_id = self.config.client_id.as_str();
+        if !audiences.contains(&client_id) {
+            return Err(invalid_token("token is intended for another client"));
+        }
+        let azp = text("azp");
+        if azp.as_deref().is_some_and(|azp| azp != client_id) || (audiences.len() > 1 && azp.is_none()) {
+            return Err(invalid_token("token is intended for another client"));
+        }
+        match number("exp") {
+            Some(exp) if exp + CLOCK_LEEWAY > now => {},
+            _ => return Err(invalid_token("token has expired")),
+        }
+        match number("iat") {
+            Some(iat) if iat - CLOCK_LEEWAY <= now => {},
+            _ => return Err(invalid_token("token issue time is invalid")),
+        }
+        if text("nonce").as_deref() != Some(nonce) {
+            return Err(invalid_token("nonce does not match the login request"));
+        }
+
+        let subject = text("sub").ok_or_else(|| invalid_token("missing subject"))?;
+        let email = text("email").filter(|_| claims.get("email_verified").and_then(Value::as_bool) == Some(true));
+        let login = text(&self.config.login_claim)
+            .or_else(|| email.clone())
+            .unwrap_or_else(|| subject.clone());
+        Ok(Identity { issuer, subject, login, email, given_name: text("given_name"), family_name: text("family_name") })
+    }
+}
+
+/// Un compte non lié porte déjà le login de l'identité : il faut s'y connecter, puis lier l'identité par `GET /api/auth/oidc/link`
+#[derive(Debug)]
+pub struct LinkRequired(pub String);
+
+impl std::fmt::Display for LinkRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Account '{}' already exists: log in to it, then link this identity", self.0)
+    }
+}
+
+impl std::error::Error for LinkRequired {}
+
+/// Compte déjà lié au couple émetteur/sujet de l'identité
+async fn linked_user(db: &DatabaseQuery, identity: &Identity) -> Result<Option<User>> {
+    match OidcIdentityRepository::new(db.clone()).find(&identity.issuer, &identity.subject).await? {
+        Some(linked) => UserRepository::new(db.clone()).get_user_by_id(linked.user_id).await,
+        None => Ok(None),
+    }
+}
+
+/// Utilisateur correspondant à l'identité : retrouvé par émetteur et `sub`, créé et lié à la
+/// première connexion ; l'e-mail suit celui du fournisseur
+///
+/// Un compte existant du même login, même sans mot de passe ni second facteur, n'est jamais
+/// repris : erreur `LinkRequired`, le login seul ne prouvant pas qu'il s'agit du même titulaire.
+/// Le titulaire lie l'identité depuis sa session (`link`).
+pub async fn sign_in(db: &DatabaseQuery, identity: &Identity) -> Result<User> {
+    let users = UserRepository::new(db.clone());
+    let existing = match linked_user(db, identity).await? {
+        Some(user) => user,
+        None => {
+            if users.get_user(&identity.login).await?.is_some() {
+                return Err(LinkRequired(identity.login.clone()).into());
+            }
+            let user = User {
+                id: uuid::Uuid::new_v4(),
+                login: Some(identity.login.clone()),
+                birthday: None,
+                firstname: identity.given_name.clone(),
+                lastname: identity.family_name.clone(),
+                sexe: None,
+                age: None,
+                info: None,
+                email: identity.email.clone(),
+                files_info: None,
+                created_at: OffsetDateTime::now_utc(),
+            };
+            let user = match users.insert_unique(&user).await {
+                Ok(user) => user,
+                // Première connexion simultanée depuis deux onglets : l'autre a créé et lié le compte
+                Err(e) if e.is::<LoginTaken>() => return match linked_user(db, identity).await? {
+                    Some(user) => Ok(user),
+                    None => Err(LinkRequired(identity.login.clone()).into()),
+                },
+                Err(e) => return Err(e),
+            };
+            if OidcIdentityRepository::new(db.clone()).link(&identity.issuer, &identity.subject, user.id).await? {
+                user
+            } else {
+                // Liée entre-temps par une connexion simultanée
+                linked_user(db, identity).await?
+                    .ok_or_else(|| anyhow::Error::msg(format!("Identity '{}' vanished during sign-in", identity.subject)))?
+            }
+        },
+    };
+
+    match &identity.email {
+        Some(email) if existing.email.as_ref() != Some(email) => {
+            users.set_email(existing.id, email).await?;
+            Ok(User { email: Some(email.clone()), ..existing })
+        },
+        _ => Ok(existing),
+    }
+}
+
+/// Lie l'identité au compte connecté ; `false` si elle est déjà liée à un autre compte
+pub async fn link(db: &DatabaseQuery, identity: &Identity, user: &User) -> Result<bool> {
+    let identities = OidcIdentityRepository::new(db.clone());
+    if identities.link(&identity.issuer, &identity.subject, user.id).await? {
+        return Ok(true);
+    }
+    Ok(identities.find(&identity.issuer, &identity.subject).await?.is_some_and(|linked| linked.user_id == user.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, database, user};
+    use mock_provider::MockProvider;
+
+    fn client(provider: &MockProvider, client_id: &str) -> OidcClient {
+        OidcClient::new(OidcConfig {
+            issuer: provider.issuer(),
+            client_id: client_id.to_string(),
+            client_secret: None,
+            redirect_uri: "https://app.example/api/auth/oidc/callback".to_string(),
+            scopes: "openid email profile".to_string(),
+            login_claim: "preferred_username".to_string(),
+            jwks_ttl: 3600,
+        })
+    }
+
+    /// Suit la redirection du fournisseur et retourne le code reçu par le callback
+    fn authorize(url: &str, flow: &LoginFlow) -> String {
+        let response = ureq::AgentBuilder::new().redirects(0).build().get(url).call().unwrap();
+        assert_eq!(response.status(), 302);
+        let location = Url::parse(response.header("location").unwrap()).unwrap();
+        assert!(location.as_str().starts_with("https://app.example/api/auth/oidc/callback?"));
+        let param = |name: &str| location.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+        assert_eq!(param("state").as_deref(), Some(flow.state.as_str()));
+        param("code").unwrap()
+    }
+
+    #[test]
+    fn signs_in_through_the_mock_provider() {
+        let provider = MockProvider::start("client-1");
+        let oidc = client(&provider, "client-1");
+
+        let flow = LoginFlow::generate();
+        let code = authorize(&oidc.authorization_url(&flow).unwrap(), &flow);
+        // Le code n'est échangé qu'avec le bon vérificateur PKCE, et une seule fois
+        let stolen = LoginFlow { verifier: LoginFlow::generate().verifier, ..flow.clone() };
+        assert!(matches!(oidc.exchange_code(&code, &stolen), Err(OidcError::Denied(_))));
+        let identity = oidc.exchange_code(&code, &flow).unwrap();
+        assert_eq!(identity.login, "alice");
+        assert_eq!(identity.email.as_deref(), Some("alice@example.com"));
+        assert!(matches!(oidc.exchange_code(&code, &flow), Err(OidcError::Denied(_))));
+
+        block_on(async {
+            let db = database().await;
+            let user = sign_in(&db, &identity).await.unwrap();
+            assert_eq!((user.login.as_deref(), user.firstname.as_deref()), (Some("alice"), Some("Alice")));
+            // Le compte suit le sujet, même quand le login et l'e-mail changent chez le fournisseur
+            let moved = Identity { login: "alice.liddell".to_string(), email: Some("alice@corp.example".to_string()), ..identity.clone() };
+            let again = sign_in(&db, &moved).await.unwrap();
+            assert_eq!((again.id, again.email.as_deref()), (user.id, Some("alice@corp.example")));
+            assert_eq!(UserRepository::new(db).get_all().await.unwrap().len(), 1);
+        });
+    }
+
+    fn identity(subject: &str, login: &str) -> Identity {
+        Identity {
+            issuer: "https://idp.example".to_string(),
+            subject: subject.to_string(),
+            login: login.to_string(),
+            email: None,
+            given_name: None,
+            family_name: None,
+        }
+    }
+
+    #[test]
+    fn protected_accounts_are_only_linked_explicitly() {
+        block_on(async {
+            let db = database().await;
+            let users = UserRepository::new(db.clone());
+            let owner = sign_in(&db, &identity("owner", "bob")).await.unwrap();
+            users.set_password_hash(owner.id, "$argon2id$hash").await.unwrap();
+
+            // Un autre sujet du même login ne reprend pas le compte
+            let intruder = identity("intruder", "bob");
+            let error = sign_in(&db, &intruder).await.unwrap_err();
+            assert!(error.is::<LinkRequired>());
+
+            // Liaison faite depuis la session du compte : le sujet y mène ensuite
+            let own = identity("bob-at-idp", "bob");
+            assert!(link(&db, &own, &owner).await.unwrap());
+            assert!(link(&db, &own, &owner).await.unwrap());
+            assert_eq!(sign_in(&db, &own).await.unwrap().id, owner.id);
+            let other = sign_in(&db, &identity("carol", "carol")).await.unwrap();
+            assert!(!link(&db, &own, &other).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn existing_accounts_without_credentials_are_not_taken_over() {
+        block_on(async {
+            let db = database().await;
+            let users = UserRepository::new(db.clone());
+            // Compte créé hors OIDC (import, administration), sans mot de passe ni second facteur
+            let dave = user(&db, "dave").await;
+            users.set_email(dave.id, "dave@example.com").await.unwrap();
+
+            let intruder = Identity { email: Some("intruder@example.com".to_string()), ..identity("intruder", "dave") };
+            let error = sign_in(&db, &intruder).await.unwrap_err();
+            assert!(error.is::<LinkRequired>());
+            // Ni liaison, ni e-mail repris
+            assert!(OidcIdentityRepository::new(db.clone()).find(&intruder.issuer, &intruder.subject).await.unwrap().is_none());
+            let unchanged = users.get_user_by_id(dave.id).await.unwrap().unwrap();
+            assert_eq!(unchanged.email.as_deref(), Some("dave@example.com"));
+            assert_eq!(users.get_all().await.unwrap().len(), 1);
+
+            // Seule la liaison depuis la session du compte y donne accès
+            assert!(link(&db, &intruder, &dave).await.unwrap());
+            assert_eq!(sign_in(&db, &intruder).await.unwrap().id, dave.id);
+        });
+    }
+
+    #[test]
+    fn ignores_unverified_emails() {
+        let provider = MockProvider::start("client-1");
+        let oidc = client(&provider, "client-1");
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let token = |verified: Value| provider.id_token_with("client-1", "n-1", now, serde_json::json!({
+            "preferred_username": null,
+            "email": "admin@example.com",
+            "email_verified": verified,
+        }));
+
+        let verified = oidc.validate_id_token(&token(Value::Bool(true)), "n-1", now).unwrap();
+        assert_eq!((verified.login.as_str(), verified.email.as_deref()), ("admin@example.com", Some("admin@example.com")));
+        for claim in [Value::Bool(false), Value::Null, Value::String("true".to_string())] {
+            let identity = oidc.validate_id_token(&token(claim), "n-1", now).unwrap();
+            assert_eq!((identity.login.as_str(), identity.email), ("user-1", None));
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_or_tampered_tokens() {
+        let provider = MockProvider::start("client-1");
+        let oidc = client(&provider, "client-1");
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let token = provider.id_token("client-1", "n-1", now);
+
+        assert_eq!(oidc.validate_id_token(&token, "n-1", now).unwrap().subject, "user-1");
+        assert_eq!(oidc.validate_id_token(&token, "n-2", now), Err(invalid_token("nonce does not match the login request")));
+        assert_eq!(oidc.validate_id_token(&token, "n-1", now + 3600), Err(invalid_token("token has expired")));
+        let other_client = client(&provider, "client-2");
+        assert_eq!(other_client.validate_id_token(&token, "n-1", now), Err(invalid_token("token is intended for another client")));
+
+        // Contenu modifié ou algorithme `none` : la signature ne correspond plus
+        let parts: Vec<&str> = token.split('.').collect();
+        let mut claims: Value = serde_json::from_slice(&jwt::decode_base64(parts[1]).unwrap()).unwrap();
+        claims["preferred_username"] = "admin".into();
+        let forged = jwt::encode_base64(claims.to_string().as_bytes());
+        let tampered = format!("{}.{}.{}", parts[0], forged, parts[2]);
+        assert_eq!(oidc.validate_id_token(&tampered, "n-1", now), Err(invalid_token("signature does not match the provider keys")));
+        let unsigned = format!("{}.{}.", jwt::encode_base64(br#"{"alg":"none"}"#), forged);
+        assert_eq!(oidc.validate_id_token(&unsigned, "n-1", now), Err(invalid_token("signature does not match the provider keys")));
+    }
+}
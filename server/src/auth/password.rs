@@ -0,0 +1,55 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::sync::OnceLock;
+
+/// Longueur minimale d'un mot de passe, en caractères
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// Empreinte argon2id au format PHC (`$argon2id$v=19$...`), sel aléatoire inclus
+pub fn hash_password(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("Password must be at least {} characters long", MIN_PASSWORD_LEN));
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Password hashing failed: {}", e))
+}
+
+/// Empreinte sans mot de passe connu, vérifiée à la place d'un compte absent
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| hash_password(&uuid::Uuid::new_v4().to_string()).expect("dummy password is long enough"))
+}
+
+/// Vérifie le mot de passe contre l'empreinte enregistrée
+///
+/// Sans empreinte (login inconnu ou compte sans mot de passe), le calcul est fait quand même
+/// sur une empreinte factice : le temps de réponse ne révèle pas quels logins existent.
+pub fn verify_password(password: &str, hash: Option<&str>) -> bool {
+    let known = hash.is_some();
+    let Ok(parsed) = PasswordHash::new(hash.unwrap_or_else(|| dummy_hash())) else {
+        return false;
+    };
+    Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok() && known
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_and_verifies_passwords() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_password("correct horse").unwrap());
+
+        assert!(verify_password("correct horse", Some(&hash)));
+        assert!(!verify_password("wrong horse", Some(&hash)));
+        assert!(!verify_password("correct horse", None));
+        assert!(!verify_password("correct horse", Some("not a hash")));
+        assert!(hash_password("short").is_err());
+    }
+}
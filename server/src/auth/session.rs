@@ -0,0 +1,107 @@
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::HttpRequest;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use anyhow::Result;
+use core::config::SessionConfig;
+use core::repositories::{Session, SessionRepository, User, UserRepository};
+use core::_database::DatabaseQuery;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Ouvre, retrouve et ferme les sessions enregistrées en base
+///
+/// Le cookie porte un jeton aléatoire de 256 bits ; la base n'en garde que l'empreinte.
+pub struct SessionManager {
+    config: SessionConfig,
+}
+
+impl SessionManager {
+    pub fn new(config: SessionConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(SessionConfig::from_env())
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// Identifiant de la session en base : SHA-256 du jeton
+    pub fn session_id(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// Jeton présent dans le cookie de la requête
+    pub fn token(&self, req: &HttpRequest) -> Option<String> {
+        req.cookie(&self.config.cookie_name)
+            .map(|cookie| cookie.value().to_string())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Ouvre une session pour l'utilisateur et retourne le jeton à placer dans le cookie
+    pub async fn open(&self, db: &DatabaseQuery, user_id: Uuid) -> Result<(String, Session)> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+
+        let now = OffsetDateTime::now_utc();
+        let session = SessionRepository::new(db.clone()).create(&Session {
+            id: Self::session_id(&token),
+            user_id,
+            created_at: now,
+            expires_at: now + time::Duration::seconds(self.config.ttl as i64),
+        }).await?;
+        Ok((token, session))
+    }
+
+    /// Session valide et son utilisateur, `None` si le jeton est inconnu ou expiré
+    pub async fn resolve(&self, db: &DatabaseQuery, token: &str) -> Result<Option<(Session, User)>> {
+        let Some(session) = SessionRepository::new(db.clone()).find_active(&Self::session_id(token)).await? else {
+            return Ok(None);
+        };
+        let user = UserRepository::new(db.clone()).get_user_by_id(session.user_id).await?;
+        Ok(user.map(|user| (session, user)))
+    }
+
+    pub async fn close(&self, db: &DatabaseQuery, token: &str) -> Result<bool> {
+        SessionRepository::new(db.clone()).delete(&Self::session_id(token)).await
+    }
+
+    /// Cookie `HttpOnly`/`SameSite=Lax` de la session, valable aussi longtemps qu'elle
+    pub fn cookie(&self, token: &str) -> Cookie<'static> {
+        Cookie::build(self.config.cookie_name.clone(), token.to_string())
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(self.config.secure)
+            .max_age(Duration::seconds(self.config.ttl as i64))
+            .finish()
+    }
+
+    /// Cookie vide et déjà expiré, qui efface celui du navigateur
+    pub fn removal_cookie(&self) -> Cookie<'static> {
+        let mut cookie = self.cookie("");
+        cookie.make_removal();
+        cookie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_hardened_cookies() {
+        let sessions = SessionManager::new(SessionConfig { cookie_name: "sid".to_string(), ttl: 60, secure: true });
+        let cookie = sessions.cookie("abc").to_string();
+        for attribute in ["sid=abc", "HttpOnly", "SameSite=Lax", "Secure", "Path=/", "Max-Age=60"] {
+            assert!(cookie.contains(attribute), "{} missing from {}", attribute, cookie);
+        }
+        assert!(sessions.removal_cookie().to_string().contains("Max-Age=0"));
+        assert_eq!(SessionManager::session_id("abc").len(), 64);
+        assert_ne!(SessionManager::session_id("abc"), "abc");
+    }
+}
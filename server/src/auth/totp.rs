@@ -0,0 +1,174 @@
+use anyhow::Result;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use core::repositories::TotpRepository;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Chiffres d'un code, et durée de validité d'un code en secondes (RFC 6238)
+pub const DIGITS: u32 = 6;
+pub const PERIOD: i64 = 30;
+/// Pas de temps acceptés de part et d'autre du pas courant, pour les horloges décalées
+const WINDOW: i64 = 1;
+/// Codes de secours remis à l'enrôlement
+pub const RECOVERY_CODES: usize = 10;
+/// Codes refusés avant qu'une connexion en attente soit abandonnée
+pub const MAX_ATTEMPTS: i32 = 5;
+/// Délai pour saisir le code après le mot de passe, en secondes
+pub const CHALLENGE_TTL: i64 = 300;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 RFC 4648 sans remplissage, le format attendu par les applications d'authentification
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for chunk in bytes.chunks(5) {
+        let mut buffer = [0u8; 5];
+        buffer[..chunk.len()].copy_from_slice(chunk);
+        let value = buffer.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        for i in 0..(chunk.len() * 8).div_ceil(5) {
+            out.push(BASE32[((value >> (35 - i * 5)) & 31) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Décode du base32, sans tenir compte de la casse, des espaces ni du remplissage
+pub fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32.iter().position(|&b| b as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// Secret aléatoire de 160 bits, en base32
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// Code HOTP (RFC 4226) du pas de temps `step`
+pub fn code(secret: &[u8], step: i64) -> String {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    format!("{:0width$}", value % 10u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+/// Pas de temps du code s'il est valide à l'instant `now` (secondes Unix), fenêtre comprise
+pub fn verify(secret: &str, candidate: &str, now: i64) -> Option<i64> {
+    let secret = base32_decode(secret)?;
+    let candidate = candidate.trim();
+    if candidate.len() != DIGITS as usize || !candidate.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let current = now.div_euclid(PERIOD);
+    (current - WINDOW..=current + WINDOW).find(|&step| {
+        // Comparaison en temps constant
+        bool::from(code(&secret, step).as_bytes().ct_eq(candidate.as_bytes()))
+    })
+}
+
+/// URI `otpauth://` lue par les applications d'authentification (Google Authenticator, Aegis…)
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let label = format!("{}:{}", issuer, account);
+    format!(
+        "otpauth://totp/{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        utf8_percent_encode(&label, NON_ALPHANUMERIC), secret, utf8_percent_encode(issuer, NON_ALPHANUMERIC), DIGITS, PERIOD,
+    )
+}
+
+/// QR code SVG de l'URI, à scanner depuis l'application ; sans déclaration XML, le SVG
+/// étant inséré tel quel dans la page
+pub fn qr_svg(uri: &str) -> String {
+    let Ok(code) = QrCode::with_error_correction_level(uri, EcLevel::M) else {
+        return String::new();
+    };
+    let svg = code.render::<svg::Color>().module_dimensions(4, 4).build();
+    match svg.find("<svg") {
+        Some(start) => svg[start..].to_string(),
+        None => svg,
+    }
+}
+
+/// Codes de secours de 10 caractères base32 (50 bits), en deux groupes : `abcde-fghij`
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES).map(|_| {
+        let mut bytes = [0u8; 7];
+        OsRng.fill_bytes(&mut bytes);
+        let code = base32_encode(&bytes)[..10].to_ascii_lowercase();
+        format!("{}-{}", &code[..5], &code[5..])
+    }).collect()
+}
+
+/// Empreinte stockée d'un code de secours, tirets, espaces et casse ignorés
+pub fn recovery_code_hash(code: &str) -> String {
+    let normalized: String = code.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+/// Vérifie un code TOTP ou, à défaut, un code de secours, et le consomme ;
+/// `false` si le compte n'a pas de second facteur actif ou si le code est refusé
+pub async fn check_second_factor(totp: &TotpRepository, user_id: Uuid, candidate: &str, now: i64) -> Result<bool> {
+    let Some(secret) = totp.find(user_id).await?.filter(|secret| secret.is_enabled()) else {
+        return Ok(false);
+    };
+    match verify(&secret.secret, candidate, now) {
+        Some(step) => totp.use_step(user_id, step).await,
+        None => totp.use_recovery_code(user_id, &recovery_code_hash(candidate)).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rfc_vectors() {
+        // RFC 6238, annexe B (SHA-1) : 94287082 et 07081804 sur 8 chiffres
+        let secret = b"12345678901234567890";
+        assert_eq!(code(secret, 59 / PERIOD), "287082");
+        assert_eq!(code(secret, 1111111109 / PERIOD), "081804");
+
+        let encoded = base32_encode(secret);
+        assert_eq!(encoded, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(base32_decode(&encoded.to_lowercase()).unwrap(), secret);
+        assert_eq!(base32_encode(b"fo"), "MZXQ");
+        assert_eq!(base32_decode("MZXQ====").unwrap(), b"fo");
+        assert!(base32_decode("not base32!").is_none());
+
+        // Un pas de décalage est toléré, pas deux
+        assert_eq!(verify(&encoded, "287082", 59), Some(1));
+        assert_eq!(verify(&encoded, "287082", 59 + PERIOD), Some(1));
+        assert_eq!(verify(&encoded, "287082", 59 + 2 * PERIOD), None);
+        assert_eq!(verify(&encoded, "28708", 59), None);
+
+        let uri = otpauth_uri("Crate", "alice", &encoded);
+        assert_eq!(uri, "otpauth://totp/Crate%3Aalice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Crate&algorithm=SHA1&digits=6&period=30");
+        assert!(qr_svg(&uri).starts_with("<svg"));
+
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES);
+        assert_eq!(codes[0].len(), 11);
+        assert_eq!(recovery_code_hash(&codes[0].to_uppercase().replace('-', " ")), recovery_code_hash(&codes[0]));
+    }
+}
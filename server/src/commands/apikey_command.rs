@@ -0,0 +1,170 @@
+use core::_database::{connect_db, DatabaseQuery};
+use core::repositories::{ApiKey, ApiKeyRepository, User, UserRepository};
+use core::Table;
+use serde_json::json;
+use std::collections::HashMap;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::auth::api_key;
+
+fn usage() {
+    println!("  Usage: apikey create <login> <nom> [--scopes read,write] [--days n]");
+    println!("         apikey list");
+    println!("         apikey revoke <id|préfixe>");
+}
+
+/// Options de `apikey create <login> <nom> [--scopes read,write] [--days n]`
+struct CreateOptions {
+    login: String,
+    name: String,
+    scopes: String,
+    days: Option<i64>,
+}
+
+fn parse_create(args: &[String]) -> Result<CreateOptions, String> {
+    let mut positional = Vec::new();
+    let mut scopes = "read,write".to_string();
+    let mut days = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scopes" => scopes = args.next().ok_or("--scopes attend une liste (read,write)")?.clone(),
+            "--days" => {
+                let value = args.next().ok_or("--days attend un nombre de jours")?;
+                days = Some(value.parse().ok().filter(|&d: &i64| d > 0).ok_or(format!("Nombre de jours invalide: {}", value))?);
+            },
+            other => positional.push(other.to_string()),
+        }
+    }
+    let [login, name]: [String; 2] = positional.try_into().map_err(|_| "Login et nom de la clé attendus".to_string())?;
+    Ok(CreateOptions { login, name, scopes: api_key::parse_scopes(&scopes)?, days })
+}
+
+fn format_date(date: Option<OffsetDateTime>) -> String {
+    date.and_then(|d| d.format(&Rfc3339).ok()).unwrap_or_else(|| "-".to_string())
+}
+
+/// `apikey create` : la clé n'est affichée qu'ici, seule son empreinte est conservée
+async fn create(db: DatabaseQuery, options: CreateOptions) -> anyhow::Result<()> {
+    let generated = api_key::generate();
+    let now = OffsetDateTime::now_utc();
+    let (login, name, scopes, days) = (options.login.clone(), options.name, options.scopes, options.days);
+    let (prefix, hash) = (generated.prefix.clone(), generated.hash.clone());
+
+    // Compte et clé créés ensemble : pas de compte orphelin si l'insertion de la clé échoue
+    let key = db.transaction(|tx| async move {
+        tx.lock(&login).await?;
+        let users = UserRepository::new(tx.as_query());
+        let user = match users.get_user(&login).await? {
+            Some(user) => user,
+            None => {
+                let fields = HashMap::from([("login".to_string(), login.clone())]);
+                println!("👤 Création du compte '{}'", login);
+                users.create_user(&User::from_form_fields(&fields, &Vec::new())).await?
+            }
+        };
+        ApiKeyRepository::new(tx.as_query()).create(&ApiKey {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            name,
+            prefix,
+            key_hash: hash,
+            scopes,
+            expires_at: days.map(|days| now + time::Duration::days(days)),
+            last_used_at: None,
+            revoked_at: None,
+            created_at: now,
+        }).await
+    }).await?;
+
+    println!("🔑 === CLÉ D'API ===");
+    println!("  Nom : {} ({})", key.name, key.id);
+    println!("  Compte : {}", options.login);
+    println!("  Portées : {}", key.scopes);
+    println!("  Expire le : {}", format_date(key.expires_at));
+    println!("  {}", generated.key);
+    println!("⚠️  Conservez cette clé : elle ne sera plus affichée.");
+    println!("   Utilisation : Authorization: Bearer {}…", key.prefix);
+    println!("===================");
+    Ok(())
+}
+
+/// `apikey list` : clés et état, sans leur valeur
+async fn list(db: DatabaseQuery) -> anyhow::Result<()> {
+    let keys = ApiKeyRepository::new(db.clone()).list().await?;
+    if keys.is_empty() {
+        println!("Aucune clé d'API");
+        return Ok(());
+    }
+    let users = UserRepository::new(db);
+    let now = OffsetDateTime::now_utc();
+    let mut rows = Vec::new();
+    for key in keys {
+        let login = users.get_user_by_id(key.user_id).await?.and_then(|user| user.login).unwrap_or_default();
+        let state = if key.revoked_at.is_some() { "révoquée" } else if key.is_expired(now) { "expirée" } else { "active" };
+        rows.push(json!({
+            "id": key.id.to_string(),
+            "préfixe": key.prefix,
+            "nom": key.name,
+            "compte": login,
+            "portées": key.scopes,
+            "expire": format_date(key.expires_at),
+            "dernière utilisation": format_date(key.last_used_at),
+            "état": state,
+        }));
+    }
+    println!("{}", Table::create(&json!(rows), "").to_text());
+    Ok(())
+}
+
+async fn revoke(db: DatabaseQuery, id_or_prefix: &str) -> anyhow::Result<()> {
+    match ApiKeyRepository::new(db).revoke(id_or_prefix).await? {
+        0 => println!("❌ Aucune clé active ne correspond à '{}'", id_or_prefix),
+        count => println!("✅ {} clé(s) révoquée(s)", count),
+    }
+    Ok(())
+}
+
+enum Action {
+    Create(CreateOptions),
+    List,
+    Revoke(String),
+}
+
+/// Gère `apikey <create|list|revoke>`
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    let action = match (args.first().map(String::as_str), args.get(1)) {
+        (Some("create"), _) => match parse_create(&args[1..]) {
+            Ok(options) => Action::Create(options),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                usage();
+                return Ok(());
+            }
+        },
+        (Some("list"), _) => Action::List,
+        (Some("revoke"), Some(key)) => Action::Revoke(key.clone()),
+        _ => {
+            usage();
+            return Ok(());
+        }
+    };
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let result = match action {
+        Action::Create(options) => create(db, options).await,
+        Action::List => list(db).await,
+        Action::Revoke(key) => revoke(db, &key).await,
+    };
+    result.map_err(|e| {
+        eprintln!("❌ Erreur de base de données: {}", e);
+        std::io::Error::other(e.to_string())
+    })
+}
@@ -0,0 +1,102 @@
+use core::_database::connect_db;
+use core::repositories::FileRepository;
+use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
+use crate::server_lib::create_web_server_config;
+use crate::signed_links::LinkSigner;
+
+/// Options de `link <id-fichier> [--ttl secondes] [--single-use] [--base-url url]`
+struct LinkOptions {
+    file_id: Uuid,
+    ttl: Option<u64>,
+    single_use: bool,
+    base_url: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<LinkOptions, String> {
+    let mut options = LinkOptions { file_id: Uuid::nil(), ttl: None, single_use: false, base_url: None };
+    let mut file_id = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ttl" => {
+                let value = args.next().ok_or("--ttl attend une durée en secondes")?;
+                options.ttl = Some(value.parse().map_err(|_| format!("Durée invalide: {}", value))?);
+            },
+            "--single-use" => options.single_use = true,
+            "--base-url" => options.base_url = Some(args.next().ok_or("--base-url attend une adresse")?.clone()),
+            other if file_id.is_none() => {
+                file_id = Some(Uuid::parse_str(other).map_err(|_| format!("Identifiant de fichier invalide: {}", other))?);
+            },
+            other => return Err(format!("Argument inattendu: {}", other)),
+        }
+    }
+    options.file_id = file_id.ok_or("Identifiant de fichier manquant")?;
+    Ok(options)
+}
+
+/// Adresse publique du serveur : `--base-url`, sinon `PUBLIC_URL`, sinon l'hôte et le port configurés
+fn base_url(options: &LinkOptions) -> String {
+    options.base_url.clone()
+        .or_else(|| std::env::var("PUBLIC_URL").ok())
+        .unwrap_or_else(|| {
+            let config = create_web_server_config();
+            format!("{}://{}:{}", if config.ssl_enabled { "https" } else { "http" }, config.host, config.port)
+        })
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Gère `link` : crée un lien de téléchargement signé pour le support
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            println!("  Usage: link <id-fichier> [--ttl secondes] [--single-use] [--base-url url]");
+            return Ok(());
+        }
+    };
+    let signer = match LinkSigner::from_env() {
+        Ok(signer) => signer,
+        Err(e) => {
+            eprintln!("❌ Liens signés indisponibles: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e));
+        }
+    };
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let file = match FileRepository::new(db).get_file(options.file_id).await {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            eprintln!("❌ Fichier introuvable: {}", options.file_id);
+            return Ok(());
+        },
+        Err(e) => {
+            eprintln!("❌ Erreur de base de données: {}", e);
+            return Err(std::io::Error::other(e.to_string()));
+        }
+    };
+
+    match signer.mint(file.id, options.ttl, options.single_use) {
+        Ok(link) => {
+            println!("🔗 === LIEN DE TÉLÉCHARGEMENT ===");
+            println!("  Fichier : {} ({} octets)", file.original_name, file.size);
+            println!("  Expire le : {}", link.expires_at().format(&Rfc3339).unwrap_or_default());
+            println!("  Usage unique : {}", if link.nonce.is_some() { "oui" } else { "non" });
+            println!("  {}{}", base_url(&options), signer.path(&link));
+            println!("================================");
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            Ok(())
+        }
+    }
+}
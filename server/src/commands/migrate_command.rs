@@ -0,0 +1,73 @@
+use core::_database::connect_db;
+use core::repositories::migrations::{Migrator, MigrationState};
+
+/// Gère `migrate up|down [n]|status|redo`
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    let action = args.first().map(|s| s.as_str()).unwrap_or("status");
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let migrator = Migrator::new(db);
+
+    let result = match action {
+        "up" => migrator.up().await.map(|applied| {
+            if applied.is_empty() {
+                println!("✅ Aucune migration en attente");
+            } else {
+                println!("✅ Migration(s) appliquée(s): {}", applied.join(", "));
+            }
+        }),
+        "down" => {
+            let steps = match args.get(1).map(|n| n.parse::<usize>()) {
+                None => 1,
+                Some(Ok(n)) => n,
+                Some(Err(_)) => {
+                    eprintln!("❌ Nombre d'étapes invalide: {}", args[1]);
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid step count: {}", args[1])));
+                }
+            };
+            migrator.down(steps).await.map(|reverted| {
+                if reverted.is_empty() {
+                    println!("✅ Aucune migration à annuler");
+                } else {
+                    println!("✅ Migration(s) annulée(s): {}", reverted.join(", "));
+                }
+            })
+        },
+        "redo" => migrator.redo().await.map(|redone| {
+            println!("✅ Migration(s) rejouée(s): {}", redone.join(", "));
+        }),
+        "status" => migrator.status().await.map(|statuses| {
+            println!("🗄️  === STATUT DES MIGRATIONS ===");
+            for status in statuses {
+                let icon = match status.state {
+                    MigrationState::Applied => "✅",
+                    MigrationState::Pending => "⏳",
+                    MigrationState::Dirty => "⚠️ ",
+                    MigrationState::ChecksumMismatch => "❌",
+                };
+                let applied_at = status.applied_at
+                    .map(|date| date.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                println!("  {} {:>4}  {:<20} {:<18} {}", icon, status.version, status.name, format!("{:?}", status.state), applied_at);
+            }
+            println!("================================");
+        }),
+        _ => {
+            eprintln!("❌ Action de migration inconnue: {}", action);
+            println!("  Usage: migrate up | down [n] | status | redo");
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("unknown migrate action: {}", action)));
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("❌ Erreur de migration: {}", e);
+        return Err(std::io::Error::other(e.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod migrate_command;
+pub mod storage_command;
+pub mod link_command;
+pub mod user_command;
+pub mod apikey_command;
+pub mod role_command;
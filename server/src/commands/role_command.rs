@@ -0,0 +1,82 @@
+use core::_database::{connect_db, DatabaseQuery};
+use core::repositories::{RoleRepository, UserRepository};
+use core::Table;
+use serde_json::json;
+
+fn usage() {
+    println!("  Usage: role grant <login> <rôle>");
+    println!("         role revoke <login> <rôle>");
+    println!("         role list");
+}
+
+enum Action {
+    Grant(String, String),
+    Revoke(String, String),
+    List,
+}
+
+/// `role grant|revoke <login> <rôle>` : le compte et le rôle doivent exister
+async fn change(db: DatabaseQuery, login: &str, role: &str, grant: bool) -> anyhow::Result<()> {
+    let Some(user) = UserRepository::new(db.clone()).get_user(login).await? else {
+        println!("❌ Utilisateur introuvable: {}", login);
+        return Ok(());
+    };
+    let roles = RoleRepository::new(db);
+    if !roles.role_exists(role).await? {
+        println!("❌ Rôle inconnu: {} (voir `role list`)", role);
+        return Ok(());
+    }
+
+    let changed = if grant { roles.grant(user.id, role).await? } else { roles.revoke(user.id, role).await? };
+    match (grant, changed) {
+        (true, true) => println!("✅ Rôle '{}' attribué à '{}'", role, login),
+        (true, false) => println!("ℹ️  '{}' a déjà le rôle '{}'", login, role),
+        (false, true) => println!("✅ Rôle '{}' retiré à '{}'", role, login),
+        (false, false) => println!("ℹ️  '{}' n'a pas le rôle '{}'", login, role),
+    }
+    Ok(())
+}
+
+async fn list(db: DatabaseQuery) -> anyhow::Result<()> {
+    let rows: Vec<_> = RoleRepository::new(db).list_roles().await?
+        .into_iter()
+        .map(|role| json!({
+            "rôle": role.name,
+            "description": role.description.unwrap_or_default(),
+            "permissions": role.permissions.join(", "),
+            "membres": role.members.join(", "),
+        }))
+        .collect();
+    println!("{}", Table::create(&json!(rows), "").to_text());
+    Ok(())
+}
+
+/// Gère `role <grant|revoke|list>`
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    let action = match (args.first().map(String::as_str), args.get(1), args.get(2)) {
+        (Some("grant"), Some(login), Some(role)) => Action::Grant(login.clone(), role.clone()),
+        (Some("revoke"), Some(login), Some(role)) => Action::Revoke(login.clone(), role.clone()),
+        (Some("list"), _, _) => Action::List,
+        _ => {
+            usage();
+            return Ok(());
+        }
+    };
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let result = match action {
+        Action::Grant(login, role) => change(db, &login, &role, true).await,
+        Action::Revoke(login, role) => change(db, &login, &role, false).await,
+        Action::List => list(db).await,
+    };
+    result.map_err(|e| {
+        eprintln!("❌ Erreur de base de données: {}", e);
+        std::io::Error::other(e.to_string())
+    })
+}
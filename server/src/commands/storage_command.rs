@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+use core::_database::{connect_db, DatabaseQuery};
+use core::config::{StorageConfig, UploadConfig};
+use core::repositories::{blob_lock_key, BlobRepository, LinkNonceRepository};
+use crate::storage::ContentStore;
+
+/// Âge minimal d'un fichier sans référence avant suppression : laisse aux uploads en cours
+/// le temps d'enregistrer leur contenu
+const GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Bilan d'un passage du ramasse-miettes
+#[derive(Default)]
+struct GcReport {
+    recounted: u64,
+    removed_blobs: usize,
+    orphan_files: usize,
+    orphan_thumbnails: usize,
+    temp_files: usize,
+    expired_link_nonces: u64,
+    freed_bytes: u64,
+}
+
+/// Gère `storage gc`
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    let action = args.first().map(|s| s.as_str()).unwrap_or("");
+    if action != "gc" {
+        eprintln!("❌ Action de stockage inconnue: {}", action);
+        println!("  Usage: storage gc");
+        return Ok(());
+    }
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let store = match StorageConfig::from_env()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+        .and_then(|storage| ContentStore::from_config(&storage, &UploadConfig::from_env()))
+    {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ Configuration du stockage invalide: {}", e);
+            return Err(e);
+        }
+    };
+
+    match collect_garbage(&db, &store).await {
+        Ok(report) => {
+            println!("🧹 === NETTOYAGE DU STOCKAGE ({}) ===", store.backend());
+            println!("  Compteurs de références corrigés : {}", report.recounted);
+            println!("  Contenus sans référence supprimés : {}", report.removed_blobs);
+            println!("  Fichiers inconnus de la base supprimés : {}", report.orphan_files);
+            println!("  Miniatures sans contenu supprimées : {}", report.orphan_thumbnails);
+            println!("  Uploads temporaires abandonnés supprimés : {}", report.temp_files);
+            println!("  Jetons de liens expirés supprimés : {}", report.expired_link_nonces);
+            println!("  Espace libéré : {} octets", report.freed_bytes);
+            println!("================================");
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("❌ Erreur de nettoyage: {}", e);
+            Err(std::io::Error::other(e.to_string()))
+        }
+    }
+}
+
+/// Réaligne les compteurs sur la table `files`, puis supprime les contenus sans référence,
+/// les fichiers du stockage absents de la base, leurs miniatures, les uploads temporaires abandonnés
+/// et les jetons des liens signés expirés
+async fn collect_garbage(db: &DatabaseQuery, store: &ContentStore) -> anyhow::Result<GcReport> {
+    let blobs = BlobRepository::new(db.clone());
+    let mut report = GcReport { recounted: blobs.recount().await?, ..GcReport::default() };
+
+    for blob in blobs.unreferenced().await? {
+        let key = ContentStore::blob_key(&blob.sha256);
+        // Sous verrou : un upload du même contenu attend la fin de la suppression
+        let removed = db.transaction(|tx| async move {
+            tx.lock(&blob_lock_key(&blob.sha256)).await?;
+            let removed = BlobRepository::new(tx.as_query()).delete_unreferenced(&blob.sha256).await?;
+            if removed {
+                store.erase(&key).await?;
+            }
+            Ok(removed)
+        }).await?;
+        if removed {
+            report.removed_blobs += 1;
+            report.freed_bytes += blob.size as u64;
+        }
+    }
+
+    let known: HashSet<String> = blobs.digests().await?.into_iter().collect();
+    for (sha256, info) in store.blobs().await? {
+        if !known.contains(&sha256) && older_than_grace(info.modified) {
+            store.erase(&info.key).await?;
+            report.orphan_files += 1;
+            report.freed_bytes += info.size;
+        }
+    }
+
+    for (sha256, info) in store.thumbnails().await? {
+        if !known.contains(&sha256) && older_than_grace(info.modified) {
+            store.erase(&info.key).await?;
+            report.orphan_thumbnails += 1;
+            report.freed_bytes += info.size;
+        }
+    }
+
+    for (path, metadata) in store.temp_files()? {
+        if older_than_grace(metadata.modified().ok()) {
+            store.remove_temp(&path).await?;
+            report.temp_files += 1;
+            report.freed_bytes += metadata.len();
+        }
+    }
+
+    report.expired_link_nonces = LinkNonceRepository::new(db.clone()).purge_expired().await?;
+    Ok(report)
+}
+
+fn older_than_grace(modified: Option<SystemTime>) -> bool {
+    modified
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= GRACE_PERIOD)
+}
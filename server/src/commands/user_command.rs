@@ -0,0 +1,112 @@
+use core::_database::connect_db;
+use core::repositories::{SessionRepository, TotpRepository, User, UserRepository};
+use std::collections::HashMap;
+use std::io::BufRead;
+use crate::auth::password;
+
+fn usage() {
+    println!("  Usage: user set-password <login>   (mot de passe lu sur l'entrée standard)");
+    println!("         user reset-totp <login>     (retire le second facteur, appareil et codes de secours perdus)");
+}
+
+/// Première ligne de l'entrée standard, sans le retour à la ligne
+fn read_password() -> std::io::Result<String> {
+    println!("🔑 Mot de passe (une ligne sur l'entrée standard) :");
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// `user set-password <login>` : définit le mot de passe, en créant le compte s'il n'existe pas ;
+/// les sessions ouvertes du compte sont fermées
+async fn set_password(login: &str) -> std::io::Result<()> {
+    let hash = match password::hash_password(&read_password()?) {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let users = UserRepository::new(db.clone());
+    let result = async {
+        let user = match users.get_user(login).await? {
+            Some(user) => user,
+            None => {
+                let fields = HashMap::from([("login".to_string(), login.to_string())]);
+                println!("👤 Création du compte '{}'", login);
+                users.insert_unique(&User::from_form_fields(&fields, &Vec::new())).await?
+            }
+        };
+        users.set_password_hash(user.id, &hash).await?;
+        SessionRepository::new(db.clone()).delete_for_user(user.id).await
+    }.await;
+
+    match result {
+        Ok(closed) => {
+            println!("✅ Mot de passe de '{}' enregistré ({} session(s) fermée(s))", login, closed);
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("❌ Erreur de base de données: {}", e);
+            Err(std::io::Error::other(e.to_string()))
+        }
+    }
+}
+
+/// `user reset-totp <login>` : retire le second facteur du compte et ferme ses sessions
+async fn reset_totp(login: &str) -> std::io::Result<()> {
+    let db = match connect_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("❌ Connexion à la base impossible: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()));
+        }
+    };
+    let result = async {
+        let Some(user) = UserRepository::new(db.clone()).get_user(login).await? else {
+            return Ok(None);
+        };
+        let removed = TotpRepository::new(db.clone()).disable(user.id).await?;
+        SessionRepository::new(db.clone()).delete_for_user(user.id).await?;
+        Ok::<_, anyhow::Error>(Some(removed))
+    }.await;
+
+    match result {
+        Ok(Some(true)) => {
+            println!("✅ Second facteur de '{}' retiré, le compte se connecte de nouveau par mot de passe seul", login);
+            Ok(())
+        },
+        Ok(Some(false)) => {
+            println!("ℹ️ '{}' n'avait pas de second facteur", login);
+            Ok(())
+        },
+        Ok(None) => {
+            eprintln!("❌ Utilisateur '{}' introuvable", login);
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("❌ Erreur de base de données: {}", e);
+            Err(std::io::Error::other(e.to_string()))
+        }
+    }
+}
+
+/// Gère `user <sous-commande>`
+pub async fn run(args: &[String]) -> std::io::Result<()> {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("set-password"), Some(login)) if !login.is_empty() => set_password(login).await,
+        (Some("reset-totp"), Some(login)) if !login.is_empty() => reset_totp(login).await,
+        _ => {
+            usage();
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use core::repositories::{LoginChallenge, RoleRepository, Session, SessionRepository, TotpRepository, User, UserRepository};
+use core::{HttpSendResponse, _database::DatabaseQuery};
+use serde::Deserialize;
+use actix_web::http::header;
+use serde_json::{json, Value};
+use time::format_description::well_known::Rfc3339;
+use crate::auth::oidc::{self, Identity, LinkRequired, LoginFlow, OidcError};
+use crate::csrf;
+use crate::auth::{authenticate, password, totp, AuthenticatedUser, OidcClient, SessionManager};
+use actix_web::ResponseError;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use time::OffsetDateTime;
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub login: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct SecondFactorRequest {
+    /// Jeton remis par `POST /api/auth/login`
+    pub challenge: String,
+    /// Code TOTP à 6 chiffres ou code de secours
+    pub code: String,
+}
+
+pub(crate) fn respond(status: StatusCode, message: impl Into<String>, data: Option<Value>) -> HttpResponse {
+    HttpResponse::build(status).json(HttpSendResponse {
+        status: status.as_u16(),
+        message: Some(message.into()),
+        data,
+    })
+}
+
+/// Utilisateur et expiration de sa session (`null` pour une clé d'API)
+fn session_data(user: &User, session: Option<&Session>) -> Option<Value> {
+    Some(json!({
+        "user": user,
+        "expires_at": session.and_then(|s| s.expires_at.format(&Rfc3339).ok()),
+    }))
+}
+
+pub(crate) fn database_error(e: anyhow::Error) -> HttpResponse {
+    println!("Database error: {}", e);
+    respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e), None)
+}
+
+/// Ouvre une nouvelle session pour l'utilisateur : une session existante n'est jamais reprise
+pub(crate) async fn open_session(req: &HttpRequest, db: &DatabaseQuery, sessions: &SessionManager, user: &User) -> anyhow::Result<(String, Session)> {
+    if let Some(token) = sessions.token(req) {
+        if let Err(e) = sessions.close(db, &token).await {
+            println!("Database error while closing session: {}", e);
+        }
+    }
+    if let Err(e) = SessionRepository::new(db.clone()).purge_expired().await {
+        println!("Database error while purging sessions: {}", e);
+    }
+    sessions.open(db, user.id).await
+}
+
+/// POST /api/auth/login : `{ "login", "password" }`, ouvre une session et pose son cookie
+///
+/// Login inconnu et mot de passe faux donnent la même réponse (401). Un compte à second
+/// facteur n'a pas encore de session : voir `start_challenge`.
+pub async fn login(
+    req: HttpRequest,
+    db_pool: web::Data<DatabaseQuery>,
+    sessions: web::Data<SessionManager>,
+    body: web::Json<LoginRequest>,
+) -> HttpResponse {
+    let credentials = match UserRepository::new(db_pool.get_ref().clone()).get_credentials(&body.login).await {
+        Ok(credentials) => credentials,
+        Err(e) => return database_error(e),
+    };
+    let hash = credentials.as_ref().and_then(|(_, hash)| hash.as_deref());
+    let user = match credentials.as_ref() {
+        Some((user, _)) if password::verify_password(&body.password, hash) => user,
+        _ => return respond(StatusCode::UNAUTHORIZED, "Invalid login or password", None),
+    };
+
+    match TotpRepository::new(db_pool.get_ref().clone()).is_enabled(user.id).await {
+        Ok(true) => return start_challenge(&db_pool, user).await,
+        Ok(false) => {},
+        Err(e) => return database_error(e),
+    }
+    match open_session(&req, &db_pool, &sessions, user).await {
+        Ok((token, session)) => {
+            let mut response = respond(StatusCode::OK, format!("Logged in as {}", body.login), session_data(user, Some(&session)));
+            let _ = response.add_cookie(&sessions.cookie(&token));
+            csrf::rotate(&req, &mut response, Some(&token));
+            response
+        },
+        Err(e) => database_error(e),
+    }
+}
+
+/// Connexion en attente du second facteur : jeton à présenter avec le code à
+/// `POST /api/auth/login/totp`, et son expiration
+async fn create_challenge(db: &DatabaseQuery, user: &User) -> anyhow::Result<(String, OffsetDateTime)> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+
+    let challenges = TotpRepository::new(db.clone());
+    if let Err(e) = challenges.purge_expired_challenges().await {
+        println!("Database error while purging login challenges: {}", e);
+    }
+    let expires_at = OffsetDateTime::now_utc() + time::Duration::seconds(totp::CHALLENGE_TTL);
+    let challenge = LoginChallenge { id: SessionManager::session_id(&token), user_id: user.id, attempts: 0, expires_at };
+    challenges.create_challenge(&challenge).await?;
+    Ok((token, expires_at))
+}
+
+/// Mot de passe vérifié d'un compte à second facteur : 202 et jeton de la connexion en attente
+async fn start_challenge(db: &DatabaseQuery, user: &User) -> HttpResponse {
+    match create_challenge(db, user).await {
+        Ok((token, expires_at)) => respond(StatusCode::ACCEPTED, "Two-factor code required", Some(json!({
+            "two_factor": true,
+            "challenge": token,
+            "expires_at": expires_at.format(&Rfc3339).ok(),
+        }))),
+        Err(e) => database_error(e),
+    }
+}
+
+/// POST /api/auth/login/totp : `{ "challenge", "code" }`, termine une connexion à second facteur
+///
+/// Le code est un code TOTP ou un code de secours, chacun accepté une seule fois ; après
+/// `MAX_ATTEMPTS` codes refusés, il faut reprendre la connexion depuis le mot de passe.
+pub async fn login_totp(
+    req: HttpRequest,
+    db_pool: web::Data<DatabaseQuery>,
+    sessions: web::Data<SessionManager>,
+    body: web::Json<SecondFactorRequest>,
+) -> HttpResponse {
+    let totp_repository = TotpRepository::new(db_pool.get_ref().clone());
+    let id = SessionManager::session_id(body.challenge.trim());
+    // L'essai est compté avant la vérification du code : pas plus de `MAX_ATTEMPTS` essais,
+    // même envoyés simultanément
+    let challenge = match totp_repository.take_attempt(&id, totp::MAX_ATTEMPTS).await {
+        Ok(Some(challenge)) => challenge,
+        Ok(None) => return respond(StatusCode::UNAUTHORIZED, "Login challenge has expired, log in again", None),
+        Err(e) => return database_error(e),
+    };
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    match totp::check_second_factor(&totp_repository, challenge.user_id, &body.code, now).await {
+        Ok(true) => {},
+        Ok(false) => {
+            let left = totp::MAX_ATTEMPTS - challenge.attempts;
+            return respond(StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid two-factor code ({} attempt(s) left)", left), None);
+        },
+        Err(e) => return database_error(e),
+    }
+
+    // Une seule requête ouvre la session de cette connexion en attente
+    match totp_repository.delete_challenge(&id).await {
+        Ok(true) => {},
+        Ok(false) => return respond(StatusCode::UNAUTHORIZED, "Login challenge has expired, log in again", None),
+        Err(e) => return database_error(e),
+    }
+    let user = match UserRepository::new(db_pool.get_ref().clone()).get_user_by_id(challenge.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return respond(StatusCode::UNAUTHORIZED, "Login challenge has expired, log in again", None),
+        Err(e) => return database_error(e),
+    };
+    match open_session(&req, &db_pool, &sessions, &user).await {
+        Ok((token, session)) => {
+            let login = user.login.clone().unwrap_or_default();
+            let mut response = respond(StatusCode::OK, format!("Logged in as {}", login), session_data(&user, Some(&session)));
+            let _ = response.add_cookie(&sessions.cookie(&token));
+            csrf::rotate(&req, &mut response, Some(&token));
+            response
+        },
+        Err(e) => database_error(e),
+    }
+}
+
+/// POST /api/auth/logout : ferme la session du cookie et l'efface, même si elle n'existe plus ;
+/// le jeton CSRF lié à la session est remplacé
+pub async fn logout(
+    req: HttpRequest,
+    db_pool: web::Data<DatabaseQuery>,
+    sessions: web::Data<SessionManager>,
+) -> HttpResponse {
+    if let Some(token) = sessions.token(&req) {
+        if let Err(e) = sessions.close(&db_pool, &token).await {
+            return database_error(e);
+        }
+    }
+    let mut response = respond(StatusCode::OK, "Logged out", None);
+    let _ = response.add_cookie(&sessions.removal_cookie());
+    csrf::rotate(&req, &mut response, None);
+    response
+}
+
+/// GET /api/auth/me : utilisateur de la session ou de la clé d'API, ses rôles et son second facteur
+pub async fn me(auth: AuthenticatedUser, db_pool: web::Data<DatabaseQuery>) -> HttpResponse {
+    let roles = match RoleRepository::new(db_pool.get_ref().clone()).roles_of(auth.user.id).await {
+        Ok(roles) => roles,
+        Err(e) => return database_error(e),
+    };
+    let two_factor = match TotpRepository::new(db_pool.get_ref().clone()).is_enabled(auth.user.id).await {
+        Ok(enabled) => enabled,
+        Err(e) => return database_error(e),
+    };
+    let login = auth.user.login.clone().unwrap_or_default();
+    let mut data = session_data(&auth.user, auth.session.as_ref());
+    if let Some(Value::Object(data)) = data.as_mut() {
+        data.insert("roles".to_string(), json!(roles));
+        data.insert("two_factor".to_string(), json!(two_factor));
+        if let Some(api_key) = &auth.api_key {
+            data.insert("api_key".to_string(), json!({ "name": api_key.name, "prefix": api_key.prefix }));
+        }
+    }
+    respond(StatusCode::OK, format!("Logged in as {}", login), data)
+}
+
+#[derive(Deserialize)]
+pub struct OidcCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+fn oidc_disabled() -> HttpResponse {
+    respond(StatusCode::SERVICE_UNAVAILABLE, "OIDC login is disabled (OIDC_ISSUER is not set)", None)
+}
+
+fn see_other(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther().insert_header((header::LOCATION, location)).finish()
+}
+
+/// Redirection vers le fournisseur ; `state`, `nonce` et vérificateur PKCE attendent le retour dans un cookie
+async fn redirect_to_provider(oidc: Option<web::Data<OidcClient>>, sessions: &SessionManager, flow: LoginFlow) -> HttpResponse {
+    let Some(oidc) = oidc else {
+        return oidc_disabled();
+    };
+    let request = flow.clone();
+    let url = match web::block(move || oidc.authorization_url(&request)).await {
+        Ok(Ok(url)) => url,
+        Ok(Err(e)) => return respond(e.status(), e.to_string(), None),
+        Err(e) => return respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string(), None),
+    };
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, url))
+        .cookie(flow.cookie(sessions.config().secure))
+        .finish()
+}
+
+/// GET /api/auth/oidc/login : redirige vers le fournisseur OIDC
+pub async fn oidc_login(oidc: Option<web::Data<OidcClient>>, sessions: web::Data<SessionManager>) -> HttpResponse {
+    redirect_to_provider(oidc, &sessions, LoginFlow::generate()).await
+}
+
+/// GET /api/auth/oidc/link : redirige vers le fournisseur OIDC ; au retour, l'identité est liée
+/// au compte de la session, qui pourra ensuite se connecter par OIDC
+pub async fn oidc_link(_auth: AuthenticatedUser, oidc: Option<web::Data<OidcClient>>, sessions: web::Data<SessionManager>) -> HttpResponse {
+    redirect_to_provider(oidc, &sessions, LoginFlow::generate_link()).await
+}
+
+/// GET /api/auth/oidc/callback : retour du fournisseur ; vérifie `state`, échange le code,
+/// valide l'ID token puis connecte l'utilisateur correspondant, ou lui lie l'identité
+pub async fn oidc_callback(
+    req: HttpRequest,
+    db_pool: web::Data<DatabaseQuery>,
+    sessions: web::Data<SessionManager>,
+    oidc: Option<web::Data<OidcClient>>,
+    query: web::Query<OidcCallback>,
+) -> HttpResponse {
+    let Some(oidc) = oidc else {
+        return oidc_disabled();
+    };
+    let query = query.into_inner();
+    let flow = LoginFlow::from_request(&req);
+    let linking = flow.as_ref().is_some_and(|flow| flow.link);
+    let identity = match (flow, query.state) {
+        (Some(flow), Some(state)) if flow.state == state => match (query.error, query.code) {
+            (Some(error), _) => Err(OidcError::Denied(query.error_description.unwrap_or(error))),
+            (None, Some(code)) => web::block(move || oidc.exchange_code(&code, &flow)).await
+                .unwrap_or_else(|e| Err(OidcError::Provider(e.to_string()))),
+            (None, None) => Err(OidcError::Denied("no authorization code returned".to_string())),
+        },
+        _ => Err(OidcError::InvalidState),
+    };
+
+    // Le cookie de connexion ne sert qu'une fois, que la connexion aboutisse ou non
+    let secure = sessions.config().secure;
+    let mut response = match identity {
+        Ok(identity) if linking => link_identity(&req, &db_pool, &identity).await,
+        Ok(identity) => oidc_sign_in(&req, &db_pool, &sessions, &identity).await,
+        Err(e) => respond(e.status(), e.to_string(), None),
+    };
+    let _ = response.add_cookie(&LoginFlow::removal_cookie(secure));
+    response
+}
+
+/// Connexion par l'identité OIDC ; un compte à second facteur repart vers l'application avec
+/// le jeton de la connexion en attente (`/#totp_challenge=...`), sans session
+async fn oidc_sign_in(req: &HttpRequest, db: &DatabaseQuery, sessions: &SessionManager, identity: &Identity) -> HttpResponse {
+    let user = match oidc::sign_in(db, identity).await {
+        Ok(user) => user,
+        Err(e) if e.is::<LinkRequired>() => return respond(StatusCode::CONFLICT, e.to_string(), None),
+        Err(e) => return database_error(e),
+    };
+    match TotpRepository::new(db.clone()).is_enabled(user.id).await {
+        Ok(true) => return match create_challenge(db, &user).await {
+            Ok((token, _)) => see_other(&format!("/#totp_challenge={}", token)),
+            Err(e) => database_error(e),
+        },
+        Ok(false) => {},
+        Err(e) => return database_error(e),
+    }
+    match open_session(req, db, sessions, &user).await {
+        Ok((token, _)) => {
+            println!("OIDC login: {} ({})", identity.login, identity.subject);
+            let mut response = see_other("/");
+            let _ = response.add_cookie(&sessions.cookie(&token));
+            csrf::rotate(req, &mut response, Some(&token));
+            response
+        },
+        Err(e) => database_error(e),
+    }
+}
+
+/// Liaison de l'identité OIDC au compte de la session, commencée par `GET /api/auth/oidc/link`
+async fn link_identity(req: &HttpRequest, db: &DatabaseQuery, identity: &Identity) -> HttpResponse {
+    let auth = match authenticate(req).await {
+        Ok(auth) => auth,
+        Err(e) => return e.error_response(),
+    };
+    match oidc::link(db, identity, &auth.user).await {
+        Ok(true) => {
+            println!("OIDC identity {} linked to {}", identity.subject, auth.user.login.clone().unwrap_or_default());
+            see_other("/")
+        },
+        Ok(false) => respond(StatusCode::CONFLICT, "This identity is already linked to another account", None),
+        Err(e) => database_error(e),
+    }
+}
@@ -0,0 +1,441 @@
+use actix_files::HttpRange;
+use actix_web::body::SizedStream;
+use actix_web::http::header::{self, Charset, ContentDisposition, DispositionParam, DispositionType, EntityTag, ExtendedValue};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, ResponseError};
+use core::config::UploadConfig;
+use core::repositories::{FileRepository, StoredFile};
+use core::{HttpSendResponse, _database::DatabaseQuery};
+use crate::auth::guard::permissions;
+use crate::auth::{authenticate, AuthError, Permission};
+use crate::images::{self, ImageFormat};
+use crate::signed_links::{LinkSigner, SignedLink};
+use crate::storage::{collect_stream, ContentStore};
+use serde::Deserialize;
+use uuid::Uuid;
+
+fn respond(status: StatusCode, message: impl Into<String>) -> HttpResponse {
+    HttpResponse::build(status).json(HttpSendResponse {
+        status: status.as_u16(),
+        message: Some(message.into()),
+        data: None,
+    })
+}
+
+/// `attachment` avec le nom d'origine : `filename` en ASCII pour les anciens clients,
+/// `filename*` en UTF-8 pour les autres
+fn content_disposition(original_name: &str) -> ContentDisposition {
+    let ascii: String = original_name.chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() && c != '"' { c } else { '_' })
+        .collect();
+
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![
+            DispositionParam::Filename(ascii),
+            DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: original_name.as_bytes().to_vec(),
+            }),
+        ],
+    }
+}
+
+/// Accès au fichier : par son lien signé (`/api/shared`), pour son propriétaire, ou avec `permission`
+async fn can_access(req: &HttpRequest, stored: &StoredFile, permission: Permission) -> Result<bool, AuthError> {
+    if req.extensions().get::<SignedLink>().is_some_and(|link| link.file_id == stored.id) {
+        return Ok(true);
+    }
+    let auth = authenticate(req).await?;
+    Ok(stored.user_id == Some(auth.user.id) || permissions(req, &auth).await?.allows(permission))
+}
+
+/// Vrai si l'un des ETags de l'en-tête correspond (`*` compris), comparaison faible
+fn etag_listed(req: &HttpRequest, name: header::HeaderName, etag: &EntityTag) -> bool {
+    req.headers().get_all(name)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|item| item == "*" || item.parse::<EntityTag>().is_ok_and(|tag| tag.weak_eq(etag)))
+}
+
+/// Plage demandée, `None` pour le fichier entier, `Err` si elle ne peut pas être servie (416)
+///
+/// `If-Range` ne conserve la plage que si l'ETag correspond encore, comparaison forte ;
+/// une date ne suffit pas, le contenu pouvant changer dans la même seconde.
+fn requested_range(req: &HttpRequest, etag: &EntityTag, length: u64) -> Result<Option<HttpRange>, ()> {
+    let Some(range) = req.headers().get(header::RANGE) else {
+        return Ok(None);
+    };
+    if let Some(if_range) = req.headers().get(header::IF_RANGE) {
+        let unchanged = if_range.to_str().ok()
+            .and_then(|value| value.parse::<EntityTag>().ok())
+            .is_some_and(|tag| tag.strong_eq(etag));
+        if !unchanged {
+            return Ok(None);
+        }
+    }
+
+    let range = range.to_str().map_err(|_| ())?;
+    // Plusieurs plages : seule la première est servie, comme pour les fichiers statiques
+    HttpRange::parse(range, length).map_err(|_| ())?
+        .first().copied().map(Some).ok_or(())
+}
+
+/// GET /api/files/{id} : télécharge un fichier uploadé
+///
+/// ETag fort basé sur le SHA-256 (`If-None-Match` → 304), `Range` sur une plage d'octets
+/// (206, ou 416 si elle sort du fichier) et `If-Range` pour reprendre un téléchargement.
+///
+/// Seul son propriétaire y a accès, ou un utilisateur avec `files:read` ; 404 pour les autres,
+/// comme pour un fichier inconnu. Par `/api/shared/{id}`, le lien signé suffit.
+pub async fn download(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db_pool: web::Data<DatabaseQuery>,
+    store: web::Data<ContentStore>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let not_found = || respond(StatusCode::NOT_FOUND, format!("File '{}' not found", key));
+
+    let Ok(id) = Uuid::parse_str(&key) else {
+        return not_found();
+    };
+    let stored: StoredFile = match FileRepository::new(db_pool.get_ref().clone()).get_file(id).await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+        }
+    };
+    match can_access(&req, &stored, Permission::FILES_READ).await {
+        Ok(true) => {},
+        Ok(false) => return not_found(),
+        Err(e) => return e.error_response(),
+    }
+
+    let etag = EntityTag::new_strong(stored.sha256.clone());
+    let mut response = HttpResponseBuilder::new(StatusCode::OK);
+    response
+        .insert_header(header::ETag(etag.clone()))
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"));
+
+    if etag_listed(&req, header::IF_NONE_MATCH, &etag) {
+        return response.status(StatusCode::NOT_MODIFIED).finish();
+    }
+
+    let gone = || respond(StatusCode::GONE, format!("Content of file '{}' is no longer available", key));
+    let length = match store.head(&stored.stored_path).await {
+        Ok(Some(info)) => info.size,
+        Ok(None) => {
+            println!("Content '{}' missing from storage", stored.stored_path);
+            return gone();
+        },
+        Err(e) => {
+            println!("Storage error: {}", e);
+            return respond(StatusCode::BAD_GATEWAY, format!("Storage error: {}", e));
+        }
+    };
+
+    response
+        .content_type(stored.mime.as_str())
+        .insert_header(content_disposition(&stored.original_name))
+        .insert_header((header::ACCEPT_RANGES, "bytes"));
+
+    let range = match requested_range(&req, &etag, length) {
+        Ok(range) => range,
+        Err(()) => return HttpResponse::RangeNotSatisfiable()
+            .insert_header((header::CONTENT_RANGE, format!("bytes */{}", length)))
+            .finish(),
+    };
+    let (offset, size) = range.map_or((0, length), |range| (range.start, range.length));
+    let body = match store.open(&stored.stored_path, range.map(|_| offset..offset + size)).await {
+        Ok(Some(body)) => SizedStream::new(size, body),
+        Ok(None) => return gone(),
+        Err(e) => {
+            println!("Storage error: {}", e);
+            return respond(StatusCode::BAD_GATEWAY, format!("Storage error: {}", e));
+        }
+    };
+
+    match range {
+        Some(range) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .insert_header((header::CONTENT_RANGE, format!("bytes {}-{}/{}", range.start, range.start + range.length - 1, length)))
+            // Pas de compression sur une réponse partielle : les octets doivent correspondre à la plage
+            .insert_header((header::CONTENT_ENCODING, "identity"))
+            .body(body),
+        None => response.body(body),
+    }
+}
+
+/// GET /api/files/{id}/thumbnail : miniature PNG carrée d'une image uploadée
+///
+/// La miniature est calculée à l'upload ; pour les fichiers plus anciens, elle l'est à la
+/// première demande puis conservée. 404 si le fichier n'est pas une image décodable ou dépasse les dimensions maximales.
+pub async fn thumbnail(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db_pool: web::Data<DatabaseQuery>,
+    upload_config: web::Data<UploadConfig>,
+    store: web::Data<ContentStore>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let not_found = || respond(StatusCode::NOT_FOUND, format!("No thumbnail for file '{}'", key));
+
+    let Ok(id) = Uuid::parse_str(&key) else {
+        return not_found();
+    };
+    let stored: StoredFile = match FileRepository::new(db_pool.get_ref().clone()).get_file(id).await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+        }
+    };
+    match can_access(&req, &stored, Permission::FILES_READ).await {
+        Ok(true) => {},
+        Ok(false) => return not_found(),
+        Err(e) => return e.error_response(),
+    }
+    let Some(format) = ImageFormat::from_mime(&stored.mime) else {
+        return not_found();
+    };
+
+    let size = upload_config.image.thumbnail_size;
+    let etag = EntityTag::new_strong(format!("{}-{}", stored.sha256, size));
+    let mut response = HttpResponseBuilder::new(StatusCode::OK);
+    response
+        .insert_header(header::ETag(etag.clone()))
+        .insert_header((header::CACHE_CONTROL, "private, max-age=86400"));
+    if etag_listed(&req, header::IF_NONE_MATCH, &etag) {
+        return response.status(StatusCode::NOT_MODIFIED).finish();
+    }
+
+    let png = match store.thumbnail(&stored.sha256, size).await {
+        Ok(Some(png)) => png,
+        Ok(None) => {
+            let content = match store.open(&stored.stored_path, None).await {
+                Ok(Some(body)) => collect_stream(body).await,
+                Ok(None) => return not_found(),
+                Err(e) => Err(e),
+            };
+            let content = match content {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("Storage error: {}", e);
+                    return respond(StatusCode::BAD_GATEWAY, format!("Storage error: {}", e));
+                }
+            };
+            let image_config = upload_config.image.clone();
+            let png = match tokio::task::spawn_blocking(move || images::thumbnail(&content, format, &image_config)).await {
+                Ok(Ok(png)) => png,
+                Ok(Err(e)) => {
+                    println!("No thumbnail for '{}': {}", stored.original_name, e);
+                    return not_found();
+                },
+                Err(e) => return respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            };
+            if let Err(e) = store.put_thumbnail(&stored.sha256, size, png.clone()).await {
+                println!("Storage error while saving thumbnail: {}", e);
+            }
+            png
+        },
+        Err(e) => {
+            println!("Storage error: {}", e);
+            return respond(StatusCode::BAD_GATEWAY, format!("Storage error: {}", e));
+        }
+    };
+
+    response.content_type("image/png").body(png)
+}
+
+/// Corps de `POST /api/files/{id}/links`, tous les champs sont facultatifs
+#[derive(Debug, Default, Deserialize)]
+pub struct LinkRequest {
+    /// Durée de validité en secondes, `SignedLinkConfig::default_ttl` sinon
+    pub ttl: Option<u64>,
+    /// Le lien ne sert qu'une fois
+    #[serde(default)]
+    pub single_use: bool,
+}
+
+/// POST /api/files/{id}/links : crée un lien de téléchargement signé et temporaire
+///
+/// Le lien, `/api/shared/{id}?expires=…&signature=…`, est vérifié par `require_signed_link`.
+/// 503 si `LINK_SECRET` n'est pas configuré.
+pub async fn create_link(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<LinkRequest>>,
+    db_pool: web::Data<DatabaseQuery>,
+    signer: Option<web::Data<LinkSigner>>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let Some(signer) = signer else {
+        return respond(StatusCode::SERVICE_UNAVAILABLE, "Signed links are disabled (LINK_SECRET is not set)");
+    };
+    let Ok(id) = Uuid::parse_str(&key) else {
+        return respond(StatusCode::NOT_FOUND, format!("File '{}' not found", key));
+    };
+    let not_found = || respond(StatusCode::NOT_FOUND, format!("File '{}' not found", key));
+    let stored = match FileRepository::new(db_pool.get_ref().clone()).get_file(id).await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+        }
+    };
+    match can_access(&req, &stored, Permission::FILES_READ).await {
+        Ok(true) => {},
+        Ok(false) => return not_found(),
+        Err(e) => return e.error_response(),
+    }
+
+    let request = body.map(web::Json::into_inner).unwrap_or_default();
+    let link = match signer.mint(id, request.ttl, request.single_use) {
+        Ok(link) => link,
+        Err(e) => return respond(e.status(), e.to_string()),
+    };
+    let info = req.connection_info();
+    let url = format!("{}://{}{}", info.scheme(), info.host(), signer.path(&link));
+    let expires_at = link.expires_at().format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+
+    HttpResponse::Created().json(HttpSendResponse {
+        status: StatusCode::CREATED.as_u16(),
+        message: Some(format!("Link valid until {}", expires_at)),
+        data: Some(serde_json::json!({
+            "url": url,
+            "expires_at": expires_at,
+            "single_use": link.nonce.is_some(),
+        })),
+    })
+}
+
+/// DELETE /api/files/{id} : supprime le fichier ; son contenu disparaît avec la dernière référence
+pub async fn delete(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db_pool: web::Data<DatabaseQuery>,
+    store: web::Data<ContentStore>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let not_found = || respond(StatusCode::NOT_FOUND, format!("File '{}' not found", key));
+    let Ok(id) = Uuid::parse_str(&key) else {
+        return not_found();
+    };
+    let files = FileRepository::new(db_pool.get_ref().clone());
+    let allowed = match files.get_file(id).await {
+        Ok(Some(stored)) => can_access(&req, &stored, Permission::FILES_DELETE).await,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+        }
+    };
+    match allowed {
+        Ok(true) => {},
+        Ok(false) => return not_found(),
+        Err(e) => return e.error_response(),
+    }
+
+    let erase = |file: StoredFile| async move {
+        store.erase(&file.stored_path).await.map_err(anyhow::Error::from)
+    };
+    match files.delete_file_with(id, erase).await {
+        Ok(Some(file)) => respond(StatusCode::OK, format!("File '{}' deleted", file.original_name)),
+        Ok(None) => not_found(),
+        Err(e) => {
+            println!("Database error: {}", e);
+            respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::SessionManager;
+    use crate::storage::{BlobStore, MemoryBlobStore};
+    use crate::test_support::{block_on, database, user};
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::web::Bytes;
+    use actix_web::App;
+    use core::config::SessionConfig;
+    use sha2::{Digest, Sha256};
+    use std::sync::Arc;
+
+    #[test]
+    fn serves_ranges_and_validators_to_the_owner_only() {
+        block_on(async {
+            let db = database().await;
+            let (alice, bob) = (user(&db, "alice").await, user(&db, "bob").await);
+            let sessions = web::Data::new(SessionManager::new(SessionConfig { cookie_name: "sid".to_string(), ttl: 60, secure: false }));
+            let (alice_token, _) = sessions.open(&db, alice.id).await.unwrap();
+            let (bob_token, _) = sessions.open(&db, bob.id).await.unwrap();
+
+            let sha256 = hex::encode(Sha256::digest(b"0123456789"));
+            let key = ContentStore::blob_key(&sha256);
+            let blobs = Arc::new(MemoryBlobStore::default());
+            blobs.put(&key, Box::pin(futures::stream::iter([Ok(Bytes::from_static(b"0123456789"))]))).await.unwrap();
+            let stored = StoredFile::new("rapport \"final\" été.txt", &key, 10, "text/plain", &sha256).with_owner(Some(alice.id));
+            FileRepository::new(db.clone()).create_file(&stored).await.unwrap();
+
+            let app = init_service(App::new()
+                .app_data(web::Data::new(db))
+                .app_data(sessions.clone())
+                .app_data(web::Data::new(ContentStore::new(blobs, std::env::temp_dir())))
+                .route("/api/files/{id}", web::get().to(download))).await;
+            let uri = format!("/api/files/{}", stored.id);
+            let get = |headers: &[(header::HeaderName, &str)]| {
+                let mut request = TestRequest::get().uri(&uri).cookie(sessions.cookie(&alice_token));
+                for (name, value) in headers {
+                    request = request.insert_header((name.clone(), value.to_string()));
+                }
+                request.to_request()
+            };
+            let etag = format!("\"{}\"", sha256);
+
+            let response = call_service(&app, get(&[])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let value = |response: &actix_web::dev::ServiceResponse, name| response.headers().get(name).map(|v| v.to_str().unwrap().to_string());
+            assert_eq!(value(&response, header::ETAG), Some(etag.clone()));
+            assert_eq!(value(&response, header::ACCEPT_RANGES).as_deref(), Some("bytes"));
+            // Guillemets et caractères non ASCII remplacés dans `filename`, conservés dans `filename*`
+            let disposition = value(&response, header::CONTENT_DISPOSITION).unwrap();
+            assert!(disposition.starts_with("attachment; filename=\"rapport _final_ _t_.txt\""), "{}", disposition);
+            assert!(disposition.contains("filename*=UTF-8''rapport%20%22final%22%20%C3%A9t%C3%A9.txt"), "{}", disposition);
+            assert_eq!(read_body(response).await, Bytes::from_static(b"0123456789"));
+
+            let response = call_service(&app, get(&[(header::RANGE, "bytes=2-5")])).await;
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(value(&response, header::CONTENT_RANGE).as_deref(), Some("bytes 2-5/10"));
+            assert_eq!(read_body(response).await, Bytes::from_static(b"2345"));
+
+            let response = call_service(&app, get(&[(header::RANGE, "bytes=20-30")])).await;
+            assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+            assert_eq!(value(&response, header::CONTENT_RANGE).as_deref(), Some("bytes */10"));
+
+            // `If-Range` périmé : le fichier entier ; à jour : la plage
+            let response = call_service(&app, get(&[(header::RANGE, "bytes=2-5"), (header::IF_RANGE, "\"stale\"")])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(read_body(response).await, Bytes::from_static(b"0123456789"));
+            let response = call_service(&app, get(&[(header::RANGE, "bytes=2-5"), (header::IF_RANGE, &etag)])).await;
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+            let response = call_service(&app, get(&[(header::IF_NONE_MATCH, &etag)])).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(value(&response, header::ETAG), Some(etag.clone()));
+
+            // Un autre utilisateur, sans `files:read` ni lien signé, ne voit pas le fichier
+            let other = TestRequest::get().uri(&uri).cookie(sessions.cookie(&bob_token)).to_request();
+            assert_eq!(call_service(&app, other).await.status(), StatusCode::NOT_FOUND);
+            let anonymous = TestRequest::get().uri(&uri).to_request();
+            assert_eq!(call_service(&app, anonymous).await.status(), StatusCode::UNAUTHORIZED);
+        });
+    }
+}
@@ -1,22 +1,46 @@
 // Import necessary dependencies
-use actix_web::{web, HttpResponse, Error};
+use actix_web::{web, http::StatusCode, HttpRequest, HttpResponse, Error};
 use actix_multipart::Multipart;
 use futures::StreamExt;
 use core::{HttpSendResponse, UserRepository, Table, _database::DatabaseQuery};
+use core::table::Formatter;
+use core::config::UploadConfig;
+use core::repositories::{FileRepository, LoginTaken, StoredFile, User};
+use core::repositories::_repository::{Entity, Page};
 use std::collections::HashMap;
-use crate::extract_form::{extract_form_field, save_uploaded_file};
+use crate::auth::AuthenticatedUser;
+use crate::extract_form::{extract_form_field, save_uploaded_file, UploadError};
 use crate::models::form_response::FormResponse;
-use serde_json::{to_value, value, Value};
+use crate::models::list_query::ListQuery;
+use crate::models::user_payload;
+use crate::negotiation::{self, ResponseFormat};
+use crate::storage::ContentStore;
+use std::path::PathBuf;
+use std::rc::Rc;
+use serde_json::{to_value, Value};
 
 /// Handles POST requests with multipart form data
 /// Processes both file uploads and form fields
+///
+/// Without an explicit format, answers with the historical envelope whose message is an HTML table;
+/// `Accept`/`?format=` select a bare HTML table, a JSON envelope with a plain message, or CSV.
+/// Uploaded files must respect `UploadConfig`: 413 when too large, 415 for a refused extension or content,
+/// 422 for an unreadable image or one beyond the pixel limits, or for fields that fail the `/api/users` validation.
+/// An existing login can only be updated from that account's session (401 without a session, 403 from another one).
 pub async fn post(
+    req: HttpRequest,
+    auth: Option<AuthenticatedUser>,
     mut payload: Multipart,
-    db_pool: web::Data<DatabaseQuery>
+    db_pool: web::Data<DatabaseQuery>,
+    upload_config: web::Data<UploadConfig>,
+    store: web::Data<ContentStore>,
 ) -> Result<HttpResponse, Error> {
+    let format = ResponseFormat::from_request(&req)?;
+
     // Store form fields and file information
     let mut form_data = HashMap::new();
     let mut files_info = Vec::new();
+    let mut uploads: Vec<(StoredFile, PathBuf, Option<Vec<u8>>)> = Vec::new();
 
     // Process each field in the multipart form
     while let Some(field) = payload.next().await {
@@ -29,9 +53,22 @@ pub async fn post(
         match (field_name, filename) {
             // Handle file upload fields (has both name and filename)
             (Some(_name), Some(filename)) => {
-                match save_uploaded_file(&mut field, &filename).await {
-                    Ok(save_info) => {
-                        files_info.push(format!("{} ({})", filename, save_info));
+                match save_uploaded_file(&mut field, &filename, &upload_config, &store).await {
+                    Ok(saved) => {
+                        let key = ContentStore::blob_key(&saved.sha256);
+                        let stored = StoredFile::new(&filename, &key, saved.size, saved.mime, &saved.sha256);
+                        files_info.push(format!("{} (/api/files/{}, {} bytes)", filename, stored.id, saved.size));
+                        uploads.push((stored, saved.temp_path, saved.thumbnail));
+                    },
+                    // Fichier refusé : la requête est interrompue sans lire la suite du flux
+                    Err(e @ (UploadError::TooLarge { .. } | UploadError::UnsupportedType { .. } | UploadError::InvalidImage { .. })) => {
+                        remove_uploads(&store, &uploads).await;
+                        let reason = match e.status() {
+                            StatusCode::PAYLOAD_TOO_LARGE => "File too large",
+                            StatusCode::UNPROCESSABLE_ENTITY => "Invalid image",
+                            _ => "Unsupported file type",
+                        };
+                        return Ok(error_response(format.unwrap_or(ResponseFormat::Json), e.status(), reason, &e.to_string()));
                     },
                     Err(e) => {
                         files_info.push(format!("Error saving {}: {}", filename, e));
@@ -48,19 +85,50 @@ pub async fn post(
         }
     }    // Créer le repository pour la base de données
     let repository = UserRepository::new(db_pool.get_ref().clone());
-    
-    // Tentative de sauvegarde en base de données
-    let database_result = match repository.upsert_user(&form_data, &files_info).await {
+
+    // Champs vérifiés comme pour /api/users, avant toute écriture
+    if let Err(errors) = user_payload::validate(&User::from_form_fields(&form_data, &files_info)) {
+        remove_uploads(&store, &uploads).await;
+        let details = errors.iter().map(|(field, error)| format!("{}: {}", field, error)).collect::<Vec<_>>().join(", ");
+        return Ok(error_response(format.unwrap_or(ResponseFormat::Json), StatusCode::UNPROCESSABLE_ENTITY, "Validation failed", &details));
+    }
+
+    // Un login existant n'est modifiable que depuis la session de son compte
+    let (database_result, owner) = match repository.upsert_user(&form_data, &files_info, auth.as_ref().map(|auth| auth.user.id)).await {
         Ok(form_data_saved) => {
             println!("User saved/updated successfully: {:?}", form_data_saved);
-            Some(format!("User {} successfully saved to database", 
-                form_data_saved.login.as_deref().unwrap_or("unknown")))
+            (Some(format!("User {} successfully saved to database", 
+                form_data_saved.login.as_deref().unwrap_or("unknown"))), Some(form_data_saved.id))
+        },
+        Err(e) if e.is::<LoginTaken>() => {
+            remove_uploads(&store, &uploads).await;
+            let status = if auth.is_some() { StatusCode::FORBIDDEN } else { StatusCode::UNAUTHORIZED };
+            let login = form_data.get("login").map(String::as_str).unwrap_or_default();
+            let details = format!("Login '{}' belongs to an account, log in as this user to update it", login);
+            return Ok(error_response(format.unwrap_or(ResponseFormat::Json), status, "Account is protected", &details));
         },
         Err(e) => {
             println!("Database error: {}", e);
-            Some(format!("Database error: {}", e))
+            (Some(format!("Database error: {}", e)), None)
         }
     };    
+
+    // Les fichiers sont enregistrés même sans utilisateur, pour rester téléchargeables ;
+    // un contenu déjà stocké n'est pas dupliqué
+    let file_repository = FileRepository::new(db_pool.get_ref().clone());
+    for (upload, temp_path, thumbnail) in uploads {
+        let upload = upload.with_owner(owner);
+        let commit = || async { store.commit(&temp_path, &upload.sha256).await.map(|_| ()).map_err(anyhow::Error::from) };
+        if let Err(e) = file_repository.create_file_with(&upload, commit).await {
+            println!("Database error while registering file: {}", e);
+            let _ = store.remove_temp(&temp_path).await;
+        } else if let Some(png) = thumbnail {
+            // Sans miniature enregistrée, elle sera recalculée à la première demande
+            if let Err(e) = store.put_thumbnail(&upload.sha256, upload_config.image.thumbnail_size, png).await {
+                println!("Storage error while saving thumbnail: {}", e);
+            }
+        }
+    }
     
     // Prepare response data combining form fields, files and database result
     let mut response_data = serde_json::Map::new();
@@ -76,9 +144,6 @@ pub async fn post(
     response_data.insert("Données".to_string(), to_value(form_data.clone()).unwrap());
     
     let data = Value::Object(response_data);
-    
-    // Generate HTML table using client's table generator
-    let message: String = Table::create(&data, "response-table").to_html();
 
     // Also create the original FormResponse for backward compatibility
     let form_response = FormResponse {
@@ -87,44 +152,133 @@ pub async fn post(
     };
     let form_response_data = to_value(form_response).unwrap();
 
-    // Return successful response with status, message and processed data
-    Ok(HttpResponse::Ok().json(HttpSendResponse {
-        status: 200,
-        message: Some(message),
-        data: Some(form_response_data),
-    }))
+    match format {
+        Some(ResponseFormat::Json) => Ok(HttpResponse::Ok().json(HttpSendResponse {
+            status: 200,
+            message: database_result,
+            data: Some(form_response_data),
+        })),
+        Some(ResponseFormat::Csv) => {
+            let mut fields: Vec<(&String, &String)> = form_data.iter().collect();
+            fields.sort();
+            let mut body = negotiation::csv_record(&["field", "value"]);
+            for (name, value) in fields {
+                body.push_str(&negotiation::csv_record(&[name, value]));
+            }
+            for file in &files_info {
+                body.push_str(&negotiation::csv_record(&["file", file]));
+            }
+            Ok(HttpResponse::Ok().content_type("text/csv; charset=utf-8").body(body))
+        },
+        Some(ResponseFormat::Html) => Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(Table::create(&data, "response-table").with_formatter("Fichiers", Formatter::Attachments).to_html())),
+        None => {
+            // Generate HTML table using client's table generator
+            let message: String = Table::create(&data, "response-table")
+                .with_formatter("Fichiers", Formatter::Attachments)
+                .to_html();
+
+            // Return successful response with status, message and processed data
+            Ok(HttpResponse::Ok().json(HttpSendResponse {
+                status: 200,
+                message: Some(message),
+                data: Some(form_response_data),
+            }))
+        },
+    }
+}
+
+/// Supprime les fichiers reçus d'une requête refusée
+async fn remove_uploads(store: &ContentStore, uploads: &[(StoredFile, PathBuf, Option<Vec<u8>>)]) {
+    for (_, temp_path, _) in uploads {
+        let _ = store.remove_temp(temp_path).await;
+    }
+}
+
+/// Réponse d'erreur dans le format demandé
+fn error_response(format: ResponseFormat, status: StatusCode, error: &str, details: &str) -> HttpResponse {
+    match format {
+        ResponseFormat::Json => HttpResponse::build(status).json(HttpSendResponse {
+            status: status.as_u16(),
+            message: Some(error.to_string()),
+            data: Some(serde_json::json!({ "details": details })),
+        }),
+        ResponseFormat::Csv => HttpResponse::build(status)
+            .content_type("text/plain; charset=utf-8")
+            .body(format!("{}: {}", error, details)),
+        ResponseFormat::Html => HttpResponse::build(status)
+            .content_type("text/html")
+            .body(Table::create(&serde_json::json!({
+                "error": error,
+                "details": details
+            }), "error-table").to_html()),
+    }
 }
 
-/// Récupère toutes les données de la table form_data
+/// Récupère une page de la table users, triée et filtrée selon la requête
+///
+/// Paramètres : `page`, `per_page`, `sort`, `order` (`asc`|`desc`) et un filtre par colonne.
+/// Le format suit `Accept` ou `?format=html|json|csv` (HTML par défaut). Le total est renvoyé
+/// dans les en-têtes `X-Total-Count`, `X-Page`, `X-Per-Page` et `X-Total-Pages`.
+/// En CSV, sans `page` ni `per_page`, toutes les lignes filtrées sont exportées en flux.
 pub async fn get_form_data(
-    db_pool: web::Data<DatabaseQuery>
+    req: HttpRequest,
+    db_pool: web::Data<DatabaseQuery>,
+    params: web::Query<HashMap<String, String>>,
 ) -> Result<HttpResponse, Error> {
+    let format = ResponseFormat::from_request(&req)?.unwrap_or(ResponseFormat::Html);
+
+    let query = match ListQuery::from_params::<User>(&params) {
+        Ok(query) => query,
+        Err(e) => return Ok(error_response(format, StatusCode::BAD_REQUEST, "Invalid query parameters", &e)),
+    };
+
     // Créer le repository pour la base de données
     let user_repo: UserRepository = UserRepository::new(db_pool.get_ref().clone());
-      // Récupérer toutes les données
-    match user_repo.get_all().await {
-        Ok(form_data_list) => {
-            // Convertir en JSON pour Table::create() - wrap dans un objet avec une clé
-            let wrapped_data = serde_json::json!({"form_data": form_data_list});
-            
-            // Générer le HTML de la table avec les outils existants
-            let table_html = Table::create(&wrapped_data, "form-data-table").to_html();
-            
-            // Retourner le HTML directement
-            Ok(HttpResponse::Ok()
-                .content_type("text/html")
-                .body(table_html))
+
+    if format == ResponseFormat::Csv {
+        let repository = Rc::new(user_repo);
+        let (filter, sort) = (query.filter(), query.sort.clone());
+        let size = if query.paged { query.page.size } else { Page::MAX_SIZE };
+
+        return Ok(negotiation::stream_csv(User::COLUMNS, "form_data.csv", query.page.number, query.paged, move |number| {
+            let (repository, filter, sort) = (Rc::clone(&repository), filter.clone(), sort.clone());
+            async move { repository.list(&filter, Page::new(number, size), &sort).await }
+        }));
+    }
+
+    match user_repo.list(&query.filter(), query.page, &query.sort).await {
+        Ok(result) => {
+            let response = if format == ResponseFormat::Json {
+                HttpResponse::Ok().json(HttpSendResponse {
+                    status: 200,
+                    message: Some(format!("{} users", result.total)),
+                    data: to_value(&result).ok(),
+                })
+            } else {
+                // Une ligne par utilisateur, suivie de la navigation
+                let rows = to_value(&result.items).unwrap_or(Value::Null);
+                let table_html = Table::create(&rows, "form-data-table")
+                    .with_grid()
+                    .with_columns(&["login", "firstname", "lastname", "email"])
+                    .hide_column("id")
+                    .with_label("firstname", "prénom")
+                    .with_label("lastname", "nom")
+                    .with_label("created_at", "créé le")
+                    .with_label("files_info", "fichiers")
+                    .with_formatter("created_at", Formatter::DateTime)
+                    .with_formatter("files_info", Formatter::Attachments)
+                    .to_html();
+                HttpResponse::Ok()
+                    .content_type("text/html")
+                    .body(format!("{}{}", table_html, query.pagination_html(&result)))
+            };
+            Ok(negotiation::with_pagination_headers(response, &result))
         },
         Err(e) => {
             println!("Database error: {}", e);
-            let error_table = Table::create(&serde_json::json!({
-                "error": "Failed to fetch form data",
-                "details": e.to_string()
-            }), "error-table").to_html();
-            
-            Ok(HttpResponse::InternalServerError()
-                .content_type("text/html")
-                .body(error_table))
+            Ok(error_response(format, StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch form data", &e.to_string()))
         }
     }
 }
@@ -1,3 +1,7 @@
 pub mod ping_controller;
 pub mod index_controller;
 pub mod weather_controller;
+pub mod users_controller;
+pub mod files_controller;
+pub mod auth_controller;
+pub mod totp_controller;
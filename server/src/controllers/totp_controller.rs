@@ -0,0 +1,96 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use core::config::TotpConfig;
+use core::repositories::TotpRepository;
+use core::_database::DatabaseQuery;
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use crate::auth::{totp, AuthenticatedUser};
+use super::auth_controller::{database_error, respond};
+
+#[derive(Deserialize)]
+pub struct CodeRequest {
+    /// Code TOTP à 6 chiffres ou, pour `disable`, code de secours
+    pub code: String,
+}
+
+/// Le second facteur se gère depuis une session, jamais avec une clé d'API
+fn require_session(auth: &AuthenticatedUser) -> Option<HttpResponse> {
+    auth.session.is_none().then(|| respond(StatusCode::FORBIDDEN, "Two-factor settings require a session", None))
+}
+
+/// POST /api/auth/totp/enroll : nouveau secret en attente de confirmation, avec son URI
+/// `otpauth://` et le QR code SVG à scanner
+pub async fn enroll(auth: AuthenticatedUser, db_pool: web::Data<DatabaseQuery>, config: web::Data<TotpConfig>) -> HttpResponse {
+    if let Some(refused) = require_session(&auth) {
+        return refused;
+    }
+    let totp_repository = TotpRepository::new(db_pool.get_ref().clone());
+    match totp_repository.is_enabled(auth.user.id).await {
+        Ok(true) => return respond(StatusCode::CONFLICT, "Two-factor authentication is already enabled", None),
+        Ok(false) => {},
+        Err(e) => return database_error(e),
+    }
+
+    let secret = totp::generate_secret();
+    if let Err(e) = totp_repository.begin_enrollment(auth.user.id, &secret).await {
+        return database_error(e);
+    }
+    let account = auth.user.login.clone().unwrap_or_else(|| auth.user.id.to_string());
+    let uri = totp::otpauth_uri(&config.issuer, &account, &secret);
+    respond(StatusCode::OK, "Scan the QR code, then confirm with a code", Some(json!({
+        "secret": secret,
+        "otpauth_uri": uri,
+        "qr_svg": totp::qr_svg(&uri),
+    })))
+}
+
+/// POST /api/auth/totp/confirm : `{ "code" }`, active le secret en attente et remet
+/// les codes de secours, affichés cette seule fois
+pub async fn confirm(auth: AuthenticatedUser, db_pool: web::Data<DatabaseQuery>, body: web::Json<CodeRequest>) -> HttpResponse {
+    if let Some(refused) = require_session(&auth) {
+        return refused;
+    }
+    let totp_repository = TotpRepository::new(db_pool.get_ref().clone());
+    let pending = match totp_repository.find(auth.user.id).await {
+        Ok(Some(secret)) if !secret.is_enabled() => secret,
+        Ok(Some(_)) => return respond(StatusCode::CONFLICT, "Two-factor authentication is already enabled", None),
+        Ok(None) => return respond(StatusCode::NOT_FOUND, "No pending enrollment, call /api/auth/totp/enroll first", None),
+        Err(e) => return database_error(e),
+    };
+    let Some(step) = totp::verify(&pending.secret, &body.code, OffsetDateTime::now_utc().unix_timestamp()) else {
+        return respond(StatusCode::UNPROCESSABLE_ENTITY, "Invalid two-factor code", None);
+    };
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| totp::recovery_code_hash(code)).collect();
+    match totp_repository.enable(auth.user.id, step, &hashes).await {
+        Ok(true) => respond(StatusCode::OK, "Two-factor authentication enabled", Some(json!({ "recovery_codes": recovery_codes }))),
+        Ok(false) => respond(StatusCode::CONFLICT, "Two-factor authentication is already enabled", None),
+        Err(e) => database_error(e),
+    }
+}
+
+/// POST /api/auth/totp/disable : `{ "code" }`, retire le second facteur sur présentation
+/// d'un code TOTP ou d'un code de secours
+pub async fn disable(auth: AuthenticatedUser, db_pool: web::Data<DatabaseQuery>, body: web::Json<CodeRequest>) -> HttpResponse {
+    if let Some(refused) = require_session(&auth) {
+        return refused;
+    }
+    let totp_repository = TotpRepository::new(db_pool.get_ref().clone());
+    match totp_repository.is_enabled(auth.user.id).await {
+        Ok(true) => {},
+        Ok(false) => return respond(StatusCode::NOT_FOUND, "Two-factor authentication is not enabled", None),
+        Err(e) => return database_error(e),
+    }
+    match totp::check_second_factor(&totp_repository, auth.user.id, &body.code, OffsetDateTime::now_utc().unix_timestamp()).await {
+        Ok(true) => {},
+        Ok(false) => return respond(StatusCode::UNPROCESSABLE_ENTITY, "Invalid two-factor code", None),
+        Err(e) => return database_error(e),
+    }
+    match totp_repository.disable(auth.user.id).await {
+        Ok(_) => respond(StatusCode::OK, "Two-factor authentication disabled", None),
+        Err(e) => database_error(e),
+    }
+}
@@ -0,0 +1,228 @@
+use actix_web::{error, http::StatusCode, web, HttpRequest, HttpResponse};
+use core::{HttpSendResponse, UserRepository, _database::DatabaseQuery};
+use core::repositories::{LoginTaken, User};
+use serde_json::{to_value, Value};
+use std::collections::HashMap;
+use crate::models::list_query::ListQuery;
+use crate::models::user_payload::{self, UserInput, UserPatch};
+
+/// Réponse JSON enveloppée dans un `HttpSendResponse`
+fn respond(status: StatusCode, message: impl Into<String>, data: Option<Value>) -> HttpResponse {
+    HttpResponse::build(status).json(HttpSendResponse {
+        status: status.as_u16(),
+        message: Some(message.into()),
+        data,
+    })
+}
+
+fn user_data(user: &User) -> Option<Value> {
+    to_value(user).ok()
+}
+
+fn not_found(key: &str) -> HttpResponse {
+    respond(StatusCode::NOT_FOUND, format!("User '{}' not found", key), None)
+}
+
+/// Erreur d'écriture : 409 si le login est pris, 500 sinon
+fn write_error(e: anyhow::Error) -> HttpResponse {
+    match e.downcast_ref::<LoginTaken>() {
+        Some(conflict) => respond(StatusCode::CONFLICT, conflict.to_string(), None),
+        None => database_error(e),
+    }
+}
+
+fn database_error(e: anyhow::Error) -> HttpResponse {
+    println!("Database error: {}", e);
+    respond(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e), None)
+}
+
+/// Vérifie l'utilisateur, 422 avec le détail par champ s'il est invalide
+fn validation_error(user: &User) -> Option<HttpResponse> {
+    user_payload::validate(user).err().map(|errors| {
+        respond(StatusCode::UNPROCESSABLE_ENTITY, "Validation failed", Some(serde_json::json!({ "errors": errors })))
+    })
+}
+
+/// Corps JSON mal formé (400) ou de forme inattendue (422), dans une enveloppe `HttpSendResponse`
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _req| {
+        let status = match &err {
+            error::JsonPayloadError::Deserialize(e) if e.is_data() => StatusCode::UNPROCESSABLE_ENTITY,
+            error::JsonPayloadError::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let response = respond(status, format!("Invalid JSON body: {}", err), None);
+        error::InternalError::from_response(err, response).into()
+    })
+}
+
+/// GET /api/users : liste paginée, mêmes paramètres que `/api/form_data`
+pub async fn list(
+    db_pool: web::Data<DatabaseQuery>,
+    params: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let query = match ListQuery::from_params::<User>(&params) {
+        Ok(query) => query,
+        Err(e) => return respond(StatusCode::BAD_REQUEST, e, None),
+    };
+
+    let repository = UserRepository::new(db_pool.get_ref().clone());
+    match repository.list(&query.filter(), query.page, &query.sort).await {
+        Ok(result) => respond(StatusCode::OK, format!("{} users", result.total), to_value(&result).ok()),
+        Err(e) => database_error(e),
+    }
+}
+
+/// GET /api/users/{key} : par ID ou par login
+pub async fn get(db_pool: web::Data<DatabaseQuery>, path: web::Path<String>) -> HttpResponse {
+    let key = path.into_inner();
+    let repository = UserRepository::new(db_pool.get_ref().clone());
+
+    match repository.find_user(&key).await {
+        Ok(Some(user)) => respond(StatusCode::OK, "User found", user_data(&user)),
+        Ok(None) => not_found(&key),
+        Err(e) => database_error(e),
+    }
+}
+
+/// POST /api/users : 201 avec l'en-tête `Location`
+pub async fn create(
+    db_pool: web::Data<DatabaseQuery>,
+    req: HttpRequest,
+    body: web::Json<UserInput>,
+) -> HttpResponse {
+    let user = body.into_inner().into_user();
+    if let Some(response) = validation_error(&user) {
+        return response;
+    }
+
+    let repository = UserRepository::new(db_pool.get_ref().clone());
+    match repository.insert_unique(&user).await {
+        Ok(user) => {
+            let location = format!("{}/{}", req.path().trim_end_matches('/'), user.id);
+            let mut response = respond(StatusCode::CREATED, "User created", user_data(&user));
+            if let Ok(value) = location.parse() {
+                response.headers_mut().insert(actix_web::http::header::LOCATION, value);
+            }
+            response
+        },
+        Err(e) => write_error(e),
+    }
+}
+
+/// PUT /api/users/{key} : remplace toute la représentation
+pub async fn replace(
+    db_pool: web::Data<DatabaseQuery>,
+    path: web::Path<String>,
+    body: web::Json<UserInput>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let input = body.into_inner();
+    update_with(db_pool, &key, |user| input.apply_to(user)).await
+}
+
+/// PATCH /api/users/{key} : ne modifie que les champs présents
+pub async fn patch(
+    db_pool: web::Data<DatabaseQuery>,
+    path: web::Path<String>,
+    body: web::Json<UserPatch>,
+) -> HttpResponse {
+    let key = path.into_inner();
+    let changes = body.into_inner();
+    update_with(db_pool, &key, |user| changes.apply_to(user)).await
+}
+
+async fn update_with(
+    db_pool: web::Data<DatabaseQuery>,
+    key: &str,
+    change: impl FnOnce(&mut User),
+) -> HttpResponse {
+    let repository = UserRepository::new(db_pool.get_ref().clone());
+
+    let mut user = match repository.find_user(key).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return not_found(key),
+        Err(e) => return database_error(e),
+    };
+    change(&mut user);
+    if let Some(response) = validation_error(&user) {
+        return response;
+    }
+
+    match repository.update_unique(&user).await {
+        Ok(Some(user)) => respond(StatusCode::OK, "User updated", user_data(&user)),
+        Ok(None) => not_found(key),
+        Err(e) => write_error(e),
+    }
+}
+
+/// DELETE /api/users/{key}
+pub async fn delete(db_pool: web::Data<DatabaseQuery>, path: web::Path<String>) -> HttpResponse {
+    let key = path.into_inner();
+    let repository = UserRepository::new(db_pool.get_ref().clone());
+
+    let user = match repository.find_user(&key).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return not_found(&key),
+        Err(e) => return database_error(e),
+    };
+
+    match repository.delete_user(user.id).await {
+        Ok(true) => respond(StatusCode::OK, "User deleted", user_data(&user)),
+        Ok(false) => not_found(&key),
+        Err(e) => database_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, database};
+    use actix_web::http::header;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::App;
+    use serde_json::json;
+
+    #[test]
+    fn creates_validates_and_patches_users() {
+        block_on(async {
+            let app = init_service(App::new()
+                .app_data(web::Data::new(database().await))
+                .service(web::scope("/api/users")
+                    .app_data(json_config())
+                    .route("", web::post().to(create))
+                    .route("/{key}", web::get().to(get))
+                    .route("/{key}", web::patch().to(patch)))).await;
+            let post = |body: Value| TestRequest::post().uri("/api/users").set_json(body).to_request();
+
+            let response = call_service(&app, post(json!({ "login": "alice", "firstname": "Alice", "email": "alice@example.com" }))).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let location = response.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+            let created: HttpSendResponse = read_body_json(response).await;
+            let id = created.data.unwrap()["id"].as_str().unwrap().to_string();
+            assert_eq!(location, format!("/api/users/{}", id));
+            let response = call_service(&app, TestRequest::get().uri(&location).to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let unknown = format!("/api/users/{}", uuid::Uuid::new_v4());
+            assert_eq!(call_service(&app, TestRequest::get().uri(&unknown).to_request()).await.status(), StatusCode::NOT_FOUND);
+            assert_eq!(call_service(&app, post(json!({ "login": "alice" }))).await.status(), StatusCode::CONFLICT);
+
+            // Erreurs détaillées par champ ; champ inconnu refusé par `json_config`
+            let response = call_service(&app, post(json!({ "login": "", "age": 151, "birthday": "2024-13-01" }))).await;
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+            let rejected: HttpSendResponse = read_body_json(response).await;
+            let errors = rejected.data.unwrap()["errors"].as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+            assert_eq!(errors, vec!["age", "birthday", "login"]);
+            assert_eq!(call_service(&app, post(json!({ "login": "bob", "admin": true }))).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+            // PATCH : `null` vide le champ, un champ absent reste inchangé
+            let request = TestRequest::patch().uri(&location).set_json(json!({ "email": null, "age": 31 })).to_request();
+            let response = call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let patched: HttpSendResponse = read_body_json(response).await;
+            let patched = patched.data.unwrap();
+            assert_eq!((&patched["email"], &patched["age"], &patched["firstname"]), (&Value::Null, &json!(31), &json!("Alice")));
+        });
+    }
+}
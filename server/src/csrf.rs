@@ -0,0 +1,353 @@
+use actix_web::body::MessageBody;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use core::config::CsrfConfig;
+use core::HttpSendResponse;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use crate::auth::{ApiKeyIdentity, SessionManager};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Refus d'une requête sans jeton CSRF valide (403)
+#[derive(Debug, PartialEq)]
+pub enum CsrfError {
+    /// Pas de cookie : le client doit d'abord appeler `GET /api/csrf`
+    MissingCookie,
+    /// Pas d'en-tête, ou en-tête différent du cookie
+    Mismatch,
+    /// Jeton non signé par ce serveur
+    InvalidToken,
+}
+
+impl std::fmt::Display for CsrfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsrfError::MissingCookie => write!(f, "CSRF cookie missing, fetch a token from /api/csrf"),
+            CsrfError::Mismatch => write!(f, "CSRF token missing or does not match the cookie"),
+            CsrfError::InvalidToken => write!(f, "Invalid CSRF token"),
+        }
+    }
+}
+
+/// Jetons CSRF « double-submit » liés à la session
+///
+/// Le jeton `<nonce>.<hmac>` est posé dans un cookie `SameSite=Strict` et doit être renvoyé
+/// dans l'en-tête `X-CSRF-Token` : un site tiers peut faire envoyer le cookie, pas le lire.
+/// Le HMAC porte sur l'identifiant de la session (ou, avant la connexion, sur un identifiant
+/// anonyme tiré au hasard dans son propre cookie) : un jeton obtenu par un tiers sur
+/// `/api/csrf` puis imposé par un cookie d'un sous-domaine ne vaut pas pour la session de la
+/// victime. Le jeton change à chaque connexion et déconnexion (`rotate`).
+pub struct CsrfGuard {
+    config: CsrfConfig,
+    key: Vec<u8>,
+}
+
+impl CsrfGuard {
+    pub fn new(config: CsrfConfig) -> Self {
+        let key = match &config.secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                let mut key = vec![0u8; 32];
+                OsRng.fill_bytes(&mut key);
+                key
+            }
+        };
+        Self { config, key }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(CsrfConfig::from_env())
+    }
+
+    pub fn config(&self) -> &CsrfConfig {
+        &self.config
+    }
+
+    fn mac(&self, binding: &str, nonce: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(format!("csrf\n{}\n{}", binding, nonce).as_bytes());
+        mac
+    }
+
+    /// Nouveau jeton signé pour la session (ou l'identifiant anonyme) `binding`
+    pub fn issue(&self, binding: &str) -> String {
+        let nonce = random_hex();
+        let signature = hex::encode(self.mac(binding, &nonce).finalize().into_bytes());
+        format!("{}.{}", nonce, signature)
+    }
+
+    /// Signature du jeton pour `binding`, vérifiée en temps constant
+    pub fn is_valid(&self, token: &str, binding: &str) -> bool {
+        let Some((nonce, signature)) = token.split_once('.') else {
+            return false;
+        };
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.mac(binding, nonce).verify_slice(&signature).is_ok()
+    }
+
+    /// Nom du cookie de l'identifiant anonyme, utilisé tant qu'il n'y a pas de session
+    fn anonymous_cookie_name(&self) -> String {
+        format!("{}_anonymous", self.config.cookie_name)
+    }
+
+    /// Lien du jeton : la session du cookie de session s'il y en a un, sinon l'identifiant anonyme
+    pub fn binding(&self, req: &HttpRequest) -> Option<String> {
+        let session = req.app_data::<web::Data<SessionManager>>().and_then(|sessions| sessions.token(req));
+        match session {
+            Some(token) => Some(session_binding(&token)),
+            None => req.cookie(&self.anonymous_cookie_name())
+                .map(|cookie| cookie.value().to_string())
+                .filter(|id| !id.is_empty())
+                .map(|id| anonymous_binding(&id)),
+        }
+    }
+
+    /// Jeton du cookie, s'il est signé par ce serveur pour `binding`
+    pub fn cookie_token(&self, req: &HttpRequest, binding: &str) -> Option<String> {
+        req.cookie(&self.config.cookie_name)
+            .map(|cookie| cookie.value().to_string())
+            .filter(|token| self.is_valid(token, binding))
+    }
+
+    fn build_cookie(&self, name: String, value: &str) -> Cookie<'static> {
+        Cookie::build(name, value.to_string())
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .secure(self.config.secure)
+            .finish()
+    }
+
+    /// Cookie de session navigateur, `HttpOnly` : le client lit le jeton dans la réponse de `/api/csrf`
+    pub fn cookie(&self, token: &str) -> Cookie<'static> {
+        self.build_cookie(self.config.cookie_name.clone(), token)
+    }
+
+    /// Nouvel identifiant anonyme, son lien et son cookie
+    fn anonymous(&self) -> (String, Cookie<'static>) {
+        let id = random_hex();
+        (anonymous_binding(&id), self.build_cookie(self.anonymous_cookie_name(), &id))
+    }
+
+    /// Nouveau jeton posé sur une réponse de connexion (`session_token` ouvert) ou de
+    /// déconnexion (`None`, nouvel identifiant anonyme) : l'ancien jeton n'est plus accepté
+    pub fn rotate(&self, response: &mut HttpResponse, session_token: Option<&str>) {
+        let binding = match session_token {
+            Some(token) => session_binding(token),
+            None => {
+                let (binding, cookie) = self.anonymous();
+                let _ = response.add_cookie(&cookie);
+                binding
+            }
+        };
+        let _ = response.add_cookie(&self.cookie(&self.issue(&binding)));
+    }
+
+    /// Compare le cookie et l'en-tête d'une requête qui modifie l'état, puis vérifie que le
+    /// jeton a été signé pour la session de la requête
+    pub fn check(&self, cookie: Option<&str>, header: Option<&str>, binding: Option<&str>) -> Result<(), CsrfError> {
+        let cookie = cookie.filter(|c| !c.is_empty()).ok_or(CsrfError::MissingCookie)?;
+        let binding = binding.ok_or(CsrfError::MissingCookie)?;
+        let header = header.map(str::trim).ok_or(CsrfError::Mismatch)?;
+        // Comparaison en temps constant ; seule la longueur, publique, peut l'abréger
+        if !bool::from(cookie.as_bytes().ct_eq(header.as_bytes())) {
+            return Err(CsrfError::Mismatch);
+        }
+        if !self.is_valid(cookie, binding) {
+            return Err(CsrfError::InvalidToken);
+        }
+        Ok(())
+    }
+}
+
+fn random_hex() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn session_binding(session_token: &str) -> String {
+    format!("session:{}", SessionManager::session_id(session_token))
+}
+
+fn anonymous_binding(id: &str) -> String {
+    format!("anonymous:{}", id)
+}
+
+/// Nouveau jeton CSRF sur la réponse d'une connexion ou d'une déconnexion, si la protection est active
+pub fn rotate(req: &HttpRequest, response: &mut HttpResponse, session_token: Option<&str>) {
+    if let Some(guard) = req.app_data::<web::Data<CsrfGuard>>().filter(|guard| guard.config.enabled) {
+        guard.rotate(response, session_token);
+    }
+}
+
+/// Méthodes sans effet de bord, jamais contrôlées
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+/// Middleware de `/api` : les requêtes POST/PUT/PATCH/DELETE doivent renvoyer le jeton du cookie
+/// dans l'en-tête configuré ; une requête authentifiée par clé d'API (placée après
+/// `api_key::authenticate_bearer`) n'a pas de cookie à détourner et passe telle quelle
+pub async fn protect(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    let guard = req.app_data::<web::Data<CsrfGuard>>().cloned();
+    let exempt = is_safe(req.method()) || req.extensions().get::<ApiKeyIdentity>().is_some();
+    let Some(guard) = guard.filter(|guard| guard.config.enabled && !exempt) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let cookie = req.cookie(&guard.config.cookie_name);
+    let header = req.headers().get(guard.config.header_name.as_str()).and_then(|v| v.to_str().ok());
+    let binding = guard.binding(req.request());
+    if let Err(e) = guard.check(cookie.as_ref().map(|c| c.value()), header, binding.as_deref()) {
+        let response = HttpResponse::Forbidden().json(HttpSendResponse {
+            status: StatusCode::FORBIDDEN.as_u16(),
+            message: Some(e.to_string()),
+            data: None,
+        });
+        return Ok(req.into_response(response));
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// GET /api/csrf : jeton à renvoyer dans l'en-tête des requêtes POST/PUT/PATCH/DELETE ;
+/// le jeton du cookie est conservé tant qu'il est valide pour la session, pour ne pas
+/// invalider les autres onglets ; sans session, un identifiant anonyme est créé au besoin
+pub async fn token(req: HttpRequest, guard: web::Data<CsrfGuard>) -> HttpResponse {
+    let mut response = HttpResponse::Ok();
+    response.insert_header(("Cache-Control", "no-store"));
+    let binding = match guard.binding(&req) {
+        Some(binding) => binding,
+        None => {
+            let (binding, cookie) = guard.anonymous();
+            response.cookie(cookie);
+            binding
+        }
+    };
+    let token = match guard.cookie_token(&req, &binding) {
+        Some(token) => token,
+        None => {
+            let token = guard.issue(&binding);
+            response.cookie(guard.cookie(&token));
+            token
+        }
+    };
+    response.json(HttpSendResponse {
+        status: StatusCode::OK.as_u16(),
+        message: Some("CSRF token".to_string()),
+        data: Some(json!({ "token": token, "header": guard.config.header_name })),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(secret: Option<&str>) -> CsrfGuard {
+        CsrfGuard::new(CsrfConfig {
+            enabled: true,
+            cookie_name: "csrf".to_string(),
+            header_name: "X-CSRF-Token".to_string(),
+            secret: secret.map(str::to_string),
+            secure: true,
+        })
+    }
+
+    #[test]
+    fn requires_matching_tokens_signed_for_the_session() {
+        let guard = guard(Some(&"s".repeat(32)));
+        let session = session_binding("session-token");
+        let token = guard.issue(&session);
+        assert!(guard.is_valid(&token, &session));
+        assert_eq!(guard.check(Some(&token), Some(&token), Some(&session)), Ok(()));
+
+        assert_eq!(guard.check(None, Some(&token), Some(&session)), Err(CsrfError::MissingCookie));
+        assert_eq!(guard.check(Some(&token), Some(&token), None), Err(CsrfError::MissingCookie));
+        assert_eq!(guard.check(Some(&token), None, Some(&session)), Err(CsrfError::Mismatch));
+        assert_eq!(guard.check(Some(&token), Some(&guard.issue(&session)), Some(&session)), Err(CsrfError::Mismatch));
+
+        // Jeton imposé par un tiers : identique dans le cookie et l'en-tête, mais pas signé
+        let (nonce, _) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", nonce, "00".repeat(32));
+        assert_eq!(guard.check(Some(&forged), Some(&forged), Some(&session)), Err(CsrfError::InvalidToken));
+        assert!(!guard.is_valid("no-dot", &session));
+
+        // Jeton authentique obtenu par un tiers pour sa propre session ou son identifiant anonyme
+        for other in [session_binding("attacker-token"), anonymous_binding("attacker")] {
+            let planted = guard.issue(&other);
+            assert_eq!(guard.check(Some(&planted), Some(&planted), Some(&session)), Err(CsrfError::InvalidToken));
+        }
+
+        // Une autre clé (autre instance sans CSRF_SECRET partagé) refuse le jeton
+        assert!(!self::guard(None).is_valid(&token, &session));
+
+        let cookie = guard.cookie(&token).to_string();
+        for attribute in ["HttpOnly", "SameSite=Strict", "Secure", "Path=/"] {
+            assert!(cookie.contains(attribute), "{} missing from {}", attribute, cookie);
+        }
+    }
+
+    #[test]
+    fn rotates_the_token_at_login_and_logout() {
+        use actix_web::test::{call_service, init_service, TestRequest};
+        use core::config::SessionConfig;
+
+        crate::test_support::block_on(async {
+            let guard = web::Data::new(guard(Some(&"s".repeat(32))));
+            let sessions = web::Data::new(SessionManager::new(SessionConfig { cookie_name: "sid".to_string(), ttl: 60, secure: true }));
+            let app = init_service(
+                actix_web::App::new()
+                    .app_data(guard.clone())
+                    .app_data(sessions)
+                    .wrap(actix_web::middleware::from_fn(protect))
+                    .route("/csrf", web::get().to(token))
+                    .route("/login", web::post().to(|req: HttpRequest| async move {
+                        let mut response = HttpResponse::Ok().finish();
+                        rotate(&req, &mut response, Some("session-token"));
+                        response
+                    }))
+                    .route("/action", web::post().to(HttpResponse::Ok)),
+            ).await;
+
+            // Avant la connexion, le jeton est lié à un identifiant anonyme
+            let response = call_service(&app, TestRequest::get().uri("/csrf").to_request()).await;
+            let cookies: Vec<_> = response.response().cookies().map(|c| c.into_owned()).collect();
+            let anonymous = cookies.iter().find(|c| c.name() == "csrf_anonymous").expect("anonymous cookie").clone();
+            let token = cookies.iter().find(|c| c.name() == "csrf").expect("token cookie").value().to_string();
+            let post = |uri: &str, token: &str, cookies: &[Cookie<'static>]| {
+                let mut request = TestRequest::post().uri(uri).insert_header(("X-CSRF-Token", token.to_string()));
+                for cookie in cookies {
+                    request = request.cookie(cookie.clone());
+                }
+                request.to_request()
+            };
+            let response = call_service(&app, post("/login", &token, &[anonymous.clone(), guard.cookie(&token)])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let rotated = response.response().cookies().find(|c| c.name() == "csrf").expect("rotated cookie").value().to_string();
+            assert_ne!(rotated, token);
+
+            // Après la connexion, l'ancien jeton ne vaut plus rien, le nouveau oui
+            let session = Cookie::new("sid", "session-token");
+            let response = call_service(&app, post("/action", &token, &[session.clone(), guard.cookie(&token)])).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let response = call_service(&app, post("/action", &rotated, &[session.clone(), guard.cookie(&rotated)])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            // Un jeton authentique d'un autre client, imposé dans le cookie, est refusé
+            let response = call_service(&app, post("/action", &token, &[anonymous.clone(), guard.cookie(&token)])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let intruder = Cookie::new("csrf_anonymous", "intruder");
+            let response = call_service(&app, post("/action", &token, &[intruder, guard.cookie(&token)])).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        });
+    }
+}
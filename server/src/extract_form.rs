@@ -1,25 +1,14 @@
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use actix_multipart::Field;
+use actix_web::http::StatusCode;
+use core::config::UploadConfig;
 use futures::StreamExt;
+use crate::images::{self, ImageFormat};
+use crate::storage::ContentStore;
+use sha2::{Digest, Sha256};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
-pub async fn extract_file_info(field: &mut Field) -> String {
-    let filename = field.content_disposition()
-        .get_filename()
-        .unwrap_or("unknown")
-        .to_owned();
-    
-    let mut size = 0;
-    while let Some(chunk) = field.next().await {
-        if let Ok(data) = chunk {
-            size += data.len();
-        }
-    }
-    format!("{}({} bytes)", filename, size)
-}
-
 pub async fn extract_form_field(field: &mut Field) -> (String, String) {
     let name = field.content_disposition()
         .get_name()
@@ -37,28 +26,405 @@ pub async fn extract_form_field(field: &mut Field) -> (String, String) {
     (name, value)
 }
 
-pub async fn save_uploaded_file(field: &mut Field, filename: &str) -> Result<String, std::io::Error> {
-    // Créer le dossier Storage/form s'il n'existe pas
-    let upload_dir = Path::new("storage/files");
-    fs::create_dir_all(upload_dir)?;
+/// Refus d'un fichier uploadé
+#[derive(Debug)]
+pub enum UploadError {
+    /// Fichier plus gros que `UploadConfig::max_file_size` (413)
+    TooLarge { filename: String, limit: u64 },
+    /// Extension non autorisée ou contenu ne correspondant pas à l'extension (415)
+    UnsupportedType { filename: String, reason: String },
+    /// Image illisible ou plus grande que `ImageConfig::max_width` / `max_height` (422)
+    InvalidImage { filename: String, reason: String },
+    Io(std::io::Error),
+}
 
-    // Construire le chemin complet du fichier
-    let file_path = upload_dir.join(filename);
-    
-    // Créer le fichier
-    let mut file = File::create(&file_path).await?;
-    
-    // Lire le contenu du champ et écrire dans le fichier
-    while let Some(chunk) = field.next().await {
-        let data = chunk
-            .map_err(|e| std::io::Error::new(
-                std::io::ErrorKind::Other, 
-                e.to_string()  // Convertir l'erreur en String pour être thread-safe
-            ))?;
-        file.write_all(&data).await?;
+impl UploadError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            UploadError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::UnsupportedType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UploadError::InvalidImage { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            UploadError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::TooLarge { filename, limit } => write!(f, "File '{}' exceeds the {} bytes limit", filename, limit),
+            UploadError::UnsupportedType { filename, reason } => write!(f, "File '{}' rejected: {}", filename, reason),
+            UploadError::InvalidImage { filename, reason } => write!(f, "Image '{}' rejected: {}", filename, reason),
+            UploadError::Io(e) => write!(f, "{}", e),
+        }
     }
+}
+
+impl From<std::io::Error> for UploadError {
+    fn from(e: std::io::Error) -> Self {
+        UploadError::Io(e)
+    }
+}
+
+/// Noms de périphériques réservés par Windows, avec ou sans extension (`con.txt`)
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Nom de fichier sûr : dernier segment du chemin, caractères limités à `[A-Za-z0-9._-]`,
+/// sans point initial ni final (pas de fichier caché ni de `..`) ni nom réservé par Windows
+pub fn sanitize_filename(filename: &str) -> String {
+    let base = filename.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = base.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+    let cleaned: String = cleaned.chars().take(100).collect();
+    let cleaned = cleaned.trim_end_matches('.');
 
-    // Retourner la taille du fichier
-    let metadata = file_path.metadata()?;
-    Ok(format!("Saved {} bytes", metadata.len()))
+    let stem = cleaned.split('.').next().unwrap_or("");
+    if cleaned.is_empty() {
+        "file".to_string()
+    } else if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        format!("_{}", cleaned)
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Extension en minuscules, sans le point
+fn extension_of(filename: &str) -> Option<String> {
+    Path::new(filename).extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Types reconnus par leur signature
+#[derive(Debug, PartialEq)]
+enum Sniffed {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+    Pdf,
+    Zip,
+    Text,
+    Binary,
+}
+
+/// Nombre d'octets lus avant de vérifier la signature
+const SNIFF_LEN: usize = 512;
+
+fn sniff(head: &[u8]) -> Sniffed {
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Sniffed::Jpeg
+    } else if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Sniffed::Png
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        Sniffed::Gif
+    } else if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+        Sniffed::Webp
+    } else if head.starts_with(b"%PDF-") {
+        Sniffed::Pdf
+    } else if head.starts_with(b"PK\x03\x04") {
+        Sniffed::Zip
+    } else if !head.contains(&0) && valid_utf8_prefix(head) {
+        Sniffed::Text
+    } else {
+        Sniffed::Binary
+    }
+}
+
+/// UTF-8 valide, en tolérant un caractère coupé en fin de tampon
+fn valid_utf8_prefix(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Contenu attendu pour une extension : seules ces extensions sont acceptées, quelle que
+/// soit `UploadConfig::allowed_extensions`
+fn expected_content(extension: &str) -> Option<Sniffed> {
+    match extension {
+        "jpg" | "jpeg" => Some(Sniffed::Jpeg),
+        "png" => Some(Sniffed::Png),
+        "gif" => Some(Sniffed::Gif),
+        "webp" => Some(Sniffed::Webp),
+        "pdf" => Some(Sniffed::Pdf),
+        "zip" | "docx" | "xlsx" | "odt" | "ods" => Some(Sniffed::Zip),
+        "txt" | "csv" | "md" | "json" => Some(Sniffed::Text),
+        _ => None,
+    }
+}
+
+/// Le contenu correspond-il à l'extension ? Une extension sans signature connue est refusée.
+fn matches_extension(extension: &str, sniffed: &Sniffed) -> bool {
+    expected_content(extension).as_ref() == Some(sniffed)
+}
+
+/// Type MIME servi au téléchargement : d'après l'extension, sinon d'après le contenu
+fn mime_for(extension: &str, sniffed: &Sniffed) -> &'static str {
+    match (extension, sniffed) {
+        ("jpg" | "jpeg", _) | (_, Sniffed::Jpeg) => "image/jpeg",
+        ("png", _) | (_, Sniffed::Png) => "image/png",
+        ("gif", _) | (_, Sniffed::Gif) => "image/gif",
+        ("webp", _) | (_, Sniffed::Webp) => "image/webp",
+        ("pdf", _) | (_, Sniffed::Pdf) => "application/pdf",
+        ("csv", _) => "text/csv",
+        ("md", _) => "text/markdown",
+        ("json", _) => "application/json",
+        (_, Sniffed::Zip) => "application/zip",
+        (_, Sniffed::Text) => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Upload reçu et vérifié, en attente de `ContentStore::commit`
+#[derive(Debug, Clone)]
+pub struct SavedUpload {
+    /// Fichier temporaire contenant l'upload
+    pub temp_path: PathBuf,
+    pub size: u64,
+    pub mime: &'static str,
+    /// SHA-256 du contenu, en hexadécimal
+    pub sha256: String,
+    /// Miniature PNG, pour les images que le serveur sait décoder
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// Empreinte SHA-256 en hexadécimal
+fn sha256_hex(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/// Reçoit un fichier uploadé dans un fichier temporaire du `ContentStore` en appliquant les limites
+///
+/// L'extension, autorisée par la configuration et de contenu connu (`expected_content`), est
+/// vérifiée avant toute écriture, la signature sur les premiers octets,
+/// et la taille au fil du flux : le fichier partiel est supprimé dès que la limite est dépassée.
+/// Le SHA-256 est calculé pendant la réception. Les images sont ensuite contrôlées
+/// (dimensions maximales), débarrassées de leurs métadonnées et accompagnées d'une miniature.
+pub async fn save_uploaded_file(field: &mut Field, filename: &str, config: &UploadConfig, store: &ContentStore) -> Result<SavedUpload, UploadError> {
+    let safe_name = sanitize_filename(filename);
+    let extension = extension_of(&safe_name).unwrap_or_default();
+    if !config.allows_extension(&extension) {
+        return Err(UploadError::UnsupportedType {
+            filename: safe_name,
+            reason: format!("extension '{}' is not allowed ({})", extension, config.allowed_extensions.join(", ")),
+        });
+    }
+    if expected_content(&extension).is_none() {
+        return Err(UploadError::UnsupportedType {
+            filename: safe_name,
+            reason: format!("extension '{}' is not a supported file type", extension),
+        });
+    }
+
+    let mut head: Vec<u8> = Vec::new();
+    let mut written: u64 = 0;
+    let mut hasher = Sha256::new();
+    let mut target: Option<(File, PathBuf)> = None;
+
+    let result = async {
+        loop {
+            let chunk = match field.next().await {
+                Some(chunk) => chunk.map_err(|e| std::io::Error::other(e.to_string()))?,
+                None => break,
+            };
+            written += chunk.len() as u64;
+            if written > config.max_file_size {
+                return Err(UploadError::TooLarge { filename: safe_name.clone(), limit: config.max_file_size });
+            }
+            hasher.update(&chunk);
+
+            match target.as_mut() {
+                Some((file, _)) => file.write_all(&chunk).await?,
+                None => {
+                    // Mettre en tampon jusqu'à avoir assez d'octets pour la signature
+                    head.extend_from_slice(&chunk);
+                    if head.len() >= SNIFF_LEN {
+                        target = Some(open_checked(store, &safe_name, &extension, &head).await?);
+                    }
+                }
+            }
+        }
+
+        if target.is_none() {
+            target = Some(open_checked(store, &safe_name, &extension, &head).await?);
+        }
+        if let Some((file, _)) = target.as_mut() {
+            file.flush().await?;
+        }
+        Ok(())
+    }.await;
+
+    match (result, target) {
+        (Ok(()), Some((file, temp_path))) => {
+            drop(file);
+            let saved = SavedUpload {
+                temp_path,
+                size: written,
+                mime: mime_for(&extension, &sniff(&head[..head.len().min(SNIFF_LEN)])),
+                sha256: hex::encode(hasher.finalize()),
+                thumbnail: None,
+            };
+            match ImageFormat::from_mime(saved.mime) {
+                Some(format) => prepare_image(saved, format, &safe_name, config, store).await,
+                None => Ok(saved),
+            }
+        },
+        (Ok(()), None) => unreachable!("file is opened once the stream ends"),
+        (Err(e), Some((file, path))) => {
+            drop(file);
+            let _ = store.remove_temp(&path).await;
+            Err(e)
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Réécrit une image reçue sans ses métadonnées et calcule sa miniature ;
+/// le fichier temporaire est supprimé si l'image est refusée
+async fn prepare_image(mut saved: SavedUpload, format: ImageFormat, safe_name: &str, config: &UploadConfig, store: &ContentStore) -> Result<SavedUpload, UploadError> {
+    let result = async {
+        let content = tokio::fs::read(&saved.temp_path).await?;
+        let image_config = config.image.clone();
+        let prepared = tokio::task::spawn_blocking(move || images::prepare(&content, format, &image_config))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?
+            .map_err(|e| UploadError::InvalidImage { filename: safe_name.to_string(), reason: e.to_string() })?;
+        tokio::fs::write(&saved.temp_path, &prepared.content).await?;
+        Ok::<_, UploadError>(prepared)
+    }.await;
+
+    match result {
+        Ok(prepared) => {
+            saved.size = prepared.content.len() as u64;
+            saved.sha256 = sha256_hex(&prepared.content);
+            saved.thumbnail = prepared.thumbnail;
+            Ok(saved)
+        },
+        Err(e) => {
+            let _ = store.remove_temp(&saved.temp_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Vérifie la signature des premiers octets, puis crée le fichier temporaire et y écrit le tampon
+async fn open_checked(store: &ContentStore, safe_name: &str, extension: &str, head: &[u8]) -> Result<(File, PathBuf), UploadError> {
+    let sniffed = sniff(&head[..head.len().min(SNIFF_LEN)]);
+    if !matches_extension(extension, &sniffed) {
+        return Err(UploadError::UnsupportedType {
+            filename: safe_name.to_string(),
+            reason: format!("content looks like {:?}, not .{}", sniffed, extension),
+        });
+    }
+
+    let (mut file, path) = store.create_temp().await?;
+    file.write_all(head).await?;
+    Ok((file, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBlobStore;
+    use crate::test_support::block_on;
+    use actix_multipart::Multipart;
+    use actix_web::http::header::{self, HeaderMap, HeaderValue};
+    use actix_web::web::Bytes;
+    use core::config::ImageConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn sanitizes_hostile_filenames() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("..\\..\\boot.ini"), "boot.ini");
+        assert_eq!(sanitize_filename("../"), "file");
+        assert_eq!(sanitize_filename(".."), "file");
+        assert_eq!(sanitize_filename(".htaccess"), "htaccess");
+        assert_eq!(sanitize_filename("evil\0.txt"), "evil_.txt");
+        assert_eq!(sanitize_filename("rapport final (v2).pdf"), "rapport_final__v2_.pdf");
+        assert_eq!(sanitize_filename("notes.txt..."), "notes.txt");
+        for reserved in ["CON", "con.txt", "Nul.tar.gz", "com1.pdf", "LPT9", "aux."] {
+            assert!(sanitize_filename(reserved).starts_with('_'), "{} not escaped", reserved);
+        }
+        assert_eq!(sanitize_filename("console.txt"), "console.txt");
+        assert_eq!(sanitize_filename(&"a".repeat(300)).len(), 100);
+    }
+
+    #[test]
+    fn accepts_only_known_extensions_with_matching_content() {
+        assert!(matches_extension("png", &sniff(b"\x89PNG\r\n\x1a\n....")));
+        assert!(matches_extension("txt", &sniff("déjà vu".as_bytes())));
+        assert!(!matches_extension("png", &sniff(b"%PDF-1.7")));
+        assert!(!matches_extension("txt", &sniff(b"MZ\x90\0\x03")));
+        // Extensions sans signature connue : refusées même si le contenu ressemble à du texte
+        for extension in ["exe", "html", "svg", "js", ""] {
+            assert!(!matches_extension(extension, &sniff(b"hello")), "{} accepted", extension);
+        }
+    }
+
+    fn config(max_file_size: u64, allowed: &[&str]) -> UploadConfig {
+        UploadConfig {
+            max_file_size,
+            allowed_extensions: allowed.iter().map(|ext| ext.to_string()).collect(),
+            upload_dir: String::new(),
+            image: ImageConfig { max_width: 64, max_height: 64, thumbnail_size: 16 },
+        }
+    }
+
+    /// Envoie `chunks` comme unique fichier `filename` d'un formulaire multipart
+    async fn upload(filename: &str, chunks: Vec<&'static [u8]>, config: &UploadConfig, store: &ContentStore) -> Result<SavedUpload, UploadError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("multipart/form-data; boundary=XyZ"));
+        let start = format!(
+            "--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            filename,
+        );
+        let mut body = vec![Ok(Bytes::from(start))];
+        body.extend(chunks.into_iter().map(|chunk| Ok(Bytes::from_static(chunk))));
+        body.push(Ok(Bytes::from_static(b"\r\n--XyZ--\r\n")));
+
+        // Chaque morceau arrive séparément, comme sur le réseau
+        let body = futures::stream::iter(body).then(|chunk| async {
+            tokio::task::yield_now().await;
+            chunk
+        });
+        let mut multipart = Multipart::new(&headers, body);
+        let mut field = multipart.next().await.unwrap().unwrap();
+        save_uploaded_file(&mut field, filename, config, store).await
+    }
+
+    #[test]
+    fn refuses_oversize_and_mismatched_uploads_without_leftovers() {
+        let root = std::env::temp_dir().join(format!("uploads-{}", uuid::Uuid::new_v4()));
+        let store = ContentStore::new(Arc::new(MemoryBlobStore::default()), &root);
+        let config = config(1024, &["txt", "png", "exe"]);
+        let kilobyte: &'static [u8] = &[b'a'; 1024];
+
+        block_on(async {
+            // Limite dépassée en cours de flux, après l'ouverture du fichier temporaire
+            let error = upload("big.txt", vec![kilobyte, b"!"], &config, &store).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+            assert!(root.join("tmp").is_dir());
+            assert!(store.temp_files().unwrap().is_empty());
+
+            let saved = upload("notes.txt", vec![b"hello ", b"world"], &config, &store).await.unwrap();
+            assert_eq!((saved.size, saved.mime), (11, "text/plain"));
+            store.remove_temp(&saved.temp_path).await.unwrap();
+
+            let error = upload("photo.png", vec![b"%PDF-1.7 not an image"], &config, &store).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            // Autorisée par la configuration, mais sans signature connue
+            let error = upload("setup.exe", vec![b"MZ"], &config, &store).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            let error = upload("page.html", vec![b"<html>"], &config, &store).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert!(store.temp_files().unwrap().is_empty());
+        });
+        let _ = std::fs::remove_dir_all(root);
+    }
 }
@@ -0,0 +1,366 @@
+use std::io::Cursor;
+use core::config::ImageConfig;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader, Limits};
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::png::Png;
+use img_parts::riff::RiffContent;
+use img_parts::webp::{WebP, CHUNK_EXIF, CHUNK_VP8X, CHUNK_XMP};
+use img_parts::{Bytes, ImageEXIF};
+
+/// Formats d'image acceptés à l'upload
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+}
+
+impl ImageFormat {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "image/jpeg" => Some(ImageFormat::Jpeg),
+            "image/png" => Some(ImageFormat::Png),
+            "image/gif" => Some(ImageFormat::Gif),
+            "image/webp" => Some(ImageFormat::Webp),
+            _ => None,
+        }
+    }
+
+    fn codec(self) -> image::ImageFormat {
+        match self {
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Gif => image::ImageFormat::Gif,
+            ImageFormat::Webp => image::ImageFormat::WebP,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ImageError {
+    /// Contenu illisible ou tronqué
+    Invalid(String),
+    /// Image valide que le décodeur ne sait pas lire (variante de format non prise en charge)
+    Unsupported(String),
+    /// Dimensions au-delà de `ImageConfig::max_width` / `max_height`
+    TooLarge { width: u32, height: u32, max_width: u32, max_height: u32 },
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Invalid(reason) => write!(f, "invalid image: {}", reason),
+            ImageError::Unsupported(reason) => write!(f, "unsupported image: {}", reason),
+            ImageError::TooLarge { width, height, max_width, max_height } =>
+                write!(f, "image is {}x{} pixels, the limit is {}x{}", width, height, max_width, max_height),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<image::ImageError> for ImageError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::Unsupported(e) => ImageError::Unsupported(e.to_string()),
+            other => ImageError::Invalid(other.to_string()),
+        }
+    }
+}
+
+impl From<img_parts::Error> for ImageError {
+    fn from(error: img_parts::Error) -> Self {
+        ImageError::Invalid(error.to_string())
+    }
+}
+
+impl From<gif::DecodingError> for ImageError {
+    fn from(error: gif::DecodingError) -> Self {
+        ImageError::Invalid(error.to_string())
+    }
+}
+
+impl From<gif::EncodingError> for ImageError {
+    fn from(error: gif::EncodingError) -> Self {
+        ImageError::Invalid(error.to_string())
+    }
+}
+
+pub type ImageResult<T> = Result<T, ImageError>;
+
+fn invalid<T>(reason: &str) -> ImageResult<T> {
+    Err(ImageError::Invalid(reason.to_string()))
+}
+
+/// Largeur et hauteur lues dans l'en-tête, sans décoder l'image
+pub fn dimensions(content: &[u8], format: ImageFormat) -> ImageResult<(u32, u32)> {
+    Ok(ImageReader::with_format(Cursor::new(content), format.codec()).into_dimensions()?)
+}
+
+/// Dimensions de l'image, refusée si elle est vide ou dépasse les limites de `config`
+pub fn check_dimensions(content: &[u8], format: ImageFormat, config: &ImageConfig) -> ImageResult<(u32, u32)> {
+    let (width, height) = dimensions(content, format)?;
+    if width == 0 || height == 0 {
+        return invalid("empty image");
+    }
+    if width > config.max_width || height > config.max_height {
+        return Err(ImageError::TooLarge { width, height, max_width: config.max_width, max_height: config.max_height });
+    }
+    Ok((width, height))
+}
+
+/// Décode l'image, redressée selon son orientation EXIF, après contrôle des dimensions ;
+/// les mêmes limites sont passées au décodeur pour les images dont l'en-tête ment
+fn decode(content: &[u8], format: ImageFormat, config: &ImageConfig) -> ImageResult<DynamicImage> {
+    check_dimensions(content, format, config)?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(config.max_width);
+    limits.max_image_height = Some(config.max_height);
+    let mut reader = ImageReader::with_format(Cursor::new(content), format.codec());
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Miniature PNG carrée de `config.thumbnail_size` pixels, prise au centre de l'image ;
+/// première image pour un GIF ou un WebP animé
+pub fn thumbnail(content: &[u8], format: ImageFormat, config: &ImageConfig) -> ImageResult<Vec<u8>> {
+    let size = config.thumbnail_size;
+    let image = decode(content, format, config)?.resize_to_fill(size, size, FilterType::Triangle);
+    // PNG 8 bits, en gardant la transparence seulement si la source en a
+    let image = match image.color().has_alpha() {
+        true => DynamicImage::ImageRgba8(image.to_rgba8()),
+        false => DynamicImage::ImageRgb8(image.to_rgb8()),
+    };
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Retire les métadonnées (EXIF, XMP, commentaires, textes) ; l'image elle-même est
+/// recopiée sans réencodage
+pub fn strip_metadata(content: &[u8], format: ImageFormat) -> ImageResult<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => strip_jpeg(content),
+        ImageFormat::Png => strip_png(content),
+        ImageFormat::Gif => strip_gif(content),
+        ImageFormat::Webp => strip_webp(content),
+    }
+}
+
+/// Garde JFIF, le profil ICC et le segment Adobe (sens des couleurs CMYK) ; l'EXIF est
+/// remplacé par un EXIF minimal portant la seule orientation
+fn strip_jpeg(content: &[u8]) -> ImageResult<Vec<u8>> {
+    let mut jpeg = Jpeg::from_bytes(Bytes::copy_from_slice(content))?;
+    let orientation = jpeg.exif()
+        .and_then(|exif| Orientation::from_exif_chunk(&exif))
+        .unwrap_or(Orientation::NoTransforms);
+    jpeg.segments_mut().retain(|segment| match segment.marker() {
+        markers::APP2 => segment.contents().starts_with(b"ICC_PROFILE\0"),
+        markers::APP14 => segment.contents().starts_with(b"Adobe"),
+        markers::APP1..=markers::APP15 | markers::COM => false,
+        _ => true,
+    });
+
+    if orientation != Orientation::NoTransforms {
+        // Juste après JFIF, ou en tête s'il n'y en a pas
+        let position = jpeg.segments().iter().take_while(|segment| segment.marker() == markers::APP0).count();
+        jpeg.segments_mut().insert(position, JpegSegment::new_with_contents(markers::APP1, orientation_exif(orientation)));
+    }
+    let mut stripped = Vec::with_capacity(content.len());
+    jpeg.encoder().write_to(&mut stripped).map_err(|e| ImageError::Invalid(e.to_string()))?;
+    Ok(stripped)
+}
+
+/// Segment APP1 EXIF big-endian réduit à une entrée : l'orientation (balise 0x0112)
+fn orientation_exif(orientation: Orientation) -> Bytes {
+    let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08".to_vec();
+    exif.extend_from_slice(&[0, 1]);
+    exif.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation.to_exif(), 0, 0]);
+    exif.extend_from_slice(&[0, 0, 0, 0]);
+    Bytes::from(exif)
+}
+
+/// Blocs utiles à l'affichage ; les textes (tEXt, iTXt, zTXt), eXIf et dates sont retirés
+const PNG_KEPT_CHUNKS: [&[u8; 4]; 15] = [
+    b"IHDR", b"PLTE", b"IDAT", b"IEND", b"tRNS", b"gAMA", b"cHRM", b"sRGB",
+    b"iCCP", b"sBIT", b"bKGD", b"pHYs", b"acTL", b"fcTL", b"fdAT",
+];
+
+fn strip_png(content: &[u8]) -> ImageResult<Vec<u8>> {
+    let mut png = Png::from_bytes(Bytes::copy_from_slice(content))?;
+    png.chunks_mut().retain(|chunk| PNG_KEPT_CHUNKS.contains(&&chunk.kind()));
+    let mut stripped = Vec::with_capacity(content.len());
+    png.encoder().write_to(&mut stripped).map_err(|e| ImageError::Invalid(e.to_string()))?;
+    Ok(stripped)
+}
+
+/// Recopie les images, déjà compressées, et la boucle d'animation ; commentaires et
+/// autres extensions d'application (XMP…) sont laissés de côté
+fn strip_gif(content: &[u8]) -> ImageResult<Vec<u8>> {
+    let mut options = gif::DecodeOptions::new();
+    options.skip_frame_decoding(true);
+    let mut decoder = options.read_info(content)?;
+    let mut stripped = Vec::with_capacity(content.len());
+    {
+        let palette = decoder.global_palette().unwrap_or(&[]).to_vec();
+        let mut encoder = gif::Encoder::new(&mut stripped, decoder.width(), decoder.height(), &palette)?;
+        let mut first = true;
+        while let Some(frame) = decoder.read_next_frame()? {
+            let frame = frame.clone();
+            // L'extension NETSCAPE2.0 précède la première image : son nombre de boucles est connu ici
+            if first {
+                encoder.set_repeat(decoder.repeat())?;
+                first = false;
+            }
+            encoder.write_lzw_pre_encoded_frame(&frame)?;
+        }
+    }
+    Ok(stripped)
+}
+
+/// Bits EXIF et XMP de l'en-tête VP8X, à effacer avec les blocs correspondants
+const VP8X_EXIF_FLAG: u8 = 0x08;
+const VP8X_XMP_FLAG: u8 = 0x04;
+
+fn strip_webp(content: &[u8]) -> ImageResult<Vec<u8>> {
+    let mut webp = WebP::from_bytes(Bytes::copy_from_slice(content))?;
+    webp.remove_chunks_by_id(CHUNK_EXIF);
+    webp.remove_chunks_by_id(CHUNK_XMP);
+    // Le bloc VP8X reste : il porte aussi la transparence, l'animation et le profil ICC
+    for chunk in webp.chunks_mut().iter_mut().filter(|chunk| chunk.id() == CHUNK_VP8X) {
+        if let Some(data) = chunk.content().data().filter(|data| !data.is_empty()) {
+            let mut data = data.to_vec();
+            data[0] &= !(VP8X_EXIF_FLAG | VP8X_XMP_FLAG);
+            *chunk.content_mut() = RiffContent::Data(Bytes::from(data));
+        }
+    }
+    let mut stripped = Vec::with_capacity(content.len());
+    webp.encoder().write_to(&mut stripped).map_err(|e| ImageError::Invalid(e.to_string()))?;
+    Ok(stripped)
+}
+
+/// Image prête à être stockée
+pub struct PreparedImage {
+    /// Contenu sans métadonnées
+    pub content: Vec<u8>,
+    /// `None` si le décodeur ne sait pas lire cette variante du format
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// Vérifie les dimensions, retire les métadonnées et calcule la miniature
+pub fn prepare(content: &[u8], format: ImageFormat, config: &ImageConfig) -> ImageResult<PreparedImage> {
+    check_dimensions(content, format, config)?;
+    let content = strip_metadata(content, format)?;
+    let thumbnail = match thumbnail(&content, format, config) {
+        Ok(thumbnail) => Some(thumbnail),
+        Err(ImageError::Unsupported(reason)) => {
+            println!("No thumbnail: {}", reason);
+            None
+        },
+        Err(e) => return Err(e),
+    };
+    Ok(PreparedImage { content, thumbnail })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, RgbaImage};
+
+    fn encode(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut content = Vec::new();
+        image.write_to(&mut Cursor::new(&mut content), format.codec()).unwrap();
+        content
+    }
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| Rgb([(x * 4) as u8, (y * 4) as u8, 0])))
+    }
+
+    #[test]
+    fn orients_jpeg_thumbnails_and_keeps_only_the_orientation() {
+        let config = ImageConfig { max_width: 64, max_height: 64, thumbnail_size: 8 };
+        // Moitié gauche rouge d'une image 16x8 ; EXIF orientation 6 (quart de tour horaire) et commentaire
+        let image = RgbImage::from_fn(16, 8, |x, _| if x < 8 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+        let mut jpeg = Jpeg::from_bytes(encode(DynamicImage::ImageRgb8(image), ImageFormat::Jpeg).into()).unwrap();
+        let mut exif = orientation_exif(Orientation::Rotate90).to_vec();
+        exif.extend_from_slice(b"GPS 48.85N 2.35E");
+        jpeg.segments_mut().insert(1, JpegSegment::new_with_contents(markers::APP1, exif.into()));
+        jpeg.segments_mut().insert(1, JpegSegment::new_with_contents(markers::COM, Bytes::from_static(b"secret")));
+        let content = jpeg.encoder().bytes().to_vec();
+
+        let stripped = strip_metadata(&content, ImageFormat::Jpeg).unwrap();
+        assert!(!stripped.windows(6).any(|w| w == b"secret") && !stripped.windows(3).any(|w| w == b"GPS"));
+        let kept = Jpeg::from_bytes(stripped.clone().into()).unwrap().exif().unwrap();
+        assert_eq!(Orientation::from_exif_chunk(&kept), Some(Orientation::Rotate90));
+
+        // Redressée, la moitié rouge passe en haut
+        let thumbnail = image::load_from_memory(&thumbnail(&stripped, ImageFormat::Jpeg, &config).unwrap()).unwrap().to_rgb8();
+        assert_eq!(thumbnail.dimensions(), (8, 8));
+        assert!(thumbnail.get_pixel(4, 0)[0] > 200 && thumbnail.get_pixel(4, 7)[2] > 200);
+    }
+
+    #[test]
+    fn thumbnails_every_accepted_format() {
+        let config = ImageConfig { max_width: 64, max_height: 64, thumbnail_size: 8 };
+        let transparent = DynamicImage::ImageRgba8(RgbaImage::from_fn(24, 12, |x, _| image::Rgba([0, 0, 255, if x < 12 { 0 } else { 255 }])));
+        for format in [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::Gif, ImageFormat::Webp] {
+            let source = match format {
+                ImageFormat::Jpeg => gradient(40, 20),
+                _ => transparent.clone(),
+            };
+            let size = (source.width(), source.height());
+            let prepared = prepare(&encode(source, format), format, &config).unwrap();
+            assert_eq!(dimensions(&prepared.content, format).unwrap(), size, "{:?}", format);
+            let thumbnail = image::load_from_memory(&prepared.thumbnail.unwrap()).unwrap();
+            assert_eq!((thumbnail.width(), thumbnail.height()), (8, 8), "{:?}", format);
+            assert_eq!(thumbnail.color().has_alpha(), format != ImageFormat::Jpeg, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn enforces_limits_on_every_path() {
+        let config = ImageConfig { max_width: 64, max_height: 64, thumbnail_size: 8 };
+        let too_wide = encode(gradient(65, 1), ImageFormat::Png);
+        let too_large = Some(ImageError::TooLarge { width: 65, height: 1, max_width: 64, max_height: 64 });
+        assert_eq!(prepare(&too_wide, ImageFormat::Png, &config).err(), too_large);
+        assert_eq!(thumbnail(&too_wide, ImageFormat::Png, &config).err(), too_large);
+        assert!(matches!(prepare(b"\x89PNG\r\n\x1a\n", ImageFormat::Png, &config), Err(ImageError::Invalid(_))));
+    }
+
+    #[test]
+    fn strips_png_text_and_keeps_gif_loops() {
+        let mut png = Png::from_bytes(encode(gradient(4, 4), ImageFormat::Png).into()).unwrap();
+        let end = png.chunks().len() - 1;
+        png.chunks_mut().insert(end, img_parts::png::PngChunk::new(*b"tEXt", Bytes::from_static(b"Author\0secret")));
+        let stripped = strip_metadata(&png.encoder().bytes(), ImageFormat::Png).unwrap();
+        assert!(!stripped.windows(6).any(|w| w == b"secret"));
+        assert_eq!(dimensions(&stripped, ImageFormat::Png).unwrap(), (4, 4));
+
+        let mut animated = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut animated, 2, 2, &[0, 0, 0, 255, 255, 255]).unwrap();
+            encoder.set_repeat(gif::Repeat::Infinite).unwrap();
+            for index in 0..2u8 {
+                encoder.write_frame(&gif::Frame { width: 2, height: 2, delay: 10, buffer: vec![index; 4].into(), ..gif::Frame::default() }).unwrap();
+            }
+        }
+        animated.splice(13 + 6..13 + 6, *b"\x21\xfe\x06secret\x00");
+        let stripped = strip_metadata(&animated, ImageFormat::Gif).unwrap();
+        assert!(!stripped.windows(6).any(|w| w == b"secret"));
+        let mut decoder = gif::DecodeOptions::new().read_info(stripped.as_slice()).unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        assert_eq!((frames, decoder.repeat()), (2, gif::Repeat::Infinite));
+    }
+}
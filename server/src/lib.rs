@@ -9,6 +9,16 @@ pub mod extract_form;
 pub mod models;
 pub mod controllers;
 pub mod ssl_config;
+pub mod negotiation;
+pub mod storage;
+pub mod images;
+pub mod csrf;
+pub mod signed_links;
+pub mod rate_limit;
+pub mod request_log;
+pub mod auth;
+#[cfg(test)]
+mod test_support;
 
 // Module contenant la logique complète du serveur
 pub mod server_lib;
@@ -1,11 +1,24 @@
 use dotenv::dotenv;
 use std::env;
+use core::Table;
+use serde_json::{json, Value};
 
 mod controllers;
 mod ssl_config;
 mod server_lib;
 mod extract_form;
 mod models;
+mod negotiation;
+mod storage;
+mod images;
+mod csrf;
+mod signed_links;
+mod rate_limit;
+mod request_log;
+mod auth;
+mod commands;
+#[cfg(test)]
+mod test_support;
 
 use server_lib::{start_full_web_server, create_web_server_config};
 use ssl_config::SslConfig;
@@ -20,8 +33,7 @@ async fn main() -> std::io::Result<()> {
     
     // Si il y a des arguments (cargo run -- COMMAND)
     if args.len() > 1 {
-        let command = &args[1];
-        return handle_command(command).await;
+        return handle_command(&args[1], &args[2..]).await;
     }
     
     // Si pas d'arguments, lancer le serveur web normal
@@ -30,7 +42,7 @@ async fn main() -> std::io::Result<()> {
 }
 
 /// Gère les commandes spécifiques
-async fn handle_command(command: &str) -> std::io::Result<()> {
+async fn handle_command(command: &str, args: &[String]) -> std::io::Result<()> {
     match command {
         "ssl-info" => {
             println!("🔒 === INFORMATIONS SSL ===");
@@ -57,6 +69,24 @@ async fn handle_command(command: &str) -> std::io::Result<()> {
             show_available_routes();
             Ok(())
         },
+        "migrate" => {
+            commands::migrate_command::run(args).await
+        },
+        "storage" => {
+            commands::storage_command::run(args).await
+        },
+        "link" => {
+            commands::link_command::run(args).await
+        },
+        "user" => {
+            commands::user_command::run(args).await
+        },
+        "apikey" => {
+            commands::apikey_command::run(args).await
+        },
+        "role" => {
+            commands::role_command::run(args).await
+        },
         "help" => {
             print_help();
             Ok(())
@@ -78,6 +108,20 @@ fn print_help() {
     println!("  cargo run -- config     - Affiche la configuration serveur");
     println!("  cargo run -- status     - Affiche le statut du système");
     println!("  cargo run -- routes     - Liste toutes les routes disponibles");
+    println!("  cargo run -- migrate up       - Applique les migrations en attente");
+    println!("  cargo run -- migrate down [n] - Annule les n dernières migrations (1 par défaut)");
+    println!("  cargo run -- migrate status   - Affiche l'état des migrations");
+    println!("  cargo run -- migrate redo     - Annule puis réapplique la dernière migration");
+    println!("  cargo run -- storage gc       - Supprime les fichiers stockés qui ne sont plus référencés");
+    println!("  cargo run -- link <id> [--ttl s] [--single-use] [--base-url url] - Crée un lien de téléchargement signé");
+    println!("  cargo run -- user set-password <login> - Définit le mot de passe d'un compte (lu sur l'entrée standard)");
+    println!("  cargo run -- user reset-totp <login>   - Retire le second facteur d'un compte");
+    println!("  cargo run -- apikey create <login> <nom> [--scopes read,write] [--days n] - Crée une clé d'API");
+    println!("  cargo run -- apikey list      - Liste les clés d'API");
+    println!("  cargo run -- apikey revoke <id|préfixe> - Révoque une clé d'API");
+    println!("  cargo run -- role grant <login> <rôle>  - Attribue un rôle (admin...)");
+    println!("  cargo run -- role revoke <login> <rôle> - Retire un rôle");
+    println!("  cargo run -- role list        - Liste les rôles, leurs permissions et leurs membres");
     println!("  cargo run -- help       - Affiche cette aide");
     println!();
     println!("📋 === EXEMPLES ===");
@@ -125,33 +169,66 @@ fn show_server_status() {
         "SERVER_HOST", "SERVER_PORT", "SERVER_WORKERS", 
         "MAX_CONNECTIONS", "SSL_ENABLED", "ENVIRONMENT"
     ];
-    
-    for var in &env_vars {
-        match env::var(var) {
-            Ok(value) => println!("   {}: {}", var, value),
-            Err(_) => println!("   {}: (défaut)", var),
-        }
-    }
+    let variables: Vec<Value> = env_vars.iter()
+        .map(|var| json!({
+            "variable": var,
+            "valeur": env::var(var).unwrap_or_else(|_| "(défaut)".to_string()),
+        }))
+        .collect();
+    print_indented(&Table::create(&Value::Array(variables), "").to_text());
     println!("===========================");
 }
 
 /// Affiche toutes les routes disponibles
 fn show_available_routes() {
+    let route = |method: &str, path: &str, description: &str| json!({
+        "méthode": method,
+        "chemin": path,
+        "description": description,
+    });
+
     println!("🔗 === ROUTES DISPONIBLES ===");    
     println!("📡 API Endpoints:");
-    println!("   • POST /api/form              - Soumission de formulaire");
-    println!("   • GET  /api/form_data         - Récupération des données form_data");
-    println!("   • GET  /api/ping              - Test de santé du serveur");
-    println!("   • POST /api/ping              - Test de santé du serveur");
-    println!("   • GET  /api/weather/temperature - Données météo");
+    print_indented(&Table::create(&json!([
+        route("GET", "/api/csrf", "Jeton CSRF à renvoyer dans X-CSRF-Token (POST/PUT/PATCH/DELETE sans clé d'API)"),
+        route("POST", "/api/form", "Soumission de formulaire"),
+        route("GET", "/api/form_data", "Données form_data paginées (page, per_page, sort, order, format ; users:list)"),
+        route("POST", "/api/auth/login", "Ouverture de session (JSON login/password, cookie)"),
+        route("POST", "/api/auth/login/totp", "Second facteur de la connexion (JSON challenge/code)"),
+        route("POST", "/api/auth/logout", "Fermeture de la session"),
+        route("GET", "/api/auth/me", "Utilisateur de la session"),
+        route("POST", "/api/auth/totp/enroll", "Enrôlement TOTP : secret, URI otpauth et QR code SVG"),
+        route("POST", "/api/auth/totp/confirm", "Activation du TOTP (JSON code), codes de secours"),
+        route("POST", "/api/auth/totp/disable", "Retrait du TOTP (JSON code TOTP ou de secours)"),
+        route("GET", "/api/auth/oidc/login", "Connexion par le fournisseur OpenID Connect"),
+        route("GET", "/api/auth/oidc/link", "Liaison de l'identité OIDC au compte de la session"),
+        route("GET", "/api/auth/oidc/callback", "Retour du fournisseur OIDC (ouvre une session ou termine une liaison ; second facteur via /#totp_challenge)"),
+        route("GET/POST", "/api/users", "Liste et création d'utilisateurs (JSON ; users:list, users:write)"),
+        route("GET/PUT/PATCH/DELETE", "/api/users/{id|login}", "Utilisateur (JSON ; sa propre fiche, ou users:read, users:write, users:delete)"),
+        route("GET", "/api/files/{id}", "Téléchargement d'un fichier uploadé (Range, ETag ; ses propres fichiers, ou files:read)"),
+        route("DELETE", "/api/files/{id}", "Suppression d'un fichier uploadé (ses propres fichiers, ou files:delete)"),
+        route("GET", "/api/files/{id}/thumbnail", "Miniature PNG d'une image uploadée (ses propres fichiers, ou files:read)"),
+        route("POST", "/api/files/{id}/links", "Création d'un lien de téléchargement signé et temporaire (ses propres fichiers, ou files:read)"),
+        route("GET", "/api/shared/{id}", "Téléchargement par lien signé (expires, nonce, signature)"),
+        route("GET/POST", "/api/ping", "Test de santé du serveur"),
+        route("GET", "/api/weather/temperature", "Données météo"),
+    ]), "").to_text());
     println!();
     println!("📄 Pages statiques:");
-    println!("   • GET  /                      - Page d'accueil (index.html)");
-    println!("   • GET  /pkg/*                 - Fichiers WebAssembly");
-    println!("   • GET  /favicon.ico           - Icône du site");
-    println!();
-    println!("🔧 Utilitaires:");
-    println!("   • GET  /*                     - Fichiers statiques");
-    println!("   • *    (404)                  - Gestion des erreurs");
+    print_indented(&Table::create(&json!([
+        route("GET", "/", "Page d'accueil (index.html)"),
+        route("GET", "/pkg/*", "Fichiers WebAssembly"),
+        route("GET", "/favicon.ico", "Icône du site"),
+        route("GET", "/*", "Fichiers statiques"),
+        route("*", "(404)", "Gestion des erreurs"),
+    ]), "").to_text());
+    println!("🔑 /api/users et /api/files demandent une session (POST /api/auth/login) ou une clé d'API (Authorization: Bearer)");
     println!("=============================");
 }
+
+/// Affiche un bloc de texte (table rendue) en retrait
+fn print_indented(text: &str) {
+    for line in text.lines() {
+        println!("   {}", line);
+    }
+}
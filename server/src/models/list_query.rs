@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use core::repositories::_repository::{Entity, Filter, Page, Paginated, Sort, SortDirection};
+
+/// Paramètres de pagination, de tri et de filtrage d'une liste
+///
+/// `page`, `per_page`, `sort`, `order` et `format` sont réservés ; tout autre paramètre
+/// est un filtre « contient » sur la colonne du même nom.
+#[derive(Clone, Debug)]
+pub struct ListQuery {
+    pub page: Page,
+    pub sort: Sort,
+    pub filters: Vec<(String, String)>,
+    /// Une page a été explicitement demandée (`page` ou `per_page`)
+    pub paged: bool,
+}
+
+impl ListQuery {
+    pub const DEFAULT_PER_PAGE: u32 = 20;
+
+    /// Lit et valide les paramètres pour l'entité `T`
+    pub fn from_params<T: Entity>(params: &HashMap<String, String>) -> Result<Self, String> {
+        let number = parse_number(params, "page")?;
+        let size = parse_number(params, "per_page")?;
+        let paged = number.is_some() || size.is_some();
+
+        let mut sort = match params.get("sort").filter(|s| !s.is_empty()) {
+            Some(column) if T::COLUMNS.contains(&column.as_str()) => Sort::asc(column),
+            Some(column) => return Err(format!("Unknown sort column '{}'", column)),
+            None => T::default_sort(),
+        };
+        match params.get("order").map(String::as_str) {
+            Some("asc") => sort.direction = SortDirection::Asc,
+            Some("desc") => sort.direction = SortDirection::Desc,
+            Some("") | None => {}
+            Some(other) => return Err(format!("Invalid order '{}', expected 'asc' or 'desc'", other)),
+        }
+
+        let mut filters = Vec::new();
+        for (key, value) in params {
+            if matches!(key.as_str(), "page" | "per_page" | "sort" | "order" | "format") || value.is_empty() {
+                continue;
+            }
+            if !T::COLUMNS.contains(&key.as_str()) {
+                return Err(format!("Unknown filter column '{}'", key));
+            }
+            filters.push((key.clone(), value.clone()));
+        }
+        filters.sort();
+
+        Ok(Self {
+            page: Page::new(number.unwrap_or(1), size.unwrap_or(Self::DEFAULT_PER_PAGE)),
+            sort,
+            filters,
+            paged,
+        })
+    }
+
+    /// Filtre SQL correspondant aux paramètres
+    pub fn filter(&self) -> Filter {
+        self.filters.iter().fold(Filter::new(), |filter, (column, text)| filter.contains(column, text))
+    }
+
+    /// Chaîne de requête pour une autre page et/ou un autre tri, filtres conservés
+    pub fn query_string(&self, page: u32, sort: &Sort) -> String {
+        let order = match sort.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        serializer
+            .append_pair("page", &page.to_string())
+            .append_pair("per_page", &self.page.size.to_string())
+            .append_pair("sort", &sort.column)
+            .append_pair("order", order);
+        for (column, text) in &self.filters {
+            serializer.append_pair(column, text);
+        }
+        serializer.finish()
+    }
+
+    /// Barre de navigation : pages précédente/suivante et tri par colonne
+    ///
+    /// Les liens portent leur requête dans `data-query`, reprise par le rafraîchissement client.
+    pub fn pagination_html<T: Entity>(&self, result: &Paginated<T>) -> String {
+        let total_pages = result.total_pages().max(1);
+        let mut html = String::from("<nav class=\"pagination\">");
+
+        if result.page > 1 {
+            html.push_str(&self.link(result.page - 1, &self.sort, "« Précédent"));
+        }
+        html.push_str(&format!(
+            "<span class=\"page-info\">Page {} / {} ({} résultats)</span>",
+            result.page, total_pages, result.total
+        ));
+        if result.page < total_pages {
+            html.push_str(&self.link(result.page + 1, &self.sort, "Suivant »"));
+        }
+
+        html.push_str("<span class=\"sort-links\">");
+        for column in T::COLUMNS {
+            let (sort, label) = if self.sort.column == *column && self.sort.direction == SortDirection::Asc {
+                (Sort::desc(column), format!("{} ▲", column))
+            } else if self.sort.column == *column {
+                (Sort::asc(column), format!("{} ▼", column))
+            } else {
+                (Sort::asc(column), column.to_string())
+            };
+            html.push_str(&self.link(1, &sort, &label));
+        }
+        html.push_str("</span></nav>");
+        html
+    }
+
+    fn link(&self, page: u32, sort: &Sort, label: &str) -> String {
+        let query = self.query_string(page, sort).replace('&', "&amp;");
+        format!("<a href=\"?{0}\" data-query=\"{0}\">{1}</a>", query, label)
+    }
+}
+
+fn parse_number(params: &HashMap<String, String>, name: &str) -> Result<Option<u32>, String> {
+    match params.get(name).filter(|s| !s.is_empty()) {
+        Some(value) => value.parse().map(Some).map_err(|_| format!("Invalid {} '{}'", name, value)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::repositories::User;
+
+    fn query(pairs: &[(&str, &str)]) -> Result<ListQuery, String> {
+        let params = pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        ListQuery::from_params::<User>(&params)
+    }
+
+    #[test]
+    fn rejects_unknown_columns_and_invalid_numbers() {
+        assert_eq!(query(&[("sort", "password_hash")]).unwrap_err(), "Unknown sort column 'password_hash'");
+        assert_eq!(query(&[("password_hash", "x")]).unwrap_err(), "Unknown filter column 'password_hash'");
+        assert!(query(&[("order", "up")]).is_err());
+        assert_eq!(query(&[("page", "abc")]).unwrap_err(), "Invalid page 'abc'");
+        assert!(query(&[("per_page", "-1")]).is_err());
+        assert!(query(&[("page", "99999999999")]).is_err());
+
+        // Bornes ramenées dans l'intervalle valide ; valeurs vides ignorées
+        let bounds = |page: &str, per_page: &str| query(&[("page", page), ("per_page", per_page)]).map(|q| (q.page.number, q.page.size, q.paged));
+        assert_eq!(bounds("0", "0"), Ok((1, 1, true)));
+        assert_eq!(bounds("3", "100000"), Ok((3, Page::MAX_SIZE, true)));
+        assert_eq!(bounds("", ""), Ok((1, ListQuery::DEFAULT_PER_PAGE, false)));
+        assert!(query(&[("login", ""), ("format", "csv")]).unwrap().filters.is_empty());
+    }
+
+    #[test]
+    fn round_trips_through_the_query_string() {
+        let original = query(&[("login", "a&b=c é"), ("age", "3"), ("sort", "login"), ("order", "desc"), ("per_page", "10")]).unwrap();
+        let query_string = original.query_string(4, &original.sort);
+        let params = url::form_urlencoded::parse(query_string.as_bytes()).into_owned().collect();
+        let parsed = ListQuery::from_params::<User>(&params).unwrap();
+
+        assert_eq!((parsed.page.number, parsed.page.size), (4, 10));
+        assert_eq!((parsed.sort.column.as_str(), parsed.sort.direction), ("login", SortDirection::Desc));
+        assert_eq!(parsed.filters, original.filters);
+    }
+
+    #[test]
+    fn escapes_filter_values_in_pagination_links() {
+        let query = query(&[("login", "\"><script>alert(1)</script>")]).unwrap();
+        let result = Paginated { items: Vec::<User>::new(), total: 50, page: 2, per_page: 20 };
+        let html = query.pagination_html(&result);
+
+        assert!(html.contains("« Précédent") && html.contains("Suivant »"));
+        assert!(!html.contains("<script"), "{}", html);
+        assert!(html.contains("login=%22%3E%3Cscript%3Ealert%281%29%3C%2Fscript%3E"), "{}", html);
+        // Chaque attribut reste fermé par son propre guillemet, `&` échappé
+        for attribute in html.split("href=\"?").skip(1) {
+            let value = &attribute[..attribute.find('"').unwrap()];
+            assert!(!value.contains('<') && !value.contains('>') && !value.contains("&p"), "{}", value);
+        }
+    }
+}
@@ -1 +1,3 @@
 pub mod form_response;
+pub mod list_query;
+pub mod user_payload;
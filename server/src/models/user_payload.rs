@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Deserializer};
+use core::repositories::User;
+use uuid::Uuid;
+
+/// Corps de `POST /api/users` et `PUT /api/users/{id}` : représentation complète,
+/// un champ absent est vidé
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct UserInput {
+    pub login: Option<String>,
+    pub birthday: Option<String>,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub sexe: Option<String>,
+    pub age: Option<i32>,
+    pub info: Option<String>,
+    pub email: Option<String>,
+    pub files_info: Option<String>,
+}
+
+/// Corps de `PATCH /api/users/{id}` : seuls les champs présents sont modifiés,
+/// `null` vide le champ
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatch {
+    #[serde(default, deserialize_with = "present")]
+    pub login: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub birthday: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub firstname: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub lastname: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub sexe: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub age: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "present")]
+    pub info: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub files_info: Option<Option<String>>,
+}
+
+/// Distingue un champ absent (`None`) d'un champ `null` (`Some(None)`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl UserInput {
+    /// Nouvel utilisateur
+    pub fn into_user(self) -> User {
+        let mut user = User::from_form_fields(&Default::default(), &Vec::new());
+        self.apply_to(&mut user);
+        user
+    }
+
+    /// Remplace tous les champs modifiables ; l'ID et la date de création sont conservés
+    pub fn apply_to(self, user: &mut User) {
+        user.login = self.login;
+        user.birthday = self.birthday;
+        user.firstname = self.firstname;
+        user.lastname = self.lastname;
+        user.sexe = self.sexe;
+        user.age = self.age;
+        user.info = self.info;
+        user.email = self.email;
+        user.files_info = self.files_info;
+    }
+}
+
+impl UserPatch {
+    pub fn apply_to(self, user: &mut User) {
+        if let Some(login) = self.login { user.login = login; }
+        if let Some(birthday) = self.birthday { user.birthday = birthday; }
+        if let Some(firstname) = self.firstname { user.firstname = firstname; }
+        if let Some(lastname) = self.lastname { user.lastname = lastname; }
+        if let Some(sexe) = self.sexe { user.sexe = sexe; }
+        if let Some(age) = self.age { user.age = age; }
+        if let Some(info) = self.info { user.info = info; }
+        if let Some(email) = self.email { user.email = email; }
+        if let Some(files_info) = self.files_info { user.files_info = files_info; }
+    }
+}
+
+/// Vérifie un utilisateur avant écriture ; les erreurs sont indexées par champ
+pub fn validate(user: &User) -> Result<(), BTreeMap<&'static str, String>> {
+    let mut errors = BTreeMap::new();
+
+    match user.login.as_deref().map(str::trim) {
+        None | Some("") => { errors.insert("login", "Login is required".to_string()); }
+        Some(login) if login.len() > 64 => { errors.insert("login", "Login must be at most 64 characters".to_string()); }
+        Some(login) if Uuid::parse_str(login).is_ok() => {
+            // Un login en forme d'UUID serait confondu avec un ID dans /api/users/{id}
+            errors.insert("login", "Login cannot be a UUID".to_string());
+        }
+        Some(_) => {}
+    }
+
+    if let Some(email) = user.email.as_deref().filter(|e| !e.is_empty()) {
+        let valid = email.split_once('@')
+            .map(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'))
+            .unwrap_or(false);
+        if !valid {
+            errors.insert("email", format!("Invalid email address '{}'", email));
+        }
+    }
+
+    if let Some(age) = user.age {
+        if !(0..=150).contains(&age) {
+            errors.insert("age", "Age must be between 0 and 150".to_string());
+        }
+    }
+
+    if let Some(birthday) = user.birthday.as_deref().filter(|b| !b.is_empty()) {
+        if !is_iso_date(birthday) {
+            errors.insert("birthday", "Birthday must be a date formatted YYYY-MM-DD".to_string());
+        }
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Date `AAAA-MM-JJ` plausible (mois 1-12, jour 1-31)
+fn is_iso_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return false;
+    }
+    match (parts[0].parse::<u32>(), parts[1].parse::<u32>(), parts[2].parse::<u32>()) {
+        (Ok(_), Ok(month), Ok(day)) => (1..=12).contains(&month) && (1..=31).contains(&day),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(login: &str) -> User {
+        UserInput { login: Some(login.to_string()), ..Default::default() }.into_user()
+    }
+
+    fn errors(user: &User) -> Vec<&'static str> {
+        validate(user).err().map(|errors| errors.into_keys().collect()).unwrap_or_default()
+    }
+
+    #[test]
+    fn validates_each_field_at_its_bounds() {
+        assert!(validate(&user("alice")).is_ok());
+        assert!(validate(&user(&"a".repeat(64))).is_ok());
+        assert_eq!(errors(&user(&"a".repeat(65))), vec!["login"]);
+        assert_eq!(errors(&user("   ")), vec!["login"]);
+        assert_eq!(errors(&user(&Uuid::new_v4().to_string())), vec!["login"]);
+
+        for (age, valid) in [(0, true), (150, true), (151, false), (-1, false)] {
+            assert_eq!(validate(&User { age: Some(age), ..user("alice") }).is_ok(), valid, "age {}", age);
+        }
+        for (birthday, valid) in [("2024-02-29", true), ("", true), ("2024-13-01", false), ("2024-1-01", false), ("24-01-01", false), ("2024-01-01T00:00", false), ("01/02/2024", false)] {
+            let dated = User { birthday: Some(birthday.to_string()), ..user("alice") };
+            assert_eq!(validate(&dated).is_ok(), valid, "birthday {}", birthday);
+        }
+        for (email, valid) in [("alice@example.com", true), ("alice@example", false), ("@example.com", false), ("alice@.com", false), ("alice@example.", false)] {
+            assert_eq!(validate(&User { email: Some(email.to_string()), ..user("alice") }).is_ok(), valid, "email {}", email);
+        }
+
+        // Toutes les erreurs sont rapportées ensemble
+        let invalid = User { login: None, age: Some(200), email: Some("nope".to_string()), ..user("alice") };
+        assert_eq!(errors(&invalid), vec!["age", "email", "login"]);
+    }
+
+    #[test]
+    fn patches_distinguish_null_from_absent_fields() {
+        let mut alice = User { firstname: Some("Alice".to_string()), email: Some("alice@example.com".to_string()), ..user("alice") };
+        let patch: UserPatch = serde_json::from_str(r#"{ "email": null, "age": 31 }"#).unwrap();
+        patch.apply_to(&mut alice);
+        assert_eq!((alice.email, alice.age, alice.firstname.as_deref()), (None, Some(31), Some("Alice")));
+        assert!(serde_json::from_str::<UserPatch>(r#"{ "admin": true }"#).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// Négociation du format de réponse des endpoints de table (HTML, JSON, CSV)
+
+use actix_web::http::header::{self, Header};
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use core::repositories::_repository::Paginated;
+use core::table::{cell_text, CsvRenderer};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+
+/// Format de réponse demandé par le client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Html,
+    Json,
+    Csv,
+}
+
+impl ResponseFormat {
+    /// Lit `?format=` (prioritaire) puis l'en-tête `Accept`, par ordre de qualité
+    ///
+    /// `Ok(None)` si le client n'exprime pas de préférence (pas d'en-tête, `*/*`) ;
+    /// `Err` (400 ou 406) si aucun format ne convient.
+    pub fn from_request(req: &HttpRequest) -> Result<Option<Self>, actix_web::Error> {
+        let format = url::form_urlencoded::parse(req.query_string().as_bytes())
+            .find(|(key, _)| key == "format")
+            .map(|(_, value)| value.into_owned());
+
+        if let Some(format) = format {
+            return match format.to_ascii_lowercase().as_str() {
+                "html" => Ok(Some(Self::Html)),
+                "json" => Ok(Some(Self::Json)),
+                "csv" => Ok(Some(Self::Csv)),
+                _ => Err(actix_web::error::ErrorBadRequest(format!(
+                    "Unknown format '{}', expected html, json or csv", format
+                ))),
+            };
+        }
+
+        let accept = match header::Accept::parse(req) {
+            Ok(accept) if !accept.is_empty() => accept,
+            _ => return Ok(None),
+        };
+
+        for mime in accept.ranked() {
+            match (mime.type_().as_str(), mime.subtype().as_str()) {
+                ("*", "*") => return Ok(None),
+                ("text", "html") | ("application", "xhtml+xml") | ("text", "*") => return Ok(Some(Self::Html)),
+                ("application", "json") | ("application", "*") => return Ok(Some(Self::Json)),
+                ("text", "csv") => return Ok(Some(Self::Csv)),
+                _ => {}
+            }
+        }
+
+        Err(actix_web::error::ErrorNotAcceptable("Supported formats: text/html, application/json, text/csv"))
+    }
+}
+
+/// Ligne CSV (RFC 4180) terminée par CRLF
+pub fn csv_record<S: AsRef<str>>(fields: &[S]) -> String {
+    CsvRenderer::default().record(fields)
+}
+
+/// Réponse CSV diffusée page par page : seule une page est en mémoire à la fois
+///
+/// `fetch_page` est appelée avec les numéros de page successifs à partir de `first_page` ;
+/// avec `single_page`, seule cette page est exportée. La diffusion s'arrête sur une page incomplète.
+pub fn stream_csv<T, F, Fut>(
+    columns: &'static [&'static str],
+    filename: &str,
+    first_page: u32,
+    single_page: bool,
+    fetch_page: F,
+) -> HttpResponse
+where
+    T: Serialize + 'static,
+    F: Fn(u32) -> Fut + 'static,
+    Fut: Future<Output = anyhow::Result<Paginated<T>>> + 'static,
+{
+    let header_line = stream::once(async move { Ok::<_, actix_web::Error>(Bytes::from(csv_record(columns))) });
+
+    let rows = stream::unfold((Some(first_page), fetch_page), move |(page, fetch_page)| async move {
+        let number = page?;
+        match fetch_page(number).await {
+            Ok(result) => {
+                let complete = result.items.len() as u32 >= result.per_page;
+                let next = (complete && !single_page).then_some(number + 1);
+                let body: String = result.items.iter()
+                    .map(|item| {
+                        let value = serde_json::to_value(item).unwrap_or(Value::Null);
+                        let cells: Vec<String> = columns.iter().map(|c| value.get(*c).map(cell_text).unwrap_or_default()).collect();
+                        csv_record(&cells)
+                    })
+                    .collect();
+                Some((Ok(Bytes::from(body)), (next, fetch_page)))
+            }
+            Err(e) => {
+                // La réponse est déjà commencée : on ne peut qu'interrompre le flux
+                println!("Database error while streaming CSV: {}", e);
+                Some((Err(actix_web::error::ErrorInternalServerError(e.to_string())), (None, fetch_page)))
+            }
+        }
+    });
+
+    HttpResponse::build(StatusCode::OK)
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .streaming(header_line.chain(rows).boxed_local())
+}
+
+/// Ajoute à une réponse les en-têtes de pagination
+pub fn with_pagination_headers<T>(mut response: HttpResponse, result: &Paginated<T>) -> HttpResponse {
+    let headers = [
+        ("x-total-count", result.total.to_string()),
+        ("x-page", result.page.to_string()),
+        ("x-per-page", result.per_page.to_string()),
+        ("x-total-pages", result.total_pages().to_string()),
+    ];
+    for (name, value) in headers {
+        if let Ok(value) = header::HeaderValue::from_str(&value) {
+            response.headers_mut().insert(header::HeaderName::from_static(name), value);
+        }
+    }
+    response
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+    use actix_web::test::TestRequest;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn negotiate(uri: &str, accept: Option<&str>) -> Result<Option<ResponseFormat>, StatusCode> {
+        let mut request = TestRequest::get().uri(uri);
+        if let Some(accept) = accept {
+            request = request.insert_header((header::ACCEPT, accept));
+        }
+        ResponseFormat::from_request(&request.to_http_request()).map_err(|e| e.as_response_error().status_code())
+    }
+
+    #[test]
+    fn ranks_accept_and_lets_the_format_parameter_win() {
+        use ResponseFormat::*;
+        assert_eq!(negotiate("/t", None), Ok(None));
+        assert_eq!(negotiate("/t", Some("*/*")), Ok(None));
+        assert_eq!(negotiate("/t", Some("text/csv;q=0.5, application/json;q=0.9")), Ok(Some(Json)));
+        assert_eq!(negotiate("/t", Some("application/json;q=0.2, text/csv")), Ok(Some(Csv)));
+        assert_eq!(negotiate("/t", Some("image/png, text/html;q=0.1")), Ok(Some(Html)));
+        assert_eq!(negotiate("/t", Some("image/png, application/xml")), Err(StatusCode::NOT_ACCEPTABLE));
+
+        assert_eq!(negotiate("/t?format=csv", Some("application/json")), Ok(Some(Csv)));
+        assert_eq!(negotiate("/t?page=2&format=JSON", Some("image/png")), Ok(Some(Json)));
+        assert_eq!(negotiate("/t?format=xml", Some("application/json")), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[derive(Serialize)]
+    struct Row {
+        name: String,
+        note: Option<String>,
+    }
+
+    /// Pages de deux lignes, la dernière incomplète ; les numéros demandés sont notés
+    fn fetch(requested: Rc<RefCell<Vec<u32>>>) -> impl Fn(u32) -> std::future::Ready<anyhow::Result<Paginated<Row>>> {
+        move |page| {
+            requested.borrow_mut().push(page);
+            let names: &[&str] = match page {
+                1 => &["Doe, \"Jo\"", "multi\nline"],
+                2 => &["last"],
+                _ => &[],
+            };
+            let items = names.iter().map(|name| Row { name: name.to_string(), note: None }).collect();
+            std::future::ready(Ok(Paginated { items, total: 3, page, per_page: 2 }))
+        }
+    }
+
+    async fn body(response: HttpResponse) -> String {
+        String::from_utf8(actix_web::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn streams_csv_until_the_last_page() {
+        block_on(async {
+            let requested = Rc::new(RefCell::new(Vec::new()));
+            let response = stream_csv(&["name", "note"], "rows.csv", 1, false, fetch(requested.clone()));
+            assert_eq!(response.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"rows.csv\"");
+            assert_eq!(
+                body(response).await,
+                "name,note\r\n\"Doe, \"\"Jo\"\"\",\r\n\"multi\nline\",\r\nlast,\r\n",
+            );
+            // La page incomplète est la dernière demandée
+            assert_eq!(*requested.borrow(), vec![1, 2]);
+
+            let requested = Rc::new(RefCell::new(Vec::new()));
+            let response = stream_csv(&["name"], "rows.csv", 2, true, fetch(requested.clone()));
+            assert_eq!(body(response).await, "name\r\nlast\r\n");
+            assert_eq!(*requested.borrow(), vec![2]);
+        });
+    }
+
+    #[test]
+    fn adds_pagination_headers() {
+        let result = Paginated { items: Vec::<Row>::new(), total: 45, page: 2, per_page: 20 };
+        let response = with_pagination_headers(HttpResponse::Ok().finish(), &result);
+        let value = |name: &str| response.headers().get(name).unwrap().to_str().unwrap().to_string();
+        assert_eq!(
+            [value("x-total-count"), value("x-page"), value("x-per-page"), value("x-total-pages")],
+            ["45", "2", "20", "3"],
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use core::config::{RateLimit, RateLimitConfig};
+use core::repositories::{RateBucket, RateLimitRepository};
+use core::{HttpSendResponse, _database::DatabaseQuery};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use time::OffsetDateTime;
+use crate::auth::ApiKeyIdentity;
+
+/// Seaux gardés en mémoire : au-delà, ceux redevenus pleins sont oubliés, puis les moins
+/// récemment utilisés
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+/// Contrôles entre deux purges des seaux inactifs en base
+const PURGE_EVERY: u64 = 1_000;
+
+/// Groupe de routes, chacun avec son propre débit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RouteGroup {
+    Api,
+    Uploads,
+    Auth,
+}
+
+impl RouteGroup {
+    /// Groupe d'une requête de `/api`
+    pub fn of(method: &Method, path: &str) -> Self {
+        if path == "/api/auth" || path.starts_with("/api/auth/") {
+            RouteGroup::Auth
+        } else if *method == Method::POST && path == "/api/form" {
+            RouteGroup::Uploads
+        } else {
+            RouteGroup::Api
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Api => "api",
+            RouteGroup::Uploads => "uploads",
+            RouteGroup::Auth => "auth",
+        }
+    }
+}
+
+/// Limiteur de débit à seaux de jetons, un seau par groupe de routes et par client
+///
+/// Les seaux sont en mémoire, propres à l'instance, ou en base (`RATE_LIMIT_STORE=database`)
+/// pour que plusieurs instances partagent le même débit. Une base indisponible laisse passer
+/// les requêtes plutôt que de bloquer l'API.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, (RouteGroup, RateBucket)>>,
+    store: Option<RateLimitRepository>,
+    checks: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, db: Option<DatabaseQuery>) -> Self {
+        Self {
+            store: db.filter(|_| config.shared).map(RateLimitRepository::new),
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            checks: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env(db: &DatabaseQuery) -> Self {
+        Self::new(RateLimitConfig::from_env(), Some(db.clone()))
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    pub fn limit(&self, group: RouteGroup) -> RateLimit {
+        match group {
+            RouteGroup::Api => self.config.api,
+            RouteGroup::Uploads => self.config.uploads,
+            RouteGroup::Auth => self.config.auth,
+        }
+    }
+
+    /// Consomme un jeton du seau de `client` ; `Some(secondes)` à attendre s'il est vide
+    pub async fn check(&self, group: RouteGroup, client: &str, now_ms: i64) -> Option<u64> {
+        let limit = self.limit(group);
+        let (capacity, per_second) = (limit.capacity as f64, limit.per_second());
+        let bucket = format!("{}:{}", group.as_str(), client);
+
+        let wait = match &self.store {
+            Some(store) => {
+                if self.checks.fetch_add(1, Ordering::Relaxed).is_multiple_of(PURGE_EVERY) {
+                    // Un seau inchangé depuis la plus longue période est plein : inutile de le garder
+                    let longest = [self.config.api, self.config.uploads, self.config.auth].iter().map(|l| l.period).max().unwrap_or(0);
+                    if let Err(e) = store.purge_idle(now_ms - longest as i64 * 1000).await {
+                        println!("Database error while purging rate limits: {}", e);
+                    }
+                }
+                store.take(&bucket, capacity, per_second, now_ms).await.unwrap_or_else(|e| {
+                    println!("Rate limit store error: {}", e);
+                    None
+                })
+            },
+            None => self.take_in_memory(group, bucket, now_ms),
+        };
+        wait.map(|seconds| seconds.ceil().max(1.0) as u64)
+    }
+
+    /// Attente avant le prochain jeton du seau de `client`, sans en consommer ; `None` s'il en reste
+    pub async fn pending(&self, group: RouteGroup, client: &str, now_ms: i64) -> Option<u64> {
+        let per_second = self.limit(group).per_second();
+        let bucket = format!("{}:{}", group.as_str(), client);
+        let state = match &self.store {
+            Some(store) => store.find(&bucket).await.unwrap_or_else(|e| {
+                println!("Rate limit store error: {}", e);
+                None
+            }),
+            None => self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&bucket).map(|(_, state)| state.clone()),
+        };
+        state.and_then(|state| state.wait_at(per_second, now_ms)).map(|seconds| seconds.ceil().max(1.0) as u64)
+    }
+
+    fn take_in_memory(&self, group: RouteGroup, bucket: String, now_ms: i64) -> Option<f64> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_MEMORY_BUCKETS && !buckets.contains_key(&bucket) {
+            buckets.retain(|_, (group, state)| {
+                let limit = self.limit(*group);
+                !state.is_full_at(limit.capacity as f64, limit.per_second(), now_ms)
+            });
+            if buckets.len() >= MAX_MEMORY_BUCKETS {
+                // Trop de clients actifs : le dixième le moins récemment servi cède sa place
+                let mut ages: Vec<(i64, String)> = buckets.iter().map(|(key, (_, state))| (state.updated_ms, key.clone())).collect();
+                let evicted = buckets.len() - MAX_MEMORY_BUCKETS + MAX_MEMORY_BUCKETS / 10;
+                ages.select_nth_unstable(evicted - 1);
+                for (_, key) in &ages[..evicted] {
+                    buckets.remove(key);
+                }
+            }
+        }
+        let limit = self.limit(group);
+        let (capacity, per_second) = (limit.capacity as f64, limit.per_second());
+        let (_, state) = buckets.entry(bucket).or_insert_with_key(|key| (group, RateBucket::full(key, capacity, now_ms)));
+        state.take(capacity, per_second, now_ms).err()
+    }
+}
+
+/// Adresse IP du client ; le proxy n'est cru (`X-Forwarded-For`) qu'avec `RATE_LIMIT_TRUST_PROXY`
+fn ip_key(req: &ServiceRequest, trust_proxy: bool) -> String {
+    let ip = if trust_proxy {
+        req.connection_info().realip_remote_addr().map(str::to_string)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    };
+    format!("ip:{}", ip.unwrap_or_else(|| "unknown".to_string()))
+}
+
+/// Client de la requête : clé d'API vérifiée par `api_key::authenticate_bearer`, sinon adresse IP
+///
+/// Les sessions comptent pour leur adresse : le cookie n'est vérifié qu'après, par les routes,
+/// et un cookie inventé ne doit pas donner un nouveau seau.
+fn client_key(req: &ServiceRequest, trust_proxy: bool) -> String {
+    if let Some(key) = req.extensions().get::<ApiKeyIdentity>() {
+        return format!("key:{}", key.id);
+    }
+    ip_key(req, trust_proxy)
+}
+
+fn now_ms() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+fn too_many_requests(group: RouteGroup, retry_after: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+        .json(HttpSendResponse {
+            status: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+            message: Some(format!("Too many requests, retry in {} second(s)", retry_after)),
+            data: Some(json!({ "retry_after": retry_after, "group": group.as_str() })),
+        })
+}
+
+/// Avant de chercher une clé d'API en base : 429 si l'adresse a épuisé son seau, notamment
+/// en présentant des clés refusées (voir `record_failed_key`)
+pub async fn before_key_check(req: &ServiceRequest) -> Option<HttpResponse> {
+    let limiter = req.app_data::<web::Data<RateLimiter>>().filter(|limiter| limiter.config.enabled)?;
+    let group = RouteGroup::of(req.method(), req.path());
+    let retry_after = limiter.pending(group, &ip_key(req, limiter.config.trust_proxy), now_ms()).await?;
+    Some(too_many_requests(group, retry_after))
+}
+
+/// Clé d'API refusée : un jeton de moins dans le seau de l'adresse IP
+pub async fn record_failed_key(req: &ServiceRequest) {
+    if let Some(limiter) = req.app_data::<web::Data<RateLimiter>>().filter(|limiter| limiter.config.enabled) {
+        let group = RouteGroup::of(req.method(), req.path());
+        limiter.check(group, &ip_key(req, limiter.config.trust_proxy), now_ms()).await;
+    }
+}
+
+/// Middleware de `/api`, placé après `api_key::authenticate_bearer` : au-delà du débit de son
+/// groupe, la requête reçoit 429 avec `Retry-After`
+pub async fn limit(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    let Some(limiter) = req.app_data::<web::Data<RateLimiter>>().cloned().filter(|limiter| limiter.config.enabled) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let group = RouteGroup::of(req.method(), req.path());
+    let client = client_key(&req, limiter.config.trust_proxy);
+
+    if let Some(retry_after) = limiter.check(group, &client, now_ms()).await {
+        return Ok(req.into_response(too_many_requests(group, retry_after)));
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+
+    #[test]
+    fn limits_each_group_and_client_separately() {
+        assert_eq!(RouteGroup::of(&Method::POST, "/api/auth/login"), RouteGroup::Auth);
+        assert_eq!(RouteGroup::of(&Method::POST, "/api/form"), RouteGroup::Uploads);
+        assert_eq!(RouteGroup::of(&Method::GET, "/api/form_data"), RouteGroup::Api);
+        assert_eq!(RouteGroup::of(&Method::GET, "/api/authors"), RouteGroup::Api);
+
+        let limiter = RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            api: RateLimit { capacity: 3, period: 3 },
+            uploads: RateLimit { capacity: 1, period: 60 },
+            auth: RateLimit { capacity: 2, period: 10 },
+            shared: false,
+            trust_proxy: false,
+        }, None);
+
+        block_on(async {
+            // 2 requêtes d'affilée, puis 1 toutes les 5 secondes
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.1", 0).await, None);
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.1", 0).await, None);
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.1", 1_000).await, Some(4));
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.1", 4_999).await, Some(1));
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.1", 5_100).await, None);
+
+            // Autre client, autre groupe : autres seaux
+            assert_eq!(limiter.check(RouteGroup::Auth, "ip:10.0.0.2", 5_100).await, None);
+            assert_eq!(limiter.check(RouteGroup::Api, "ip:10.0.0.1", 5_100).await, None);
+            assert_eq!(limiter.check(RouteGroup::Uploads, "user:alice", 5_100).await, None);
+            assert_eq!(limiter.check(RouteGroup::Uploads, "user:alice", 5_100).await, Some(60));
+        });
+    }
+
+    #[test]
+    fn counts_refused_api_keys_against_the_address() {
+        use actix_web::test::{call_service, init_service, TestRequest};
+        use actix_web::{middleware, App};
+
+        block_on(async {
+            let db = crate::test_support::database().await;
+            let rate = RateLimit { capacity: 2, period: 3600 };
+            let limiter = RateLimiter::new(RateLimitConfig { enabled: true, api: rate, uploads: rate, auth: rate, shared: false, trust_proxy: false }, None);
+            let app = init_service(App::new()
+                .app_data(web::Data::new(db))
+                .app_data(web::Data::new(limiter))
+                .service(web::scope("/api")
+                    .wrap(middleware::from_fn(limit))
+                    .wrap(middleware::from_fn(crate::auth::api_key::authenticate_bearer))
+                    .route("/ping", web::get().to(HttpResponse::Ok)))).await;
+
+            let bogus = |n: usize| TestRequest::get().uri("/api/ping")
+                .peer_addr("10.0.0.1:5000".parse().unwrap())
+                .insert_header((header::AUTHORIZATION, format!("Bearer ak_bogus{}", n)))
+                .to_request();
+            assert_eq!(call_service(&app, bogus(1)).await.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(call_service(&app, bogus(2)).await.status(), StatusCode::UNAUTHORIZED);
+            let refused = call_service(&app, bogus(3)).await;
+            assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert!(refused.headers().contains_key(header::RETRY_AFTER));
+            // Même adresse sans clé : le seau est celui qu'ont vidé les clés refusées
+            let anonymous = TestRequest::get().uri("/api/ping").peer_addr("10.0.0.1:5000".parse().unwrap()).to_request();
+            assert_eq!(call_service(&app, anonymous).await.status(), StatusCode::TOO_MANY_REQUESTS);
+            let other = TestRequest::get().uri("/api/ping").peer_addr("10.0.0.2:5000".parse().unwrap()).to_request();
+            assert_eq!(call_service(&app, other).await.status(), StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn forgets_the_least_recently_used_buckets() {
+        let limit = RateLimit { capacity: 2, period: 3600 };
+        let limiter = RateLimiter::new(RateLimitConfig { enabled: true, api: limit, uploads: limit, auth: limit, shared: false, trust_proxy: false }, None);
+
+        block_on(async {
+            assert_eq!(limiter.check(RouteGroup::Api, "ip:10.0.0.1", 0).await, None);
+            assert_eq!(limiter.check(RouteGroup::Api, "ip:10.0.0.1", 0).await, None);
+            // Aucun seau n'a le temps de se remplir : la mémoire reste bornée quand même
+            for client in 0..MAX_MEMORY_BUCKETS * 2 {
+                limiter.check(RouteGroup::Api, &format!("ip:{}", client), 1 + client as i64).await;
+                if client % 1_000 == 0 {
+                    // Un seau vide qui continue de servir n'est pas oublié
+                    assert!(limiter.check(RouteGroup::Api, "ip:10.0.0.1", 1 + client as i64).await.is_some());
+                }
+                assert!(limiter.buckets.lock().unwrap().len() <= MAX_MEMORY_BUCKETS);
+            }
+        });
+    }
+}
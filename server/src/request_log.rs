@@ -0,0 +1,197 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest};
+use core::repositories::{Log, LogLevel, LogRepository};
+use core::_database::DatabaseQuery;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use crate::auth::{ApiKeyIdentity, AuthenticatedUser};
+
+/// Journaux en attente d'écriture ; au-delà, les suivants sont abandonnés et comptés
+pub const QUEUE_CAPACITY: usize = 1024;
+/// Journaux écrits par requête SQL
+const BATCH_SIZE: usize = 100;
+/// Attente maximale avant d'écrire un lot incomplet
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Type des journaux de requêtes dans la table `logs`
+pub const LOG_TYPE: &str = "request";
+
+static REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Identifiant de la requête, repris de `X-Request-Id` ou tiré au hasard ;
+/// placé dans les extensions et renvoyé dans la réponse
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Garde l'identifiant d'un proxy s'il est raisonnable, pour suivre la requête d'un bout à l'autre
+    fn from_header(value: Option<&HeaderValue>) -> Self {
+        let incoming = value.and_then(|v| v.to_str().ok()).map(str::trim).filter(|id| {
+            !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        });
+        RequestId(incoming.map(str::to_string).unwrap_or_else(|| Uuid::new_v4().to_string()))
+    }
+}
+
+/// File bornée des journaux de requêtes, vidée par lots dans `LogRepository` par une tâche de fond
+///
+/// Le middleware n'attend jamais la base : si la file est pleine, le journal est abandonné et
+/// compté ; le nombre d'abandons est lui-même journalisé au lot suivant.
+#[derive(Clone)]
+pub struct RequestLogger {
+    sender: mpsc::Sender<Log>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl RequestLogger {
+    /// File et tâche d'écriture ; la tâche s'arrête, après un dernier lot, quand toutes
+    /// les copies du `RequestLogger` ont disparu
+    pub fn start(db: DatabaseQuery, capacity: usize) -> (Self, actix_web::rt::task::JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer = actix_web::rt::spawn(write_batches(LogRepository::new(db), receiver, dropped.clone()));
+        (Self { sender, dropped }, writer)
+    }
+
+    /// Met le journal en file sans attendre, ou compte son abandon si la file est pleine
+    pub fn record(&self, log: Log) {
+        if self.sender.try_send(log).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+async fn write_batches(logs: LogRepository, mut receiver: mpsc::Receiver<Log>, dropped: Arc<AtomicU64>) {
+    let mut reported = 0;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    // Un premier journal, puis ceux qui arrivent avant le délai ou jusqu'à remplir le lot
+    while let Some(log) = receiver.recv().await {
+        batch.push(log);
+        let deadline = actix_web::rt::time::sleep(FLUSH_INTERVAL);
+        tokio::pin!(deadline);
+        while batch.len() < BATCH_SIZE {
+            tokio::select! {
+                log = receiver.recv() => match log {
+                    Some(log) => batch.push(log),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            }
+        }
+
+        let total = dropped.load(Ordering::Relaxed);
+        if total > reported {
+            let message = format!("{} request log(s) dropped, queue full ({} since startup)", total - reported, total);
+            batch.push(Log::warn(LOG_TYPE, &message, None));
+            reported = total;
+        }
+        if let Err(e) = logs.create_logs(&batch).await {
+            println!("Database error while writing {} request log(s): {}", batch.len(), e);
+        }
+        batch.clear();
+    }
+}
+
+/// Journal d'une requête : niveau d'après le statut, détails en JSON dans `context`
+///
+/// Seul le chemin est gardé : la query string peut porter des secrets (signature d'un lien partagé).
+/// Clé d'API et utilisateur sont ceux laissés dans les extensions par l'authentification.
+pub fn request_log(req: &HttpRequest, status: u16, latency: Duration, request_id: &str) -> Log {
+    let header = |name: header::HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let (api_key_id, user_id) = {
+        let extensions = req.extensions();
+        let api_key = extensions.get::<ApiKeyIdentity>();
+        let user_id = extensions.get::<AuthenticatedUser>().map(|auth| auth.user.id).or(api_key.map(|key| key.user_id));
+        (api_key.map(|key| key.id), user_id)
+    };
+    let level = match status {
+        500.. => LogLevel::Error,
+        400..=499 => LogLevel::Warn,
+        _ => LogLevel::Info,
+    };
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    let context = json!({
+        "method": req.method().as_str(),
+        "path": req.path(),
+        "status": status,
+        "latency_ms": (latency_ms * 1000.0).round() / 1000.0,
+        "ip": req.peer_addr().map(|addr| addr.ip().to_string()),
+        "forwarded_for": header(header::X_FORWARDED_FOR),
+        "user_agent": header(header::USER_AGENT),
+        "request_id": request_id,
+        "api_key_id": api_key_id,
+        "user_id": user_id,
+    });
+    let message = format!("{} {} {} {:.1}ms", req.method(), req.path(), status, latency_ms);
+    Log::new(LOG_TYPE, level, &message, Some(&context.to_string()))
+}
+
+/// Middleware de toute l'application, actif avec `REQUEST_LOGGING=true` : journalise chaque
+/// requête (méthode, chemin, statut, durée, IP, user agent, clé d'API et utilisateur) et
+/// renvoie son `X-Request-Id`
+pub async fn record(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    let Some(logger) = req.app_data::<web::Data<RequestLogger>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let request_id = RequestId::from_header(req.headers().get(&REQUEST_ID));
+    req.extensions_mut().insert(request_id.clone());
+    let started = Instant::now();
+
+    let mut response = next.call(req).await?.map_into_boxed_body();
+    let latency = started.elapsed();
+
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID.clone(), value);
+    }
+    // Les erreurs levées par les handlers sont déjà converties en réponse à ce stade
+    let log = request_log(response.request(), response.status().as_u16(), latency, &request_id.0);
+    logger.record(log);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn logs_requests_without_their_query_string() {
+        let kept = RequestId::from_header(Some(&HeaderValue::from_static("edge-42_a")));
+        assert_eq!(kept.0, "edge-42_a");
+        for invalid in ["", "a b", "<script>", &"x".repeat(65)] {
+            let generated = RequestId::from_header(Some(&HeaderValue::from_str(invalid).unwrap()));
+            assert!(Uuid::parse_str(&generated.0).is_ok(), "{} kept", invalid);
+        }
+
+        let req = TestRequest::get()
+            .uri("/api/shared/42?expires=1&signature=secret")
+            .insert_header((header::USER_AGENT, "curl/8"))
+            .peer_addr("10.0.0.1:5000".parse().unwrap())
+            .to_http_request();
+        let log = request_log(&req, 404, Duration::from_micros(2_500), "edge-42");
+        assert_eq!((log.r#type.as_str(), log.level), (LOG_TYPE, LogLevel::Warn.as_i32()));
+        assert_eq!(log.message, "GET /api/shared/42 404 2.5ms");
+        let context: serde_json::Value = serde_json::from_str(log.context.as_deref().unwrap()).unwrap();
+        assert_eq!(context["ip"], "10.0.0.1");
+        assert_eq!(context["user_agent"], "curl/8");
+        assert_eq!(context["request_id"], "edge-42");
+        assert_eq!((&context["api_key_id"], &context["user_id"]), (&serde_json::Value::Null, &serde_json::Value::Null));
+        assert!(!log.context.unwrap().contains("secret"));
+
+        // Requête authentifiée par clé d'API pendant le traitement
+        let key = ApiKeyIdentity { id: Uuid::new_v4(), user_id: Uuid::new_v4(), name: "ci".to_string(), prefix: "ak_1234".to_string() };
+        req.extensions_mut().insert(key.clone());
+        let context: serde_json::Value = serde_json::from_str(request_log(&req, 200, Duration::ZERO, "id").context.as_deref().unwrap()).unwrap();
+        assert_eq!(context["api_key_id"], key.id.to_string());
+        assert_eq!(context["user_id"], key.user_id.to_string());
+
+        assert_eq!(request_log(&req, 503, Duration::ZERO, "id").level, LogLevel::Error.as_i32());
+        assert_eq!(request_log(&req, 302, Duration::ZERO, "id").level, LogLevel::Info.as_i32());
+    }
+}
@@ -7,15 +7,23 @@ use actix_web::http::header;
 use dotenv::dotenv;
 use std::env;
 use actix_cors::Cors;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use core::_database::DatabaseQuery;
-use serde_json;
+use std::time::Duration;
 
 // Import des contrôleurs - ils doivent être accessibles depuis ce module
 use crate::controllers::ping_controller;
 use crate::controllers::index_controller;
+use crate::controllers::users_controller;
+use crate::controllers::files_controller;
 use crate::controllers::weather_controller;
+use crate::controllers::auth_controller;
+use crate::controllers::totp_controller;
+use crate::auth::{self, Authorize, OidcClient, Permission, SessionManager};
+use crate::csrf::{self, CsrfGuard};
+use crate::rate_limit::{self, RateLimiter};
+use crate::request_log::{self, RequestLogger};
+use crate::signed_links::{self, LinkSigner};
 use crate::ssl_config::SslConfig;
+use crate::storage::ContentStore;
 
 #[derive(Clone)]
 pub struct WebServerConfig {
@@ -128,9 +136,9 @@ pub async fn start_full_web_server() -> std::io::Result<()> {
             pool
         },
         Err(e) => {
-            println!("❌ FATAL ERROR: Could not connect to database: {}", e);
+            println!("❌ FATAL ERROR: Could not initialize database: {}", e);
             println!("❌ Database connection is REQUIRED for server startup");
-            println!("❌ Please check your DATABASE_URL and ensure the database is accessible");
+            println!("❌ Please check your DATABASE_URL and the migration status (`server migrate status`)");
             return Err(std::io::Error::new(
                 std::io::ErrorKind::ConnectionRefused,
                 format!("Database connection failed: {}", e)
@@ -138,6 +146,84 @@ pub async fn start_full_web_server() -> std::io::Result<()> {
         }
     };
 
+    // Limites d'upload (taille, extensions, dossier), partagées par tous les workers
+    let upload_config = web::Data::new(core::config::UploadConfig::from_env());
+
+    // Stockage des contenus, créé une fois : le backend mémoire doit être le même pour tous les workers
+    let content_store = match core::config::StorageConfig::from_env()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+        .and_then(|storage| ContentStore::from_config(&storage, &upload_config))
+    {
+        Ok(store) => web::Data::new(store),
+        Err(e) => {
+            eprintln!("❌ Storage configuration error: {}", e);
+            return Err(e);
+        }
+    };
+    println!("📦 Storage backend: {}", content_store.backend());
+
+    // Liens signés : désactivés tant que LINK_SECRET n'est pas configuré
+    let link_signer = match LinkSigner::from_env() {
+        Ok(signer) => {
+            println!("🔗 Signed links: enabled (max {}s)", signer.config().max_ttl);
+            Some(web::Data::new(signer))
+        },
+        Err(e) => {
+            println!("🔗 Signed links: disabled ({})", e);
+            None
+        }
+    };
+
+    // Sessions des comptes locaux, en base derrière un cookie HttpOnly
+    let session_manager = web::Data::new(SessionManager::from_env());
+    println!("🔑 Sessions: cookie '{}', {}s", session_manager.config().cookie_name, session_manager.config().ttl);
+    let totp_config = web::Data::new(core::config::TotpConfig::from_env());
+
+    // Jetons CSRF des routes qui modifient l'état, signés par une clé commune à tous les workers
+    let csrf_guard = web::Data::new(CsrfGuard::from_env());
+    match (csrf_guard.config().enabled, csrf_guard.config().secret.is_some()) {
+        (false, _) => println!("🛡️ CSRF protection: disabled (CSRF_PROTECTION=false)"),
+        (true, true) => println!("🛡️ CSRF protection: header {}", csrf_guard.config().header_name),
+        (true, false) => println!("🛡️ CSRF protection: header {} (ephemeral key, set CSRF_SECRET to share it between instances)", csrf_guard.config().header_name),
+    }
+
+    // Limitation du débit : seaux en mémoire communs aux workers, ou en base entre instances
+    let rate_limiter = web::Data::new(RateLimiter::from_env(&db_pool));
+    {
+        let limits = rate_limiter.config();
+        if limits.enabled {
+            println!(
+                "🚦 Rate limits: api {}/{}s, uploads {}/{}s, auth {}/{}s ({})",
+                limits.api.capacity, limits.api.period, limits.uploads.capacity, limits.uploads.period,
+                limits.auth.capacity, limits.auth.period, if limits.shared { "database" } else { "memory" }
+            );
+        } else {
+            println!("🚦 Rate limits: disabled (RATE_LIMIT_ENABLED=false)");
+        }
+    }
+
+    // Journal des requêtes dans la table `logs`, écrit par lots hors du traitement des requêtes
+    let (request_logger, log_writer) = if config.request_logging {
+        let (logger, writer) = RequestLogger::start(db_pool.clone(), request_log::QUEUE_CAPACITY);
+        println!("📝 Request logging: logs table, queue of {} entries", request_log::QUEUE_CAPACITY);
+        (Some(web::Data::new(logger)), Some(writer))
+    } else {
+        println!("📝 Request logging: disabled (REQUEST_LOGGING=false)");
+        (None, None)
+    };
+
+    // Connexion OIDC : désactivée tant que OIDC_ISSUER n'est pas configuré
+    let oidc_client = match OidcClient::from_env() {
+        Ok(client) => {
+            println!("🪪 OIDC login: {} (client {})", client.config().issuer, client.config().client_id);
+            Some(web::Data::new(client))
+        },
+        Err(e) => {
+            println!("🪪 OIDC login: disabled ({})", e);
+            None
+        }
+    };
+
     // Copier les valeurs nécessaires avant le move
     let host = config.host.clone();
     let port = config.port;
@@ -175,17 +261,83 @@ pub async fn start_full_web_server() -> std::io::Result<()> {
                 "max-age=63072000; includeSubDomains; preload"
             ));
         }
-        let app = App::new()
+        let mut app = App::new()
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(upload_config.clone())
+            .app_data(content_store.clone())
+            .app_data(session_manager.clone())
+            .app_data(csrf_guard.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(totp_config.clone());
+        if let Some(signer) = &link_signer {
+            app = app.app_data(signer.clone());
+        }
+        if let Some(oidc) = &oidc_client {
+            app = app.app_data(oidc.clone());
+        }
+        if let Some(logger) = &request_logger {
+            app = app.app_data(logger.clone());
+        }
+        let app = app
             .wrap(cors)
             .wrap(middleware::Compress::default())
-            .wrap(default_headers);
+            .wrap(default_headers)
+            // En dernier pour mesurer toute la chaîne et voir le statut final
+            .wrap(middleware::from_fn(request_log::record));
         
         app
             // Routes de l'API
             .service(web::scope("/api")
+                // Le dernier `wrap` s'exécute en premier : la clé d'API est reconnue avant
+                // la limitation du débit et le contrôle CSRF ; une clé refusée compte contre
+                // l'adresse IP, qui reçoit 429 avant toute recherche en base une fois à court
+                .wrap(middleware::from_fn(csrf::protect))
+                .wrap(middleware::from_fn(rate_limit::limit))
+                .wrap(middleware::from_fn(auth::api_key::authenticate_bearer))
+                .route("/csrf", web::get().to(csrf::token))
                 .route("/form", web::post().to(index_controller::post))
-                .route("/form_data", web::get().to(index_controller::get_form_data))
+                .route("/form_data", web::get().to(index_controller::get_form_data)
+                    .wrap(Authorize::permission(Permission::USERS_LIST)))
+                .service(web::scope("/auth")
+                    .app_data(users_controller::json_config())
+                    .route("/login", web::post().to(auth_controller::login))
+                    .route("/login/totp", web::post().to(auth_controller::login_totp))
+                    .route("/logout", web::post().to(auth_controller::logout))
+                    .route("/me", web::get().to(auth_controller::me))
+                    .route("/totp/enroll", web::post().to(totp_controller::enroll))
+                    .route("/totp/confirm", web::post().to(totp_controller::confirm))
+                    .route("/totp/disable", web::post().to(totp_controller::disable))
+                    .route("/oidc/login", web::get().to(auth_controller::oidc_login))
+                    .route("/oidc/link", web::get().to(auth_controller::oidc_link))
+                    .route("/oidc/callback", web::get().to(auth_controller::oidc_callback))
+                )
+                .service(web::scope("/users")
+                    .wrap(middleware::from_fn(auth::require_login))
+                    .app_data(users_controller::json_config())
+                    .route("", web::get().to(users_controller::list)
+                        .wrap(Authorize::permission(Permission::USERS_LIST)))
+                    .route("", web::post().to(users_controller::create)
+                        .wrap(Authorize::permission(Permission::USERS_WRITE)))
+                    .route("/{key}", web::get().to(users_controller::get)
+                        .wrap(Authorize::permission(Permission::USERS_READ).or_self("key")))
+                    .route("/{key}", web::put().to(users_controller::replace)
+                        .wrap(Authorize::permission(Permission::USERS_WRITE).or_self("key")))
+                    .route("/{key}", web::patch().to(users_controller::patch)
+                        .wrap(Authorize::permission(Permission::USERS_WRITE).or_self("key")))
+                    .route("/{key}", web::delete().to(users_controller::delete)
+                        .wrap(Authorize::permission(Permission::USERS_DELETE)))
+                )
+                .service(web::scope("/files")
+                    .wrap(middleware::from_fn(auth::require_login))
+                    .route("/{id}", web::get().to(files_controller::download))
+                    .route("/{id}", web::delete().to(files_controller::delete))
+                    .route("/{id}/thumbnail", web::get().to(files_controller::thumbnail))
+                    .route("/{id}/links", web::post().to(files_controller::create_link))
+                )
+                .service(web::resource("/shared/{id}")
+                    .wrap(middleware::from_fn(signed_links::require_signed_link))
+                    .route(web::get().to(files_controller::download))
+                )
                 .route("/ping", web::post().to(ping_controller::get))
                 .route("/ping", web::get().to(ping_controller::get))
                 .route("/weather/temperature", web::get().to(weather_controller::get_temperature))
@@ -238,7 +390,14 @@ pub async fn start_full_web_server() -> std::io::Result<()> {
         http_server_instance.bind((host, port))?
     };
     
-    http_server.run().await
+    let result = http_server.run().await;
+    // Les workers arrêtés, la file se ferme : on laisse la tâche écrire le dernier lot
+    if let Some(writer) = log_writer {
+        if actix_web::rt::time::timeout(Duration::from_secs(5), writer).await.is_err() {
+            println!("⚠️ Request logs still queued at shutdown were not written");
+        }
+    }
+    result
 }
 
 fn configure_cors(config: &WebServerConfig) -> Cors {
@@ -252,8 +411,9 @@ fn configure_cors(config: &WebServerConfig) -> Cors {
     } else {
         Cors::default()
             .allowed_origin(&env::var("ALLOWED_ORIGIN").unwrap_or_else(|_| "https://yourdomain.com".to_string()))
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
             .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
+            .allowed_header(env::var("CSRF_HEADER").unwrap_or_else(|_| "X-CSRF-Token".to_string()).as_str())
             .max_age(3600)
     }
 }
@@ -277,9 +437,30 @@ fn print_server_info(config: &WebServerConfig) {
     println!("");    
     println!("🔧 API Endpoints:");
     println!("   • GET/POST /api/ping           - Server health check");
+    println!("   • GET  /api/csrf               - CSRF token to send back in X-CSRF-Token");
     println!("   • POST /api/form               - Form submission");
-    println!("   • GET /api/form_data           - Retrieve form_data table");
+    println!("   • GET /api/form_data           - Retrieve form_data table (users:list)");
+    println!("   • POST /api/auth/login         - Open a session (JSON login/password, cookie)");
+    println!("   • POST /api/auth/login/totp    - Finish a two-factor login (JSON challenge/code)");
+    println!("   • POST /api/auth/logout        - Close the current session");
+    println!("   • GET  /api/auth/me            - User of the current session");
+    println!("   • POST /api/auth/totp/enroll   - Start TOTP enrollment (secret, otpauth URI, SVG QR code)");
+    println!("   • POST /api/auth/totp/confirm|disable - Enable TOTP (returns recovery codes) or remove it");
+    println!("   • GET  /api/auth/oidc/login    - Sign in through the OpenID Connect provider");
+    println!("   • GET  /api/auth/oidc/link     - Link the provider identity to the signed-in account");
+    println!("   • GET  /api/auth/oidc/callback - Return from the provider (opens a session, or completes a link)");
+    println!("   • GET/POST /api/users          - List or create users (JSON, users:list / users:write)");
+    println!("   • GET/PUT/PATCH/DELETE /api/users/{{id|login}} - Single user (JSON, own record or users:read / users:write / users:delete)");
+    println!("   • GET  /api/files/{{id}}        - Download an uploaded file (Range, ETag)");
+    println!("   • DELETE /api/files/{{id}}      - Delete an uploaded file");
+    println!("   • GET  /api/files/{{id}}/thumbnail - Thumbnail of an uploaded image (PNG)");
+    println!("   • POST /api/files/{{id}}/links  - Create a signed, expiring download link");
+    println!("   • GET  /api/shared/{{id}}       - Download through a signed link");
     println!("   • GET /api/weather/temperature - Weather data");
+    println!("   🔑 /api/files only serves the caller's own files, other users' files need files:read / files:delete");
+    println!("   🔑 /api/users and /api/files require a session (/api/auth/login) or an API key (Authorization: Bearer)");
+    println!("   🛡️ POST/PUT/PATCH/DELETE under /api need the CSRF token, except with an API key");
+    println!("   🚦 /api is rate limited per API key, user or IP (429 with Retry-After)");
     println!("=====================================");
 }
 
@@ -0,0 +1,273 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use core::config::SignedLinkConfig;
+use core::repositories::LinkNonceRepository;
+use core::{HttpSendResponse, _database::DatabaseQuery};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Lien de téléchargement partagé : fichier, date d'expiration et jeton s'il est à usage unique
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedLink {
+    pub file_id: Uuid,
+    /// Expiration, en secondes depuis l'époque Unix
+    pub expires: i64,
+    pub nonce: Option<String>,
+}
+
+impl SignedLink {
+    pub fn expires_at(&self) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(self.expires).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+    }
+}
+
+/// Refus d'un lien signé
+#[derive(Debug, PartialEq)]
+pub enum LinkError {
+    /// Paramètre absent ou illisible (403)
+    Malformed(&'static str),
+    /// La signature ne correspond pas au lien (403)
+    BadSignature,
+    /// Lien expiré (410)
+    Expired,
+    /// Lien à usage unique déjà utilisé (410)
+    AlreadyUsed,
+    /// Durée demandée au-delà de `SignedLinkConfig::max_ttl` (400)
+    TtlTooLong { max: u64 },
+}
+
+impl LinkError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            LinkError::Malformed(_) | LinkError::BadSignature => StatusCode::FORBIDDEN,
+            LinkError::Expired | LinkError::AlreadyUsed => StatusCode::GONE,
+            LinkError::TtlTooLong { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::Malformed(reason) => write!(f, "Invalid link: {}", reason),
+            LinkError::BadSignature => write!(f, "Invalid link signature"),
+            LinkError::Expired => write!(f, "Link has expired"),
+            LinkError::AlreadyUsed => write!(f, "Link has already been used"),
+            LinkError::TtlTooLong { max } => write!(f, "Link lifetime cannot exceed {} seconds", max),
+        }
+    }
+}
+
+/// Signe et vérifie les liens `/api/shared/{id}?expires=…&nonce=…&signature=…`
+///
+/// La signature HMAC-SHA256 couvre l'identifiant du fichier, l'expiration et le jeton :
+/// modifier l'un d'eux invalide le lien.
+pub struct LinkSigner {
+    config: SignedLinkConfig,
+}
+
+impl LinkSigner {
+    pub fn new(config: SignedLinkConfig) -> Self {
+        Self { config }
+    }
+
+    /// Erreur si `LINK_SECRET` n'est pas configuré ou trop court
+    pub fn from_env() -> Result<Self, String> {
+        SignedLinkConfig::from_env().map(Self::new)
+    }
+
+    pub fn config(&self) -> &SignedLinkConfig {
+        &self.config
+    }
+
+    fn mac(&self, file_id: Uuid, expires: i64, nonce: Option<&str>) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.config.secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(format!("v1\n{}\n{}\n{}", file_id, expires, nonce.unwrap_or("")).as_bytes());
+        mac
+    }
+
+    /// Nouveau lien valable `ttl` secondes (`default_ttl` si `None`)
+    pub fn mint(&self, file_id: Uuid, ttl: Option<u64>, single_use: bool) -> Result<SignedLink, LinkError> {
+        let ttl = ttl.unwrap_or(self.config.default_ttl);
+        if ttl == 0 || ttl > self.config.max_ttl {
+            return Err(LinkError::TtlTooLong { max: self.config.max_ttl });
+        }
+        Ok(SignedLink {
+            file_id,
+            expires: OffsetDateTime::now_utc().unix_timestamp() + ttl as i64,
+            nonce: single_use.then(|| Uuid::new_v4().simple().to_string()),
+        })
+    }
+
+    /// Chemin signé du lien, à préfixer par l'adresse publique du serveur
+    pub fn path(&self, link: &SignedLink) -> String {
+        let signature = hex::encode(self.mac(link.file_id, link.expires, link.nonce.as_deref()).finalize().into_bytes());
+        match &link.nonce {
+            Some(nonce) => format!("/api/shared/{}?expires={}&nonce={}&signature={}", link.file_id, link.expires, nonce, signature),
+            None => format!("/api/shared/{}?expires={}&signature={}", link.file_id, link.expires, signature),
+        }
+    }
+
+    /// Vérifie la signature et l'expiration d'un lien ; le jeton à usage unique reste à consommer
+    pub fn verify(&self, file_id: &str, query: &str, now: i64) -> Result<SignedLink, LinkError> {
+        let file_id = Uuid::parse_str(file_id).map_err(|_| LinkError::Malformed("unknown file"))?;
+        let (mut expires, mut nonce, mut signature) = (None, None, None);
+        for (name, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match name.as_ref() {
+                "expires" => expires = Some(value.into_owned()),
+                "nonce" => nonce = Some(value.into_owned()),
+                "signature" => signature = Some(value.into_owned()),
+                _ => {},
+            }
+        }
+        let expires: i64 = expires.and_then(|v| v.parse().ok()).ok_or(LinkError::Malformed("missing expiry"))?;
+        let signature = signature.and_then(|v| hex::decode(v).ok()).ok_or(LinkError::Malformed("missing signature"))?;
+
+        // Comparaison en temps constant
+        self.mac(file_id, expires, nonce.as_deref())
+            .verify_slice(&signature)
+            .map_err(|_| LinkError::BadSignature)?;
+        if expires <= now {
+            return Err(LinkError::Expired);
+        }
+        Ok(SignedLink { file_id, expires, nonce })
+    }
+}
+
+fn refusal(status: StatusCode, message: String) -> HttpResponse {
+    HttpResponse::build(status).json(HttpSendResponse {
+        status: status.as_u16(),
+        message: Some(message),
+        data: None,
+    })
+}
+
+fn refuse(req: ServiceRequest, status: StatusCode, message: String) -> ServiceResponse {
+    req.into_response(refusal(status, message))
+}
+
+/// Middleware des routes `/api/shared/{id}` : le lien doit être signé, non expiré,
+/// et son jeton éventuel jamais utilisé ; le `SignedLink` vérifié est placé dans les extensions
+///
+/// Le jeton n'est consommé qu'une fois le téléchargement accordé (200 ou 206) : une requête
+/// conditionnelle (304), une plage invalide (416) ou une erreur le laissent utilisable. Une
+/// plage servie (206) le consomme aussi, le lien ne permet donc pas de reprendre un téléchargement.
+pub async fn require_signed_link(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse, Error> {
+    let Some(signer) = req.app_data::<web::Data<LinkSigner>>().cloned() else {
+        return Ok(refuse(req, StatusCode::SERVICE_UNAVAILABLE, "Signed links are disabled (LINK_SECRET is not set)".to_string()));
+    };
+    let file_id = req.match_info().get("id").unwrap_or_default().to_string();
+    let link = match signer.verify(&file_id, req.query_string(), OffsetDateTime::now_utc().unix_timestamp()) {
+        Ok(link) => link,
+        Err(e) => return Ok(refuse(req, e.status(), e.to_string())),
+    };
+
+    let Some(nonce) = link.nonce.clone() else {
+        req.extensions_mut().insert(link);
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let Some(db) = req.app_data::<web::Data<DatabaseQuery>>().cloned() else {
+        return Ok(refuse(req, StatusCode::INTERNAL_SERVER_ERROR, "Database is not available".to_string()));
+    };
+    let nonces = LinkNonceRepository::new(db.get_ref().clone());
+    match nonces.is_used(&nonce).await {
+        Ok(false) => {},
+        Ok(true) => return Ok(refuse(req, LinkError::AlreadyUsed.status(), LinkError::AlreadyUsed.to_string())),
+        Err(e) => {
+            println!("Database error: {}", e);
+            return Ok(refuse(req, StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)));
+        }
+    }
+
+    let (file_id, expires_at) = (link.file_id, link.expires_at());
+    req.extensions_mut().insert(link);
+    let response = next.call(req).await?.map_into_boxed_body();
+    if !matches!(response.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT) {
+        return Ok(response);
+    }
+    // Le corps n'est pas encore parti : si un téléchargement concurrent a pris le jeton
+    // entre-temps, la réponse est remplacée par un refus
+    match nonces.consume(&nonce, file_id, expires_at).await {
+        Ok(true) => Ok(response),
+        Ok(false) => Ok(response.into_response(refusal(LinkError::AlreadyUsed.status(), LinkError::AlreadyUsed.to_string()))),
+        Err(e) => {
+            println!("Database error: {}", e);
+            Ok(response.into_response(refusal(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+    use actix_web::http::header;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{middleware, App, HttpRequest};
+    use core::repositories::{FileRepository, StoredFile};
+
+    fn signer(max_ttl: u64) -> LinkSigner {
+        LinkSigner::new(SignedLinkConfig { secret: "s".repeat(32), default_ttl: 60, max_ttl })
+    }
+
+    #[test]
+    fn verifies_signature_expiry_and_nonce() {
+        let signer = signer(3600);
+        let file_id = Uuid::new_v4();
+        let link = signer.mint(file_id, None, true).unwrap();
+        let path = signer.path(&link);
+        let (_, query) = path.split_once('?').unwrap();
+        let now = link.expires - 60;
+
+        assert_eq!(signer.verify(&file_id.to_string(), query, now), Ok(link.clone()));
+        assert_eq!(signer.verify(&file_id.to_string(), query, link.expires), Err(LinkError::Expired));
+        // Autre fichier, expiration repoussée ou jeton retiré : la signature ne correspond plus
+        assert_eq!(signer.verify(&Uuid::new_v4().to_string(), query, now), Err(LinkError::BadSignature));
+        let extended = query.replace(&link.expires.to_string(), &(link.expires + 3600).to_string());
+        assert_eq!(signer.verify(&file_id.to_string(), &extended, now), Err(LinkError::BadSignature));
+        let reusable = query.replace(&format!("nonce={}&", link.nonce.as_deref().unwrap()), "");
+        assert_eq!(signer.verify(&file_id.to_string(), &reusable, now), Err(LinkError::BadSignature));
+        // Un autre secret ne reconnaît pas le lien
+        let other = LinkSigner::new(SignedLinkConfig { secret: "t".repeat(32), default_ttl: 60, max_ttl: 3600 });
+        assert_eq!(other.verify(&file_id.to_string(), query, now), Err(LinkError::BadSignature));
+
+        assert_eq!(signer.mint(file_id, Some(3601), false), Err(LinkError::TtlTooLong { max: 3600 }));
+        assert_eq!(signer.verify(&file_id.to_string(), "expires=1", now), Err(LinkError::Malformed("missing signature")));
+    }
+
+    #[test]
+    fn consumes_single_use_links_once_served() {
+        block_on(async {
+            let db = crate::test_support::database().await;
+            let file = StoredFile::new("rapport.pdf", "blobs/ab/12/ab12", 2048, "application/pdf", "ab12");
+            FileRepository::new(db.clone()).create_file(&file).await.unwrap();
+            let signer = web::Data::new(signer(3600));
+            let link = signer.mint(file.id, None, true).unwrap();
+            let path = signer.path(&link);
+            let app = init_service(App::new()
+                .app_data(web::Data::new(db))
+                .app_data(signer)
+                .service(web::resource("/api/shared/{id}")
+                    .wrap(middleware::from_fn(require_signed_link))
+                    .route(web::get().to(|req: HttpRequest| async move {
+                        match req.headers().contains_key(header::IF_NONE_MATCH) {
+                            true => HttpResponse::NotModified().finish(),
+                            false => HttpResponse::Ok().body("content"),
+                        }
+                    })))).await;
+
+            // Une revalidation (304) ne consomme pas le jeton, le téléchargement si
+            let revalidation = TestRequest::get().uri(&path).insert_header((header::IF_NONE_MATCH, "\"etag\""));
+            assert_eq!(call_service(&app, revalidation.to_request()).await.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(call_service(&app, TestRequest::get().uri(&path).to_request()).await.status(), StatusCode::OK);
+            assert_eq!(call_service(&app, TestRequest::get().uri(&path).to_request()).await.status(), StatusCode::GONE);
+        });
+    }
+}
@@ -0,0 +1,173 @@
+use std::io;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use futures::StreamExt;
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+use super::{file_stream, BlobFuture, BlobInfo, BlobStore, ByteStream};
+
+/// Objets rangés sous un dossier du disque local, une clé par fichier
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Chemin d'une clé ; refuse les clés qui sortiraient du dossier racine
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|part| matches!(part, Component::Normal(_))) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid storage key '{}'", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Fichier temporaire voisin de `path` : le renommage final reste sur le même disque
+    fn partial_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.partial", Uuid::new_v4()));
+        path.with_file_name(name)
+    }
+}
+
+/// Ouvre un fichier local, entier ou sur une plage d'octets ; `None` s'il n'existe pas
+pub(crate) async fn read_file(path: &Path, range: Option<Range<u64>>) -> io::Result<Option<ByteStream>> {
+    let file = match File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let length = file.metadata().await?.len();
+    let range = range.unwrap_or(0..length);
+    let end = range.end.min(length);
+    Ok(Some(file_stream(file, range.start, end.saturating_sub(range.start))))
+}
+
+/// Supprime un fichier local ; absent, il n'y a rien à faire
+pub(crate) async fn remove_file(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Parcourt `dir` récursivement et retourne les fichiers trouvés avec leurs métadonnées
+fn walk(dir: &Path, files: &mut Vec<(PathBuf, std::fs::Metadata)>) -> io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let metadata = std::fs::metadata(&path)?;
+        if metadata.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push((path, metadata));
+        }
+    }
+    Ok(())
+}
+
+impl BlobStore for LocalBlobStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn put<'a>(&'a self, key: &'a str, mut data: ByteStream) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            // Écrit à côté puis renomme : un lecteur ne voit jamais un objet incomplet
+            let partial = Self::partial_path(&path);
+            let mut file = File::create(&partial).await?;
+            let mut written = 0;
+            let result = async {
+                while let Some(chunk) = data.next().await {
+                    let chunk = chunk?;
+                    file.write_all(&chunk).await?;
+                    written += chunk.len() as u64;
+                }
+                file.flush().await?;
+                fs::rename(&partial, &path).await
+            }.await;
+
+            if let Err(e) = result {
+                let _ = fs::remove_file(&partial).await;
+                return Err(e);
+            }
+            Ok(written)
+        })
+    }
+
+    fn put_file<'a>(&'a self, key: &'a str, source: &'a Path) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let length = fs::metadata(source).await?.len();
+
+            // Un simple renommage, ou une copie si la source est sur un autre disque
+            if fs::rename(source, &path).await.is_err() {
+                let partial = Self::partial_path(&path);
+                fs::copy(source, &partial).await?;
+                fs::rename(&partial, &path).await?;
+                fs::remove_file(source).await?;
+            }
+            Ok(length)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str, range: Option<Range<u64>>) -> BlobFuture<'a, Option<ByteStream>> {
+        Box::pin(async move { read_file(&self.path(key)?, range).await })
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobInfo>> {
+        Box::pin(async move {
+            match fs::metadata(self.path(key)?).await {
+                Ok(metadata) => Ok(Some(BlobInfo { key: key.to_string(), size: metadata.len(), modified: metadata.modified().ok() })),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move { remove_file(&self.path(key)?).await })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, Vec<BlobInfo>> {
+        Box::pin(async move {
+            // Seul le dossier contenant le préfixe est parcouru
+            let dir = match prefix.rfind('/') {
+                Some(end) => self.path(&prefix[..end])?,
+                None => self.root.clone(),
+            };
+            let root = self.root.clone();
+            let files = tokio::task::spawn_blocking(move || {
+                let mut files = Vec::new();
+                walk(&dir, &mut files).map(|_| files)
+            }).await.map_err(io::Error::other)??;
+
+            Ok(files.into_iter()
+                .filter_map(|(path, metadata)| {
+                    let relative = path.strip_prefix(&root).ok()?;
+                    let key = relative.components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    key.starts_with(prefix).then(|| BlobInfo { key, size: metadata.len(), modified: metadata.modified().ok() })
+                })
+                .collect())
+        })
+    }
+}
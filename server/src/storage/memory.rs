@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use actix_web::web::Bytes;
+use futures::{stream, StreamExt};
+use super::{BlobFuture, BlobInfo, BlobStore, ByteStream};
+
+/// Objets gardés en mémoire, perdus à l'arrêt : pour les tests et les essais
+#[derive(Default)]
+pub struct MemoryBlobStore {
+    objects: Mutex<BTreeMap<String, (Bytes, SystemTime)>>,
+}
+
+impl MemoryBlobStore {
+    fn info(key: &str, content: &Bytes, modified: SystemTime) -> BlobInfo {
+        BlobInfo { key: key.to_string(), size: content.len() as u64, modified: Some(modified) }
+    }
+}
+
+impl BlobStore for MemoryBlobStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn put<'a>(&'a self, key: &'a str, mut data: ByteStream) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let mut content = Vec::new();
+            while let Some(chunk) = data.next().await {
+                content.extend_from_slice(&chunk?);
+            }
+            let length = content.len() as u64;
+            self.objects.lock().unwrap().insert(key.to_string(), (Bytes::from(content), SystemTime::now()));
+            Ok(length)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str, range: Option<Range<u64>>) -> BlobFuture<'a, Option<ByteStream>> {
+        Box::pin(async move {
+            let Some((content, _)) = self.objects.lock().unwrap().get(key).cloned() else {
+                return Ok(None);
+            };
+            let length = content.len() as u64;
+            let range = range.unwrap_or(0..length);
+            let (start, end) = (range.start.min(length) as usize, range.end.min(length) as usize);
+            let chunk: io::Result<Bytes> = Ok(content.slice(start..end.max(start)));
+            Ok(Some(Box::pin(stream::iter([chunk])) as ByteStream))
+        })
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobInfo>> {
+        Box::pin(async move {
+            Ok(self.objects.lock().unwrap().get(key).map(|(content, modified)| Self::info(key, content, *modified)))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            self.objects.lock().unwrap().remove(key);
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, Vec<BlobInfo>> {
+        Box::pin(async move {
+            Ok(self.objects.lock().unwrap()
+                .range(prefix.to_string()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(key, (content, modified))| Self::info(key, content, *modified))
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,355 @@
+pub mod local;
+pub mod memory;
+pub mod s3;
+
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::SystemTime;
+use actix_web::web::Bytes;
+use core::config::{StorageBackend, StorageConfig, UploadConfig};
+use futures::{stream, Stream, StreamExt};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+
+pub use local::LocalBlobStore;
+pub use memory::MemoryBlobStore;
+pub use s3::S3BlobStore;
+
+/// Contenu lu ou écrit par blocs
+pub type ByteStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// Future renvoyée par les méthodes de `BlobStore`
+pub type BlobFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// Taille des blocs lus pendant un envoi
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Métadonnées d'un objet stocké
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlobInfo {
+    pub key: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Stockage d'objets par clé (`blobs/ab/cd/abcd…`), indépendant du support
+///
+/// Les clés sont des chemins relatifs séparés par `/`. Écrire sur une clé existante la remplace.
+pub trait BlobStore: Send + Sync {
+    /// Nom du backend, pour les logs
+    fn name(&self) -> &'static str;
+
+    /// Écrit le flux sous `key` et retourne le nombre d'octets écrits
+    fn put<'a>(&'a self, key: &'a str, data: ByteStream) -> BlobFuture<'a, u64>;
+
+    /// Déplace un fichier local sous `key` ; le fichier source est consommé
+    fn put_file<'a>(&'a self, key: &'a str, path: &'a Path) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let file = File::open(path).await?;
+            let length = file.metadata().await?.len();
+            let written = self.put(key, file_stream(file, 0, length)).await?;
+            fs::remove_file(path).await?;
+            Ok(written)
+        })
+    }
+
+    /// Contenu de `key`, ou seulement la plage d'octets `range` ; `None` si la clé n'existe pas
+    fn get<'a>(&'a self, key: &'a str, range: Option<Range<u64>>) -> BlobFuture<'a, Option<ByteStream>>;
+
+    /// Métadonnées de `key`, `None` si la clé n'existe pas
+    fn head<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobInfo>>;
+
+    /// Supprime `key` ; une clé absente n'est pas une erreur
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()>;
+
+    /// Objets dont la clé commence par `prefix`
+    fn list<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, Vec<BlobInfo>>;
+}
+
+/// Backend choisi par la configuration ; le disque local est rangé sous `UploadConfig::upload_dir`
+pub fn blob_store(storage: &StorageConfig, upload: &UploadConfig) -> io::Result<Arc<dyn BlobStore>> {
+    Ok(match storage.backend {
+        StorageBackend::Local => Arc::new(LocalBlobStore::new(&upload.upload_dir)),
+        StorageBackend::Memory => Arc::new(MemoryBlobStore::default()),
+        StorageBackend::S3 => {
+            let config = storage.s3.clone()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "S3 backend selected without S3 configuration"))?;
+            Arc::new(S3BlobStore::new(config)?)
+        },
+    })
+}
+
+/// Lit `length` octets d'un fichier à partir de `offset`, par blocs
+pub fn file_stream(file: File, offset: u64, length: u64) -> ByteStream {
+    Box::pin(stream::try_unfold((file, offset, length), |(mut file, position, remaining)| async move {
+        if remaining == 0 {
+            return Ok(None);
+        }
+        file.seek(io::SeekFrom::Start(position)).await?;
+        let mut buffer = vec![0; remaining.min(CHUNK_SIZE) as usize];
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "File shrank while being read"));
+        }
+        buffer.truncate(read);
+        Ok(Some((Bytes::from(buffer), (file, position + read as u64, remaining - read as u64))))
+    }))
+}
+
+/// Rassemble un flux en mémoire
+pub async fn collect_stream(mut data: ByteStream) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    while let Some(chunk) = data.next().await {
+        content.extend_from_slice(&chunk?);
+    }
+    Ok(content)
+}
+
+/// Stockage des uploads par contenu, sur le `BlobStore` configuré
+///
+/// Chaque contenu est rangé sous son SHA-256 (`blobs/ab/cd/abcd…`), une seule fois quel que
+/// soit le nombre de fichiers qui y renvoient. Les uploads en cours sont reçus dans un dossier
+/// temporaire local, `tmp/` sous `UploadConfig::upload_dir`, puis envoyés au stockage une fois
+/// l'empreinte connue.
+#[derive(Clone)]
+pub struct ContentStore {
+    blobs: Arc<dyn BlobStore>,
+    spool_dir: PathBuf,
+}
+
+impl ContentStore {
+    pub fn new(blobs: Arc<dyn BlobStore>, upload_dir: impl Into<PathBuf>) -> Self {
+        Self { blobs, spool_dir: upload_dir.into().join("tmp") }
+    }
+
+    /// Stockage décrit par la configuration
+    pub fn from_config(storage: &StorageConfig, upload: &UploadConfig) -> io::Result<Self> {
+        Ok(Self::new(blob_store(storage, upload)?, &upload.upload_dir))
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.blobs.name()
+    }
+
+    /// Clé du contenu d'empreinte `sha256`, répartie sur deux niveaux
+    pub fn blob_key(sha256: &str) -> String {
+        format!("blobs/{}/{}/{}", &sha256[..2], &sha256[2..4], sha256)
+    }
+
+    /// Clé de la miniature PNG de `size` pixels du contenu d'empreinte `sha256`
+    pub fn thumbnail_key(sha256: &str, size: u32) -> String {
+        format!("thumbnails/{}-{}.png", sha256, size)
+    }
+
+    /// Enregistre la miniature d'un contenu
+    pub async fn put_thumbnail(&self, sha256: &str, size: u32, png: Vec<u8>) -> io::Result<()> {
+        let data: ByteStream = Box::pin(stream::iter([Ok(Bytes::from(png))]));
+        self.blobs.put(&Self::thumbnail_key(sha256, size), data).await.map(|_| ())
+    }
+
+    /// Miniature déjà calculée d'un contenu
+    pub async fn thumbnail(&self, sha256: &str, size: u32) -> io::Result<Option<Vec<u8>>> {
+        match self.blobs.get(&Self::thumbnail_key(sha256, size), None).await? {
+            Some(data) => collect_stream(data).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Crée un fichier temporaire pour un upload en cours
+    pub async fn create_temp(&self) -> io::Result<(File, PathBuf)> {
+        fs::create_dir_all(&self.spool_dir).await?;
+        let path = self.spool_dir.join(format!("{}.part", Uuid::new_v4()));
+        let file = fs::OpenOptions::new().write(true).create_new(true).open(&path).await?;
+        Ok((file, path))
+    }
+
+    /// Envoie le fichier temporaire au stockage sous son empreinte ; s'il y est déjà,
+    /// le doublon est supprimé. Retourne la clé du contenu.
+    pub async fn commit(&self, temp: &Path, sha256: &str) -> io::Result<String> {
+        let key = Self::blob_key(sha256);
+        if self.blobs.head(&key).await?.is_some() {
+            fs::remove_file(temp).await?;
+        } else {
+            self.blobs.put_file(&key, temp).await?;
+        }
+        Ok(key)
+    }
+
+    /// Supprime un fichier temporaire ; absent, il n'y a rien à faire
+    pub async fn remove_temp(&self, path: &Path) -> io::Result<()> {
+        local::remove_file(path).await
+    }
+
+    /// Métadonnées d'un fichier enregistré (`StoredFile::stored_path`)
+    pub async fn head(&self, stored_path: &str) -> io::Result<Option<BlobInfo>> {
+        match legacy_path(stored_path) {
+            None => self.blobs.head(stored_path).await,
+            Some(path) => match fs::metadata(path).await {
+                Ok(metadata) => Ok(Some(BlobInfo { key: stored_path.to_string(), size: metadata.len(), modified: metadata.modified().ok() })),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    /// Contenu d'un fichier enregistré, entier ou sur une plage d'octets
+    pub async fn open(&self, stored_path: &str, range: Option<Range<u64>>) -> io::Result<Option<ByteStream>> {
+        match legacy_path(stored_path) {
+            None => self.blobs.get(stored_path, range).await,
+            Some(path) => local::read_file(path, range).await,
+        }
+    }
+
+    /// Efface le contenu d'un fichier enregistré et ses miniatures
+    pub async fn erase(&self, stored_path: &str) -> io::Result<()> {
+        match legacy_path(stored_path) {
+            None => {
+                self.blobs.delete(stored_path).await?;
+                if let Some(sha256) = stored_path.strip_prefix("blobs/").and_then(|key| key.rsplit('/').next()) {
+                    for info in self.blobs.list(&format!("thumbnails/{}-", sha256)).await? {
+                        self.blobs.delete(&info.key).await?;
+                    }
+                }
+                Ok(())
+            },
+            Some(path) => local::remove_file(path).await,
+        }
+    }
+
+    /// Contenus présents dans le stockage : empreinte et métadonnées
+    pub async fn blobs(&self) -> io::Result<Vec<(String, BlobInfo)>> {
+        Ok(self.blobs.list("blobs/").await?
+            .into_iter()
+            .filter_map(|info| info.key.rsplit('/').next().map(str::to_string).map(|sha256| (sha256, info)))
+            .collect())
+    }
+
+    /// Miniatures présentes dans le stockage : empreinte du contenu et métadonnées
+    pub async fn thumbnails(&self) -> io::Result<Vec<(String, BlobInfo)>> {
+        Ok(self.blobs.list("thumbnails/").await?
+            .into_iter()
+            .filter_map(|info| {
+                let name = info.key.rsplit('/').next()?;
+                name.split_once('-').map(|(sha256, _)| (sha256.to_string(), info.clone()))
+            })
+            .collect())
+    }
+
+    /// Uploads temporaires, terminés ou abandonnés
+    pub fn temp_files(&self) -> io::Result<Vec<(PathBuf, std::fs::Metadata)>> {
+        let entries = match std::fs::read_dir(&self.spool_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        entries
+            .map(|entry| {
+                let path = entry?.path();
+                std::fs::metadata(&path).map(|metadata| (path, metadata))
+            })
+            .collect()
+    }
+}
+
+/// Chemin disque des fichiers enregistrés avant le stockage par clé, `None` pour une clé
+fn legacy_path(stored_path: &str) -> Option<&Path> {
+    (!stored_path.starts_with("blobs/") && !stored_path.starts_with("thumbnails/")).then(|| Path::new(stored_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+
+    fn bytes(parts: &[&'static str]) -> ByteStream {
+        Box::pin(stream::iter(parts.iter().map(|part| Ok(Bytes::from_static(part.as_bytes()))).collect::<Vec<_>>()))
+    }
+
+    async fn read(store: &dyn BlobStore, key: &str, range: Option<Range<u64>>) -> Option<String> {
+        let data = store.get(key, range).await.unwrap()?;
+        Some(String::from_utf8(collect_stream(data).await.unwrap()).unwrap())
+    }
+
+    /// Comportement commun attendu de chaque backend
+    async fn check_backend(store: &dyn BlobStore) {
+        assert_eq!(store.put("blobs/ab/cd/abcd", bytes(&["hello ", "world"])).await.unwrap(), 11);
+        store.put("blobs/ef/01/ef01", bytes(&["x"])).await.unwrap();
+        store.put("other/key", bytes(&["y"])).await.unwrap();
+
+        assert_eq!(read(store, "blobs/ab/cd/abcd", None).await.as_deref(), Some("hello world"));
+        assert_eq!(read(store, "blobs/ab/cd/abcd", Some(6..11)).await.as_deref(), Some("world"));
+        assert_eq!(read(store, "missing", None).await, None);
+        assert_eq!(store.head("blobs/ab/cd/abcd").await.unwrap().map(|info| info.size), Some(11));
+
+        let mut keys: Vec<String> = store.list("blobs/").await.unwrap().into_iter().map(|info| info.key).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["blobs/ab/cd/abcd", "blobs/ef/01/ef01"]);
+
+        store.delete("blobs/ab/cd/abcd").await.unwrap();
+        store.delete("blobs/ab/cd/abcd").await.unwrap();
+        assert!(store.head("blobs/ab/cd/abcd").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn memory_and_local_backends_behave_alike() {
+        block_on(check_backend(&MemoryBlobStore::default()));
+
+        let root = std::env::temp_dir().join(format!("blob-store-{}", Uuid::new_v4()));
+        block_on(check_backend(&LocalBlobStore::new(&root)));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Contre un stockage S3 réel ou simulé (MinIO, moto…) dont le bucket `blob-store-test` existe :
+    /// `S3_TEST_ENDPOINT=http://127.0.0.1:9000 cargo test -p server -- --ignored`
+    #[test]
+    #[ignore]
+    fn s3_backend_behaves_alike() {
+        let env = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let store = S3BlobStore::new(core::config::S3Config {
+            endpoint: env("S3_TEST_ENDPOINT", "http://127.0.0.1:9000"),
+            bucket: "blob-store-test".to_string(),
+            region: "us-east-1".to_string(),
+            access_key: env("S3_ACCESS_KEY", "test"),
+            secret_key: env("S3_SECRET_KEY", "test"),
+            path_style: true,
+        }).unwrap();
+        block_on(async {
+            check_backend(&store).await;
+
+            let file = std::env::temp_dir().join(format!("s3-upload-{}", Uuid::new_v4()));
+            std::fs::write(&file, "from disk").unwrap();
+            assert_eq!(store.put_file("blobs/12/34/1234", &file).await.unwrap(), 9);
+            assert!(!file.exists());
+            assert_eq!(read(&store, "blobs/12/34/1234", Some(5..9)).await.as_deref(), Some("disk"));
+            for key in ["blobs/ef/01/ef01", "blobs/12/34/1234", "other/key"] {
+                store.delete(key).await.unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn identical_uploads_are_stored_once() {
+        block_on(async {
+            let upload_dir = std::env::temp_dir().join(format!("content-store-{}", Uuid::new_v4()));
+            let blobs = Arc::new(MemoryBlobStore::default());
+            let store = ContentStore::new(blobs.clone(), &upload_dir);
+            let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+            for _ in 0..2 {
+                let (_, temp) = store.create_temp().await.unwrap();
+                std::fs::write(&temp, "hello").unwrap();
+                assert_eq!(store.commit(&temp, sha256).await.unwrap(), ContentStore::blob_key(sha256));
+                assert!(!temp.exists());
+            }
+
+            assert_eq!(blobs.list("").await.unwrap().len(), 1);
+            assert_eq!(store.blobs().await.unwrap()[0].0, sha256);
+            std::fs::remove_dir_all(upload_dir).unwrap();
+        });
+    }
+}
@@ -0,0 +1,147 @@
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::time::SystemTime;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region, RequestChecksumCalculation, ResponseChecksumValidation};
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use aws_sdk_s3::primitives::{ByteStream as S3Body, DateTime};
+use aws_sdk_s3::Client;
+use core::config::S3Config;
+use futures::stream;
+use url::Url;
+use super::{collect_stream, BlobFuture, BlobInfo, BlobStore, ByteStream};
+
+/// Objets rangés dans un bucket S3 ou compatible (MinIO, Garage, Ceph…), par le SDK AWS
+pub struct S3BlobStore {
+    bucket: String,
+    client: Client,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> io::Result<Self> {
+        Url::parse(&config.endpoint)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid S3_ENDPOINT '{}': {}", config.endpoint, e)))?;
+        let credentials = Credentials::new(&config.access_key, &config.secret_key, None, None, "S3_ACCESS_KEY");
+        let client_config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(&config.endpoint)
+            .region(Region::new(config.region.clone()))
+            .credentials_provider(credentials)
+            .force_path_style(config.path_style)
+            // Les sommes de contrôle ajoutées d'office ne sont pas comprises de tous les stockages compatibles
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .response_checksum_validation(ResponseChecksumValidation::WhenRequired)
+            .build();
+        Ok(Self { bucket: config.bucket, client: Client::from_conf(client_config) })
+    }
+
+    async fn upload(&self, key: &str, length: u64, body: S3Body) -> io::Result<u64> {
+        self.client.put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_length(length as i64)
+            .body(body)
+            .send().await
+            .map_err(|e| s3_error("PUT", key, e))?;
+        Ok(length)
+    }
+}
+
+/// Réponse 404 du stockage : clé ou bucket absent
+fn is_not_found<E>(error: &SdkError<E, HttpResponse>) -> bool {
+    matches!(error, SdkError::ServiceError(e) if e.raw().status().as_u16() == 404)
+}
+
+fn s3_error<E: std::error::Error + 'static>(operation: &str, key: &str, error: SdkError<E, HttpResponse>) -> io::Error {
+    io::Error::other(format!("S3 {} '{}' failed: {}", operation, key, DisplayErrorContext(error)))
+}
+
+fn system_time(date: Option<&DateTime>) -> Option<SystemTime> {
+    date.and_then(|date| SystemTime::try_from(*date).ok())
+}
+
+impl BlobStore for S3BlobStore {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    /// Le flux est rassemblé en mémoire, S3 exigeant la taille avant l'envoi ;
+    /// les uploads passent par `put_file`, qui n'a pas cette limite
+    fn put<'a>(&'a self, key: &'a str, data: ByteStream) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let content = collect_stream(data).await?;
+            self.upload(key, content.len() as u64, S3Body::from(content)).await
+        })
+    }
+
+    fn put_file<'a>(&'a self, key: &'a str, path: &'a Path) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let length = tokio::fs::metadata(path).await?.len();
+            let body = S3Body::from_path(path).await.map_err(io::Error::other)?;
+            self.upload(key, length, body).await?;
+            tokio::fs::remove_file(path).await?;
+            Ok(length)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str, range: Option<Range<u64>>) -> BlobFuture<'a, Option<ByteStream>> {
+        Box::pin(async move {
+            let range = match range {
+                Some(range) if range.end <= range.start => return Ok(Some(Box::pin(stream::empty()) as ByteStream)),
+                Some(range) => Some(format!("bytes={}-{}", range.start, range.end - 1)),
+                None => None,
+            };
+            let object = match self.client.get_object().bucket(&self.bucket).key(key).set_range(range).send().await {
+                Ok(object) => object,
+                Err(e) if is_not_found(&e) => return Ok(None),
+                Err(e) => return Err(s3_error("GET", key, e)),
+            };
+            Ok(Some(Box::pin(stream::unfold(object.body, |mut body| async move {
+                body.next().await.map(|chunk| (chunk.map_err(io::Error::other), body))
+            })) as ByteStream))
+        })
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobInfo>> {
+        Box::pin(async move {
+            match self.client.head_object().bucket(&self.bucket).key(key).send().await {
+                Ok(object) => Ok(Some(BlobInfo {
+                    key: key.to_string(),
+                    size: object.content_length().unwrap_or(0).max(0) as u64,
+                    modified: system_time(object.last_modified()),
+                })),
+                Err(e) if is_not_found(&e) => Ok(None),
+                Err(e) => Err(s3_error("HEAD", key, e)),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            match self.client.delete_object().bucket(&self.bucket).key(key).send().await {
+                Err(e) if !is_not_found(&e) => Err(s3_error("DELETE", key, e)),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, Vec<BlobInfo>> {
+        Box::pin(async move {
+            let mut objects = Vec::new();
+            let mut pages = self.client.list_objects_v2().bucket(&self.bucket).prefix(prefix).into_paginator().send();
+            while let Some(page) = pages.next().await {
+                let page = page.map_err(|e| match is_not_found(&e) {
+                    true => io::Error::new(io::ErrorKind::NotFound, format!("S3 bucket '{}' not found", self.bucket)),
+                    false => s3_error("LIST", prefix, e),
+                })?;
+                objects.extend(page.contents().iter().filter_map(|object| Some(BlobInfo {
+                    key: object.key()?.to_string(),
+                    size: object.size().unwrap_or(0).max(0) as u64,
+                    modified: system_time(object.last_modified()),
+                })));
+            }
+            Ok(objects)
+        })
+    }
+}
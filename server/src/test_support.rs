@@ -0,0 +1,32 @@
+//! Outils communs aux tests du serveur
+
+use core::_database::DatabaseQuery;
+use core::repositories::migrations::{registry, Migrator};
+use core::repositories::{User, UserRepository};
+use std::collections::HashMap;
+
+/// Exécute un futur jusqu'au bout dans un runtime Tokio dédié
+///
+/// `#[tokio::test]` et `#[actix_web::test]` sont inutilisables dans cette crate : le code
+/// qu'ils génèrent passe par `::core`, masqué ici par la crate `core` du projet.
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+/// Base SQLite en mémoire au schéma complet, construit par les migrations du registre
+///
+/// Une seule connexion : chaque connexion à `sqlite::memory:` ouvre une base distincte.
+/// `create_tests` est écartée : elle génère un fichier source du dépôt, chemin relatif à la racine.
+pub async fn database() -> DatabaseQuery {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    let db = DatabaseQuery::new_sqlite(pool);
+    let migrations = registry().into_iter().filter(|migration| migration.name() != "create_tests").collect();
+    Migrator::with_migrations(db.clone(), migrations).up().await.unwrap();
+    db
+}
+
+/// Utilisateur `login` enregistré dans la base
+pub async fn user(db: &DatabaseQuery, login: &str) -> User {
+    let fields = HashMap::from([("login".to_string(), login.to_string())]);
+    UserRepository::new(db.clone()).create_user(&User::from_form_fields(&fields, &Vec::new())).await.unwrap()
+}